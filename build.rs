@@ -1,7 +1,346 @@
+use std::{env, fs, path::PathBuf};
+
 fn main() {
     linker_be_nice();
+    generate_rid_defaults();
+    generate_partition_layout();
     // make sure linkall.x is the last linker script (otherwise might cause problems with flip-link)
-    println!("cargo:rustc-link-arg=-Tlinkall.x");
+    println!("cargo:rustc-link-arg-bins=-Tlinkall.x");
+}
+
+/// Build-time identity and radio defaults.
+///
+/// Values come from `rid.toml` in the crate root (or the file named by `RID_CONFIG`),
+/// and each one can be overridden by an environment variable, e.g.
+/// `RID_UAS_ID=1581F5BKD21340001234 RID_CHANNEL=11 RID_COUNTRY=US RID_RATE=6 cargo run --release`.
+/// See `rid.toml.example` for the keys.
+struct RidDefaults {
+    uas_id: String,
+    mac_address: [u8; 6],
+    mac_policy: String,
+    channel: u8,
+    country: String,
+    tx_power_dbm: i8,
+    rate: String,
+    interval_ms: u32,
+    profile: String,
+}
+
+impl Default for RidDefaults {
+    fn default() -> Self {
+        Self {
+            uas_id: "1581F7FVC251A00CQ211".to_string(),
+            mac_address: [0x00, 0x80, 0x41, 0x13, 0x37, 0x42],
+            mac_policy: "factory".to_string(),
+            channel: 6,
+            country: "CN".to_string(),
+            tx_power_dbm: 20,
+            rate: "1".to_string(),
+            interval_ms: 500,
+            profile: "gb".to_string(),
+        }
+    }
+}
+
+/// Transmitter address policies as written in rid.toml, and the matching `MacPolicy` variants in src/mac.rs.
+const MAC_POLICIES: [(&str, &str); 4] =
+    [("fixed", "Fixed"), ("factory", "Factory"), ("uasid", "FromUasId"), ("random", "Random")];
+
+/// Beacon rates in Mbps as written in rid.toml, and the matching `PhyRate` variants in src/radio.rs.
+const RATES: [(&str, &str); 12] = [
+    ("1", "Mbps1"),
+    ("2", "Mbps2"),
+    ("5.5", "Mbps5_5"),
+    ("11", "Mbps11"),
+    ("6", "Mbps6"),
+    ("9", "Mbps9"),
+    ("12", "Mbps12"),
+    ("18", "Mbps18"),
+    ("24", "Mbps24"),
+    ("36", "Mbps36"),
+    ("48", "Mbps48"),
+    ("54", "Mbps54"),
+];
+
+/// Mirror of `RegulatoryDomain` in src/channel.rs, so the table can be shared.
+struct RegulatoryDomain {
+    country: [u8; 2],
+    last_channel: u8,
+    max_tx_power_dbm: i8,
+}
+
+/// The regulatory table from src/regulatory_table.rs, the same one the firmware
+/// checks the stored country, channel and TX power against at boot.
+const REGULATORY_DOMAINS: &[RegulatoryDomain] = &include!("src/regulatory_table.rs");
+
+fn fail(message: String) -> ! {
+    eprintln!();
+    eprintln!("❌ Invalid RID build configuration: {message}");
+    eprintln!();
+    std::process::exit(1);
+}
+
+fn generate_rid_defaults() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    println!("cargo:rerun-if-env-changed=RID_CONFIG");
+    let config_path = match env::var("RID_CONFIG") {
+        Ok(path) => manifest_dir.join(path),
+        Err(_) => manifest_dir.join("rid.toml"),
+    };
+    println!("cargo:rerun-if-changed={}", config_path.display());
+    println!("cargo:rerun-if-changed=src/regulatory_table.rs");
+
+    let mut defaults = RidDefaults::default();
+    let mut uas_id = None;
+    let mut mac_address = None;
+    let mut mac_policy = None;
+    let mut channel = None;
+    let mut country = None;
+    let mut tx_power_dbm = None;
+    let mut rate = None;
+    let mut interval_ms = None;
+    let mut profile = None;
+
+    if config_path.exists() {
+        let text = fs::read_to_string(&config_path)
+            .unwrap_or_else(|e| fail(format!("cannot read {}: {e}", config_path.display())));
+        let table: toml::Table = text
+            .parse()
+            .unwrap_or_else(|e| fail(format!("{}: {e}", config_path.display())));
+        for (key, value) in table {
+            let text = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Integer(i) => i.to_string(),
+                other => fail(format!("{key} = {other}: expected a string or an integer")),
+            };
+            match key.as_str() {
+                "uas_id" => uas_id = Some(text),
+                "mac_address" => mac_address = Some(text),
+                "mac_policy" => mac_policy = Some(text),
+                "channel" => channel = Some(text),
+                "country" => country = Some(text),
+                "tx_power_dbm" => tx_power_dbm = Some(text),
+                "rate" => rate = Some(text),
+                "interval_ms" => interval_ms = Some(text),
+                "profile" => profile = Some(text),
+                _ => fail(format!("unknown key `{key}` in {}", config_path.display())),
+            }
+        }
+    }
+
+    for (var, slot) in [
+        ("RID_UAS_ID", &mut uas_id),
+        ("RID_MAC_ADDRESS", &mut mac_address),
+        ("RID_MAC_POLICY", &mut mac_policy),
+        ("RID_CHANNEL", &mut channel),
+        ("RID_COUNTRY", &mut country),
+        ("RID_TX_POWER_DBM", &mut tx_power_dbm),
+        ("RID_RATE", &mut rate),
+        ("RID_INTERVAL_MS", &mut interval_ms),
+        ("RID_PROFILE", &mut profile),
+    ] {
+        println!("cargo:rerun-if-env-changed={var}");
+        if let Ok(value) = env::var(var) {
+            *slot = Some(value);
+        }
+    }
+
+    if let Some(value) = uas_id {
+        validate_cta2063(&value).unwrap_or_else(|e| fail(format!("uas_id `{value}`: {e}")));
+        defaults.uas_id = value;
+    }
+    // An explicit address means it should be used, unless a policy says otherwise.
+    if let Some(value) = mac_address {
+        defaults.mac_address = parse_mac(&value).unwrap_or_else(|e| fail(format!("mac_address `{value}`: {e}")));
+        defaults.mac_policy = "fixed".to_string();
+    }
+    if let Some(value) = mac_policy {
+        if !MAC_POLICIES.iter().any(|(name, _)| *name == value) {
+            fail(format!("mac_policy `{value}`: expected one of fixed, factory, uasid, random"));
+        }
+        defaults.mac_policy = value;
+    }
+    if let Some(value) = channel {
+        defaults.channel = match value.parse::<u8>() {
+            Ok(c @ 1..=14) => c,
+            _ => fail(format!("channel `{value}`: must be a 2.4 GHz channel between 1 and 14")),
+        };
+    }
+    if let Some(value) = country {
+        defaults.country = value;
+    }
+    let domain = REGULATORY_DOMAINS
+        .iter()
+        .find(|domain| domain.country == defaults.country.as_bytes())
+        .unwrap_or_else(|| {
+            let codes: Vec<&str> =
+                REGULATORY_DOMAINS.iter().map(|domain| std::str::from_utf8(&domain.country).unwrap()).collect();
+            fail(format!("country `{}`: expected one of {}", defaults.country, codes.join(", ")))
+        });
+    if defaults.channel > domain.last_channel {
+        fail(format!("channel {} is not allowed in {}", defaults.channel, defaults.country));
+    }
+    if let Some(value) = tx_power_dbm {
+        defaults.tx_power_dbm = match value.parse::<i8>() {
+            Ok(p @ 2..=20) => p,
+            _ => fail(format!("tx_power_dbm `{value}`: must be between 2 and 20")),
+        };
+    }
+    if defaults.tx_power_dbm > domain.max_tx_power_dbm {
+        fail(format!(
+            "tx_power_dbm {} exceeds the {} dBm allowed in {}",
+            defaults.tx_power_dbm, domain.max_tx_power_dbm, defaults.country
+        ));
+    }
+    if let Some(value) = rate {
+        if !RATES.iter().any(|(name, _)| *name == value) {
+            let names: Vec<&str> = RATES.iter().map(|(name, _)| *name).collect();
+            fail(format!("rate `{value}`: expected one of {} (Mbps)", names.join(", ")));
+        }
+        defaults.rate = value;
+    }
+    if let Some(value) = interval_ms {
+        defaults.interval_ms = match value.parse::<u32>() {
+            Ok(i @ 20..=60_000) => i,
+            _ => fail(format!("interval_ms `{value}`: must be between 20 and 60000")),
+        };
+    }
+    // The profile can also be picked with a cargo feature, e.g.
+    // `cargo build --features profile-astm`. rid.toml or RID_PROFILE must then agree.
+    let features: Vec<&str> = [
+        ("CARGO_FEATURE_PROFILE_GB", "gb"),
+        ("CARGO_FEATURE_PROFILE_ASTM", "astm"),
+        ("CARGO_FEATURE_PROFILE_ASD_STAN", "asd-stan"),
+        ("CARGO_FEATURE_PROFILE_FR", "fr"),
+    ]
+    .into_iter()
+    .filter(|(var, _)| env::var_os(var).is_some())
+    .map(|(_, name)| name)
+    .collect();
+    match (features.as_slice(), profile.as_deref()) {
+        ([], _) => {}
+        ([feature], None) => profile = Some(feature.to_string()),
+        ([feature], Some(value)) if value == *feature => {}
+        ([feature], Some(value)) => fail(format!("profile `{value}` conflicts with feature `profile-{feature}`")),
+        (_, _) => fail(format!("only one profile feature can be enabled, found {}", features.join(", "))),
+    }
+    if let Some(value) = profile {
+        if !matches!(value.as_str(), "gb" | "astm" | "asd-stan" | "fr") {
+            fail(format!("profile `{value}`: expected one of gb, astm, asd-stan, fr"));
+        }
+        defaults.profile = value;
+    }
+
+    let profile = match defaults.profile.as_str() {
+        "astm" => "Astm",
+        "asd-stan" => "AsdStan",
+        "fr" => "French",
+        _ => "Gb",
+    };
+    let rate = RATES.iter().find(|(name, _)| *name == defaults.rate).map(|(_, variant)| *variant).unwrap();
+    let mac_policy = MAC_POLICIES.iter().find(|(name, _)| *name == defaults.mac_policy).map(|(_, variant)| *variant).unwrap();
+    let mac = defaults.mac_address;
+    let generated = format!(
+        "/// 默认的 UAS ID（CTA-2063 序列号格式）\n\
+         pub const DEFAULT_UAS_ID: &str = {:?};\n\
+         /// 默认的发射地址/BSSID\n\
+         pub const DEFAULT_MAC_ADDRESS: [u8; 6] = [{:#04x}, {:#04x}, {:#04x}, {:#04x}, {:#04x}, {:#04x}];\n\
+         /// 默认的发射地址策略\n\
+         pub const DEFAULT_MAC_POLICY: MacPolicy = MacPolicy::{};\n\
+         /// 默认信道\n\
+         pub const DEFAULT_CHANNEL: u8 = {};\n\
+         /// 默认国家码\n\
+         pub const DEFAULT_COUNTRY: [u8; 2] = *b{:?};\n\
+         /// 默认发射功率（dBm）\n\
+         pub const DEFAULT_TX_POWER_DBM: i8 = {};\n\
+         /// 默认信标速率\n\
+         pub const DEFAULT_RATE: PhyRate = PhyRate::{};\n\
+         /// 默认信标发送间隔（毫秒）\n\
+         pub const DEFAULT_INTERVAL_MS: u32 = {};\n\
+         /// 默认遵循的标准\n\
+         pub const DEFAULT_PROFILE: StandardProfile = StandardProfile::{};\n",
+        defaults.uas_id, mac[0], mac[1], mac[2], mac[3], mac[4], mac[5], mac_policy, defaults.channel, defaults.country,
+        defaults.tx_power_dbm, rate, defaults.interval_ms, profile,
+    );
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("rid_defaults.rs"), generated).unwrap();
+}
+
+/// Offset and size of the `ridcfg` partition, taken from partitions.csv so the
+/// firmware and the flashed partition table cannot drift apart.
+fn generate_partition_layout() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let table_path = manifest_dir.join("partitions.csv");
+    println!("cargo:rerun-if-changed={}", table_path.display());
+    let text = fs::read_to_string(&table_path)
+        .unwrap_or_else(|e| fail(format!("cannot read {}: {e}", table_path.display())));
+    let row = text
+        .lines()
+        .map(|line| line.split(',').map(str::trim).collect::<Vec<&str>>())
+        .find(|fields| fields[0] == "ridcfg")
+        .unwrap_or_else(|| fail(format!("{} has no `ridcfg` partition", table_path.display())));
+    let number = |index: usize, name: &str| -> u32 {
+        let field = row.get(index).copied().unwrap_or("");
+        let parsed = match field.strip_prefix("0x").or_else(|| field.strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => field.parse(),
+        };
+        parsed.unwrap_or_else(|_| fail(format!("ridcfg {name} `{field}` in partitions.csv is not a number")))
+    };
+    let offset = number(3, "offset");
+    let size = number(4, "size");
+    if offset % 0x1000 != 0 {
+        fail(format!("ridcfg offset {offset:#x} in partitions.csv is not sector aligned"));
+    }
+    let generated = format!(
+        "/// partitions.csv 里 ridcfg 分区的偏移\n\
+         const PARTITION_OFFSET: u32 = {offset:#x};\n\
+         /// partitions.csv 里 ridcfg 分区的大小\n\
+         const PARTITION_SIZE: u32 = {size:#x};\n",
+    );
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("partition_layout.rs"), generated).unwrap();
+}
+
+/// ANSI/CTA-2063-A serial number: 4 character manufacturer code, 1 length
+/// character (1-9, A-F meaning 1-15) and a serial of that length. Only digits
+/// and upper case letters without `I` and `O` are allowed.
+fn validate_cta2063(serial: &str) -> Result<(), String> {
+    let valid_char = |c: char| c.is_ascii_digit() || (c.is_ascii_uppercase() && c != 'I' && c != 'O');
+    if let Some(bad) = serial.chars().find(|&c| !valid_char(c)) {
+        return Err(format!("character `{bad}` is not allowed (0-9, A-Z except I and O)"));
+    }
+    if serial.len() < 6 {
+        return Err("too short, expected manufacturer code, length code and serial".to_string());
+    }
+    let length_code = serial.as_bytes()[4] as char;
+    let expected = match length_code.to_digit(16) {
+        Some(n) if n > 0 => n as usize,
+        _ => return Err(format!("length code `{length_code}` must be 1-9 or A-F")),
+    };
+    let actual = serial.len() - 5;
+    if actual != expected {
+        return Err(format!("length code `{length_code}` says {expected} serial characters, found {actual}"));
+    }
+    Ok(())
+}
+
+fn parse_mac(text: &str) -> Result<[u8; 6], String> {
+    let parts: Vec<&str> = text.split([':', '-']).collect();
+    if parts.len() != 6 {
+        return Err("expected six hex octets like 00:80:41:13:37:42".to_string());
+    }
+    let mut mac = [0u8; 6];
+    for (octet, part) in mac.iter_mut().zip(&parts) {
+        *octet = u8::from_str_radix(part, 16).map_err(|_| format!("`{part}` is not a hex octet"))?;
+    }
+    if mac[0] & 0x01 != 0 {
+        return Err("multicast addresses cannot be used as transmitter address".to_string());
+    }
+    if mac == [0; 6] {
+        return Err("the all-zero address cannot be used as transmitter address".to_string());
+    }
+    Ok(mac)
 }
 
 fn linker_be_nice() {
@@ -46,7 +385,7 @@ fn linker_be_nice() {
     }
 
     println!(
-        "cargo:rustc-link-arg=--error-handling-script={}",
+        "cargo:rustc-link-arg-bins=--error-handling-script={}",
         std::env::current_exe().unwrap().display()
     );
 }
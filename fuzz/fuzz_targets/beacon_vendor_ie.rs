@@ -0,0 +1,11 @@
+#![no_main]
+
+use esp32c6_test::beacon::{extract_rid_payload, verify_rid_beacon};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(payload) = extract_rid_payload(data) {
+        assert!(payload.len() <= 255);
+    }
+    let _ = verify_rid_beacon(data);
+});
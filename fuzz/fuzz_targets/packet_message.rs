@@ -0,0 +1,11 @@
+#![no_main]
+
+use esp32c6_test::message::{message::Message, packet_message::PacketMessage};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Re-encoding whatever decoded must not panic either.
+    if let Ok(packet) = PacketMessage::decode(data) {
+        let _ = PacketMessage::decode(&packet.encode());
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use esp32c6_test::message::{
+    base_message::BaseMessage, french_message::FrenchMessage, message::Message, operator_id_message::OperatorIdMessage,
+    position_vector_message::PositionVectorMessage, self_id_message::SelfIdMessage, system_message::SystemMessage,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = BaseMessage::decode(data);
+    let _ = PositionVectorMessage::decode(data);
+    let _ = SystemMessage::decode(data);
+    let _ = SelfIdMessage::decode(data);
+    let _ = OperatorIdMessage::decode(data);
+    let _ = FrenchMessage::decode(data);
+});
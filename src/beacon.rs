@@ -0,0 +1,432 @@
+use core::marker::PhantomData;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ieee80211::{
+    common::{CapabilitiesInformation, FCFFlags, SequenceControl},
+    elements::{
+        rates::{EncodedRate, SupportedRatesElement},
+        DSSSParameterSetElement, ElementID, RawIEEE80211Element, ReadElements, SSIDElement, VendorSpecificElement,
+        WrappedIEEE80211Element,
+    },
+    mgmt_frame::{body::BeaconBody, BeaconFrame, ManagementFrameHeader},
+    scroll::{Endian, Pread, Pwrite},
+};
+
+use crate::message::french_message::{FrenchMessage, FRENCH_VENDOR_PREFIX};
+use crate::message::{message::{Message, MessageError}, packet_message::PacketMessage};
+
+/// RID 厂商元素前缀：ASTM F3411 的 OUI fa:0b:bc 加 OUI 类型 0x0d
+pub const RID_VENDOR_PREFIX: [u8; 4] = [0xfa, 0x0b, 0xbc, 0x0d];
+/// 国家码元素 ID
+pub const COUNTRY_ELEMENT_ID: u8 = 7;
+/// SSID 最长 32 字节
+pub const MAX_SSID_LENGTH: usize = 32;
+/// 一个元素的负载最长 255 字节
+const MAX_ELEMENT_LENGTH: usize = 255;
+/// 所有元素加起来的上限
+const MAX_ELEMENTS_LENGTH: usize = 512;
+
+/// 信标组装错误
+#[derive(Debug, PartialEq)]
+pub enum BeaconError {
+    SsidTooLong(usize),          // SSID 长度
+    InvalidRates(usize),         // 速率个数不在 1-8 之间
+    InvalidChannel(u8),          // 信道
+    VendorPayloadTooLong(usize), // 厂商元素负载长度
+    BufferTooSmall,              // 输出缓冲区不够
+}
+
+impl From<ieee80211::scroll::Error> for BeaconError {
+    fn from(_: ieee80211::scroll::Error) -> Self {
+        BeaconError::BufferTooSmall
+    }
+}
+
+/// 信标自检错误
+#[derive(Debug, PartialEq)]
+pub enum VerifyError {
+    NotBeacon,              // 按信标帧解析失败
+    MissingRidElement,      // 没有 RID 厂商元素
+    Message(MessageError),  // RID 消息包或法国 TLV 解码失败
+}
+
+impl From<MessageError> for VerifyError {
+    fn from(e: MessageError) -> Self {
+        VerifyError::Message(e)
+    }
+}
+
+/// SSID 的取法
+#[derive(Debug, Clone, PartialEq)]
+pub enum SsidPolicy {
+    Prefixed(&'static str), // 前缀加 UAS ID，大疆为 "RID-"
+    Fixed(String),          // 固定 SSID
+    Hidden,                 // 空 SSID
+}
+
+/// 国家码元素，只带一组子频段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CountryElement {
+    pub country: [u8; 2],   // ISO 3166 国家码，如 b"CN"
+    pub environment: u8,    // b' ' 室内外, b'O' 室外, b'I' 室内
+    pub first_channel: u8,  // 起始信道
+    pub channel_count: u8,  // 信道个数
+    pub max_tx_power: i8,   // 最大发射功率 dBm
+}
+
+impl CountryElement {
+    fn encode(&self) -> [u8; 6] {
+        [
+            self.country[0],
+            self.country[1],
+            self.environment,
+            self.first_channel,
+            self.channel_count,
+            self.max_tx_power as u8,
+        ]
+    }
+}
+
+/// 组装带 RID 厂商元素的 802.11 信标帧，主机和芯片上生成的字节完全相同
+#[derive(Debug, Clone, PartialEq)]
+pub struct RidBeaconBuilder {
+    ssid: SsidPolicy,
+    transmitter_address: [u8; 6],
+    bssid: [u8; 6],
+    channel: u8,
+    beacon_interval: u16,
+    timestamp_us: u64,
+    sequence_number: u16,
+    supported_rates: Vec<EncodedRate>,
+    country: Option<CountryElement>,
+    vendor_prefix: &'static [u8; 4],
+}
+
+/// 802.11 的时间单位 TU，1024 微秒
+pub const TU_US: u64 = 1024;
+/// 序列号只有 12 位
+pub const SEQUENCE_NUMBER_MODULO: u16 = 4096;
+
+/// 毫秒换算成信标间隔的 TU，四舍五入，至少 1 TU
+pub fn interval_to_tu(interval_ms: u32) -> u16 {
+    let tu = (interval_ms as u64 * 1000 + TU_US / 2) / TU_US;
+    tu.clamp(1, u16::MAX as u64) as u16
+}
+
+impl RidBeaconBuilder {
+    /// 发射地址同时作为 BSSID，默认仿大疆 SSID、1 Mbps、100 TU
+    pub fn new(mac_address: [u8; 6]) -> Self {
+        Self {
+            ssid: SsidPolicy::Prefixed("RID-"),
+            transmitter_address: mac_address,
+            bssid: mac_address,
+            channel: 6,
+            beacon_interval: 100,
+            timestamp_us: 0,
+            sequence_number: 0,
+            supported_rates: vec![EncodedRate::from_rate_in_kbps(1000, true)],
+            country: None,
+            vendor_prefix: &RID_VENDOR_PREFIX,
+        }
+    }
+
+    pub fn with_ssid_policy(mut self, ssid: SsidPolicy) -> Self {
+        self.ssid = ssid;
+        self
+    }
+
+    pub fn with_bssid(mut self, bssid: [u8; 6]) -> Self {
+        self.bssid = bssid;
+        self
+    }
+
+    pub fn with_transmitter_address(mut self, transmitter_address: [u8; 6]) -> Self {
+        self.transmitter_address = transmitter_address;
+        self
+    }
+
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// 信标间隔，单位 TU (1024 us)
+    pub fn with_beacon_interval(mut self, beacon_interval: u16) -> Self {
+        self.beacon_interval = beacon_interval;
+        self
+    }
+
+    /// 时间戳字段（TSF），单位微秒，每帧取发射时的时钟
+    pub fn with_timestamp(mut self, timestamp_us: u64) -> Self {
+        self.timestamp_us = timestamp_us;
+        self
+    }
+
+    /// 序列号，超出 12 位的部分丢掉
+    pub fn with_sequence_number(mut self, sequence_number: u16) -> Self {
+        self.sequence_number = sequence_number % SEQUENCE_NUMBER_MODULO;
+        self
+    }
+
+    pub fn with_supported_rates(mut self, supported_rates: &[EncodedRate]) -> Self {
+        self.supported_rates = supported_rates.to_vec();
+        self
+    }
+
+    pub fn with_country(mut self, country: Option<CountryElement>) -> Self {
+        self.country = country;
+        self
+    }
+
+    /// 厂商元素的 OUI 和类型，默认 ASTM RID，法国信标为 FRENCH_VENDOR_PREFIX
+    pub fn with_vendor_prefix(mut self, vendor_prefix: &'static [u8; 4]) -> Self {
+        self.vendor_prefix = vendor_prefix;
+        self
+    }
+
+    /// 按策略得到 SSID
+    pub fn ssid(&self, uas_id: &str) -> String {
+        match &self.ssid {
+            SsidPolicy::Prefixed(prefix) => {
+                let mut ssid = String::from(*prefix);
+                ssid.push_str(uas_id);
+                ssid
+            }
+            SsidPolicy::Fixed(ssid) => ssid.clone(),
+            SsidPolicy::Hidden => String::new(),
+        }
+    }
+
+    /// 写入信标帧，rid_payload 为厂商元素前缀之后的内容（计数器加消息包，或法国 TLV），返回写入长度
+    pub fn build(&self, uas_id: &str, rid_payload: &[u8], buffer: &mut [u8]) -> Result<usize, BeaconError> {
+        let ssid = self.ssid(uas_id);
+        if ssid.len() > MAX_SSID_LENGTH {
+            return Err(BeaconError::SsidTooLong(ssid.len()));
+        }
+        if !(1..=14).contains(&self.channel) {
+            return Err(BeaconError::InvalidChannel(self.channel));
+        }
+        if self.vendor_prefix.len() + rid_payload.len() > MAX_ELEMENT_LENGTH {
+            return Err(BeaconError::VendorPayloadTooLong(rid_payload.len()));
+        }
+        if !(1..=8).contains(&self.supported_rates.len()) {
+            return Err(BeaconError::InvalidRates(self.supported_rates.len()));
+        }
+        let rates = SupportedRatesElement::new(self.supported_rates.iter().copied())
+            .ok_or(BeaconError::InvalidRates(self.supported_rates.len()))?;
+
+        let mut elements = [0u8; MAX_ELEMENTS_LENGTH];
+        let mut offset = 0;
+        elements.gwrite(WrappedIEEE80211Element(SSIDElement::new(ssid.as_str()).ok_or(BeaconError::SsidTooLong(ssid.len()))?), &mut offset)?;
+        elements.gwrite(WrappedIEEE80211Element(rates), &mut offset)?;
+        elements.gwrite(WrappedIEEE80211Element(DSSSParameterSetElement { current_channel: self.channel }), &mut offset)?;
+        if let Some(country) = self.country {
+            let country = country.encode();
+            elements.gwrite_with(
+                RawIEEE80211Element { tlv_type: COUNTRY_ELEMENT_ID, slice: &country, _phantom: PhantomData },
+                &mut offset,
+                Endian::Little,
+            )?;
+        }
+        elements.gwrite(
+            WrappedIEEE80211Element(VendorSpecificElement::new_prefixed(self.vendor_prefix, rid_payload)),
+            &mut offset,
+        )?;
+
+        let length = buffer.pwrite(
+            BeaconFrame {
+                header: ManagementFrameHeader {
+                    fcf_flags: FCFFlags::new(),
+                    duration: 0,
+                    receiver_address: [0xff; 6].into(),
+                    transmitter_address: self.transmitter_address.into(),
+                    bssid: self.bssid.into(),
+                    sequence_control: SequenceControl::new().with_sequence_number(self.sequence_number),
+                    ..Default::default()
+                },
+                body: BeaconBody {
+                    timestamp: self.timestamp_us,
+                    beacon_interval: self.beacon_interval,
+                    capabilities_info: CapabilitiesInformation::new().with_is_ess(true),
+                    elements: ReadElements { bytes: &elements[..offset] },
+                    _phantom: PhantomData,
+                },
+            },
+            0,
+        )?;
+        Ok(length)
+    }
+}
+
+/// 从信标帧（不带 FCS）中取出指定厂商元素前缀之后的内容
+pub fn extract_vendor_payload<'a>(frame: &'a [u8], prefix: &'static [u8]) -> Option<&'a [u8]> {
+    let beacon = frame.pread_with::<BeaconFrame>(0, false).ok()?;
+    let element = beacon.body.elements.get_first_element_raw(ElementID::VendorSpecific { prefix })?;
+    element.slice.get(prefix.len()..)
+}
+
+/// 从信标帧（不带 FCS）中取出 RID 厂商元素前缀之后的内容
+pub fn extract_rid_payload(frame: &[u8]) -> Option<&[u8]> {
+    extract_vendor_payload(frame, &RID_VENDOR_PREFIX)
+}
+
+/// 自检：按接收端的方式解析信标帧并解码其中的 RID 消息包
+pub fn verify_rid_beacon(frame: &[u8]) -> Result<PacketMessage, VerifyError> {
+    frame.pread_with::<BeaconFrame>(0, false).map_err(|_| VerifyError::NotBeacon)?;
+    let payload = extract_rid_payload(frame).ok_or(VerifyError::MissingRidElement)?;
+    Ok(PacketMessage::decode(payload)?)
+}
+
+/// 自检：解析法国信标并解码其中的 TLV
+pub fn verify_french_beacon(frame: &[u8]) -> Result<FrenchMessage, VerifyError> {
+    frame.pread_with::<BeaconFrame>(0, false).map_err(|_| VerifyError::NotBeacon)?;
+    let payload = extract_vendor_payload(frame, &FRENCH_VENDOR_PREFIX).ok_or(VerifyError::MissingRidElement)?;
+    Ok(FrenchMessage::decode(payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TransmitterConfig;
+
+    const MAC: [u8; 6] = [0x00, 0x80, 0x41, 0x13, 0x37, 0x42];
+
+    #[test]
+    fn golden_beacon() {
+        let builder = RidBeaconBuilder::new(MAC).with_beacon_interval(1000);
+        let mut buffer = [0u8; 300];
+        let length = builder.build("ABC", &[0x01, 0xf1, 0x19, 0x00], &mut buffer).unwrap();
+
+        let expected: &[u8] = &[
+            // 帧控制、持续时间
+            0x80, 0x00, 0x00, 0x00,
+            // 接收地址、发射地址、BSSID
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0x00, 0x80, 0x41, 0x13, 0x37, 0x42,
+            0x00, 0x80, 0x41, 0x13, 0x37, 0x42,
+            // 序列控制
+            0x00, 0x00,
+            // 时间戳
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // 信标间隔 1000 TU、能力信息 ESS
+            0xe8, 0x03, 0x01, 0x00,
+            // SSID "RID-ABC"
+            0x00, 0x07, b'R', b'I', b'D', b'-', b'A', b'B', b'C',
+            // 支持速率 1 Mbps (B)
+            0x01, 0x01, 0x82,
+            // DSSS 信道 6
+            0x03, 0x01, 0x06,
+            // RID 厂商元素
+            0xdd, 0x08, 0xfa, 0x0b, 0xbc, 0x0d, 0x01, 0xf1, 0x19, 0x00,
+        ];
+        assert_eq!(&buffer[..length], expected);
+    }
+
+    #[test]
+    fn timing_fields() {
+        let builder = RidBeaconBuilder::new(MAC)
+            .with_timestamp(0x0102_0304_0506_0708)
+            .with_sequence_number(SEQUENCE_NUMBER_MODULO + 3)
+            .with_beacon_interval(interval_to_tu(1000));
+        let mut buffer = [0u8; 300];
+        let length = builder.build("ABC", &[0x01], &mut buffer).unwrap();
+        // 序列控制低 4 位是分片号，序列号只留 12 位
+        assert_eq!(buffer[22..24], [0x30, 0x00]);
+        assert_eq!(buffer[24..32], [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(u16::from_le_bytes([buffer[32], buffer[33]]), 977);
+        assert!(verify_rid_beacon(&buffer[..length]).is_err());
+
+        assert_eq!((interval_to_tu(100), interval_to_tu(1000), interval_to_tu(0)), (98, 977, 1));
+        assert_eq!(interval_to_tu(u32::MAX), u16::MAX);
+    }
+
+    #[test]
+    fn optional_elements() {
+        let builder = RidBeaconBuilder::new(MAC)
+            .with_ssid_policy(SsidPolicy::Hidden)
+            .with_channel(11)
+            .with_country(Some(CountryElement {
+                country: *b"CN",
+                environment: b' ',
+                first_channel: 1,
+                channel_count: 13,
+                max_tx_power: 20,
+            }));
+        let mut buffer = [0u8; 300];
+        let length = builder.build("ABC", &[0x01], &mut buffer).unwrap();
+        assert_eq!(
+            &buffer[36..length],
+            &[
+                0x00, 0x00, // 空 SSID
+                0x01, 0x01, 0x82, // 支持速率
+                0x03, 0x01, 0x0b, // DSSS 信道 11
+                0x07, 0x06, b'C', b'N', b' ', 0x01, 0x0d, 0x14, // 国家码
+                0xdd, 0x05, 0xfa, 0x0b, 0xbc, 0x0d, 0x01, // RID 厂商元素
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_instead_of_panics() {
+        let mut buffer = [0u8; 300];
+        let long = RidBeaconBuilder::new(MAC).with_ssid_policy(SsidPolicy::Fixed(String::from("0123456789abcdef0123456789abcdef!")));
+        assert_eq!(long.build("ABC", &[], &mut buffer), Err(BeaconError::SsidTooLong(33)));
+        let no_rates = RidBeaconBuilder::new(MAC).with_supported_rates(&[]);
+        assert_eq!(no_rates.build("ABC", &[], &mut buffer), Err(BeaconError::InvalidRates(0)));
+        let builder = RidBeaconBuilder::new(MAC);
+        assert_eq!(builder.build("ABC", &[0; 252], &mut buffer), Err(BeaconError::VendorPayloadTooLong(252)));
+        assert_eq!(builder.build("ABC", &[0; 40], &mut buffer[..50]), Err(BeaconError::BufferTooSmall));
+    }
+
+    #[test]
+    fn round_trip_through_beacon_parser() {
+        let mut config = TransmitterConfig::default();
+        config.set_uas_id("1581F7FVC251A00CQ211").unwrap();
+        config.set_position(301234567, -1201234567).unwrap();
+        let mut package = PacketMessage::from_config(&config);
+        package.system_message.timestamp = 0x1234_5678;
+        let payload = package.encode();
+
+        let mut buffer = [0u8; 300];
+        let length = RidBeaconBuilder::new(MAC).build(&config.uas_id, &payload, &mut buffer).unwrap();
+        assert_eq!(extract_rid_payload(&buffer[..length]), Some(payload.as_slice()));
+
+        let decoded = verify_rid_beacon(&buffer[..length]).unwrap();
+        assert_eq!(decoded.base_message, package.base_message);
+        assert_eq!(decoded.position_message, package.position_message);
+        assert_eq!(decoded.system_message, package.system_message);
+        assert_eq!(decoded.message_counter(), payload[0]);
+    }
+
+    #[test]
+    fn french_beacon_round_trip() {
+        let mut config = TransmitterConfig::default();
+        config.set_position(481527800, 26122200).unwrap();
+        let french = FrenchMessage::from_packet(&PacketMessage::from_config(&config));
+        let payload = french.encode();
+
+        let mut buffer = [0u8; 300];
+        let length = RidBeaconBuilder::new(MAC)
+            .with_vendor_prefix(&FRENCH_VENDOR_PREFIX)
+            .build(&config.uas_id, &payload, &mut buffer)
+            .unwrap();
+        assert_eq!(extract_vendor_payload(&buffer[..length], &FRENCH_VENDOR_PREFIX), Some(payload.as_slice()));
+        assert_eq!(extract_rid_payload(&buffer[..length]), None);
+        assert_eq!(verify_french_beacon(&buffer[..length]), Ok(french));
+        assert_eq!(verify_rid_beacon(&buffer[..length]).unwrap_err(), VerifyError::MissingRidElement);
+    }
+
+    #[test]
+    fn verify_reports_missing_element() {
+        let mut buffer = [0u8; 300];
+        let length = RidBeaconBuilder::new(MAC).build("ABC", &[0x01, 0xf1, 0x19, 0x00], &mut buffer).unwrap();
+        assert_eq!(
+            verify_rid_beacon(&buffer[..length]),
+            Err(VerifyError::Message(MessageError::MissingMessage(0)))
+        );
+        assert_eq!(verify_rid_beacon(&buffer[..20]), Err(VerifyError::NotBeacon));
+    }
+}
@@ -0,0 +1,43 @@
+use embedded_io::Write;
+use esp32c6_test::ble::HciTransport;
+use esp_hal::time::{Duration, Instant};
+use esp_wifi::ble::controller::{BleConnector, BleConnectorError};
+
+/// HCI 事件包的 H4 标识与命令完成、命令状态事件码
+const HCI_EVENT_PACKET: u8 = 0x04;
+const EVENT_COMMAND_COMPLETE: u8 = 0x0E;
+const EVENT_COMMAND_STATUS: u8 = 0x0F;
+/// 等待控制器应答的上限
+const COMMAND_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// 片上蓝牙控制器的 HCI 通道
+pub struct BleRadio<'d> {
+    connector: BleConnector<'d>,
+}
+
+impl<'d> BleRadio<'d> {
+    pub fn new(connector: BleConnector<'d>) -> Self {
+        Self { connector }
+    }
+}
+
+impl HciTransport for BleRadio<'_> {
+    type Error = BleConnectorError;
+
+    // 发出命令后等命令完成或命令状态事件，控制器一次只接受有限个命令
+    fn send(&mut self, packet: &[u8]) -> Result<(), Self::Error> {
+        self.connector.write_all(packet)?;
+        let start = Instant::now();
+        let mut event = [0u8; 260];
+        while start.elapsed() < COMMAND_TIMEOUT {
+            let length = self.connector.next(&mut event)?;
+            if length >= 2
+                && event[0] == HCI_EVENT_PACKET
+                && (event[1] == EVENT_COMMAND_COMPLETE || event[1] == EVENT_COMMAND_STATUS)
+            {
+                return Ok(());
+            }
+        }
+        Err(BleConnectorError::Unknown)
+    }
+}
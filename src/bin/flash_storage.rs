@@ -0,0 +1,42 @@
+use embedded_storage::{ReadStorage, Storage};
+use esp32c6_test::storage::{ConfigFlash, RECORD_CAPACITY};
+use esp_storage::{FlashStorage, FlashStorageError};
+
+include!(concat!(env!("OUT_DIR"), "/partition_layout.rs"));
+
+// 记录必须放得进分区
+const _: () = assert!(RECORD_CAPACITY as u32 <= PARTITION_SIZE);
+
+/// 片上 flash 中的配置分区
+pub struct ConfigPartition {
+    storage: FlashStorage,
+}
+
+impl Default for ConfigPartition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigPartition {
+    pub fn new() -> Self {
+        Self { storage: FlashStorage::new() }
+    }
+}
+
+impl ConfigFlash for ConfigPartition {
+    type Error = FlashStorageError;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.storage.read(PARTITION_OFFSET + offset, bytes)
+    }
+
+    fn erase(&mut self) -> Result<(), Self::Error> {
+        // Storage::write 会按扇区读-擦-写，记录区写 0xFF 即为擦除
+        self.storage.write(PARTITION_OFFSET, &[0xff; RECORD_CAPACITY])
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.storage.write(PARTITION_OFFSET + offset, bytes)
+    }
+}
@@ -6,189 +6,888 @@
     holding buffers for the duration of a data transfer."
 )]
 
-pub mod message;
-use core::marker::PhantomData;
+pub mod ble_radio;
+pub mod flash_storage;
+pub mod wifi_radio;
 
 use esp_hal::clock::CpuClock;
-use esp_hal::main;
+use esp_hal::ram;
 use esp_hal::delay::Delay;
-use log::{info, error};
+use log::{info, warn, error};
 use esp_alloc as _;
+use esp_backtrace as _;
 extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
 
+use embassy_executor::Spawner;
+use embassy_futures::select::{select, select3, select4, Either, Either3, Either4};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::signal::Signal;
+use embassy_sync::watch::Watch;
+use embassy_time::{Instant, Timer};
+use embedded_io_async::Read;
+use esp_hal::timer::systimer::SystemTimer;
 use esp_hal::timer::timg::TimerGroup;
+use esp_hal::efuse::Efuse;
 use esp_hal::rng::Rng;
-use alloc::vec::Vec;
-use alloc::format;
-use core::convert::TryInto;
+use esp_hal::rtc_cntl::{Rwdt, RwdtStage};
+use esp_hal::time::Duration;
+use esp_hal::uart::{self, UartRx};
+use esp_hal::usb_serial_jtag::UsbSerialJtag;
+use esp_hal::Async;
+use esp_println::{print, println};
+use esp_wifi::ble::controller::BleConnector;
+use esp_wifi::wifi::WifiController;
+use esp_wifi::EspWifiController;
+use static_cell::StaticCell;
+use core::cell::RefCell;
+use core::fmt::{Debug, Write};
 
-
-use ieee80211::{
-    common::{CapabilitiesInformation, FCFFlags},
-    element_chain,
-    elements::{DSSSParameterSetElement, RawIEEE80211Element, SSIDElement},
-    mgmt_frame::{BeaconFrame, body::BeaconBody, ManagementFrameHeader},
-    scroll::Pwrite,
-    supported_rates,
+use esp32c6_test::ble::{BleAdvertiser, HciAdvertiser, MAX_LEGACY_SLOT_MS};
+use esp32c6_test::channel::{ChannelHopper, ChannelPlan};
+use esp32c6_test::config::TransmitterConfig;
+use esp32c6_test::console::{parse_command, Command, LineBuffer};
+use esp32c6_test::gnss::{mark_fix_lost, GnssFix, GnssReceiver, NmeaError};
+use esp32c6_test::pcap;
+use esp32c6_test::radio::PhyRate;
+use esp32c6_test::recovery::{retry, Backoff, BringUpError, BringUpStep, ResetCause, ResetRecord};
+use esp32c6_test::schedule::{ComplianceReport, MessageKind, MessageRule, LOCATION_MAX_AGE_MS};
+use esp32c6_test::storage::{load_config, save_config, StorageError};
+use esp32c6_test::swarm::Swarm;
+use esp32c6_test::transport::{
+    BleExtendedTransport, BleLegacyTransport, Broadcast, NanTransport, RidTransport, TransportKind,
+    TransportScheduler, TransportStats, WifiBeaconTransport,
 };
 
-use crate::message::{message::Message, packet_message::PacketMessage};
+use crate::ble_radio::BleRadio;
+use crate::flash_storage::ConfigPartition;
+use crate::wifi_radio::WifiRadio;
 
 
-#[panic_handler]
-fn panic(_: &core::panic::PanicInfo) -> ! {
-    loop {}
-}
+use esp32c6_test::message::{packet_message::PacketMessage, self_id_message::SelfIdMessage};
+
 
 // This creates a default app-descriptor required by the esp-idf bootloader.
 // For more information see: <https://docs.espressif.com/projects/esp-idf/en/stable/esp32/api-reference/system/app_image_format.html#application-description>
 esp_bootloader_esp_idf::esp_app_desc!();
 
-const MAC_ADDRESS: [u8; 6] = [0x00, 0x80, 0x41, 0x13, 0x37, 0x42];
+/// 蓝牙广播间隔，控制器在一个时隙内重复发送同一条消息
+const BLE_ADVERTISING_INTERVAL_MS: u32 = 100;
+/// 蓝牙轮播时隙，不超过 MAX_LEGACY_SLOT_MS
+const BLE_SLOT_MS: u32 = MAX_LEGACY_SLOT_MS / 2;
+/// Coded PHY 扩展广播间隔，数据随 Wi-Fi 信标一起更新
+const BLE_EXTENDED_INTERVAL_MS: u32 = 200;
+
+/// 看门狗超时，由看门狗任务定时喂狗，启动阶段在重试等待时喂狗
+const WATCHDOG_TIMEOUT_MS: u64 = 8000;
+/// 看门狗任务的检查间隔，所有发送任务都按时醒来过才喂狗
+const WATCHDOG_FEED_MS: u64 = 1000;
+/// 发送任务空闲时也至少这么久醒一次，报告自己还在运行
+const HEARTBEAT_MS: u64 = 1000;
+/// 发送任务比约定的醒来时间晚这么久算卡住
+const HEARTBEAT_GRACE_MS: u64 = 2000;
+/// 启动步骤失败后的重试：100 毫秒起每次翻倍，共试 5 次，再不行就重启
+const BRING_UP_BACKOFF: Backoff = Backoff { initial_ms: 100, max_ms: 2000, max_attempts: 5 };
+
+/// GNSS 接收机的 NMEA 输出接 UART1，RX 为 GPIO5
+const GNSS_BAUD_RATE: u32 = 9600;
+/// 定位超过这么久没有更新就作废；1 Hz 的接收机偶尔晚到一次不算丢失
+const GNSS_FIX_MAX_AGE_MS: u64 = 2 * LOCATION_MAX_AGE_MS as u64;
+/// 状态报告任务定期在日志里输出各发送方式的发送计数
+const STATUS_LOG_INTERVAL_MS: u64 = 60_000;
+
+/// 发射状态的订阅者上限：每个发送任务和机群任务各一个
+const STATE_RECEIVERS: usize = 6;
+
+/// 状态任务发布的发射状态，配置、信道或定位变化时更新
+#[derive(Clone)]
+struct TransmitState {
+    config: TransmitterConfig,
+    channel: u8,                      // 当前信道，跳信道时变化
+    fix: Option<GnssFix>,             // 最新定位，没有 GNSS 时用配置里的位置
+    fix_ms: u64,                      // 收到最新定位的时间
+    fix_lost: bool,                   // 接收机报告失去定位或定位过期，位置退回配置并标为故障
+}
+
+/// 控制台要求输出的报告
+#[derive(Clone, Copy)]
+enum ReportRequest {
+    Status,
+    Report,
+}
+
+/// 一种发送方式的统计和截至最近一次发送的合规报告，由发送任务更新
+struct TransportStatus {
+    kind: TransportKind,
+    stats: TransportStats,
+    report: Option<ComplianceReport>,
+    wake_by_ms: u64, // 心跳：任务约定的下次醒来时间，看门狗据此判断是否卡住
+}
+
+/// 控制台收到的命令，按顺序交给状态任务
+static COMMANDS: Channel<CriticalSectionRawMutex, Command, 4> = Channel::new();
+/// GNSS 任务的最新定位，None 为接收机报告失去定位；状态任务来不及取时只保留最新的
+static FIXES: Signal<CriticalSectionRawMutex, Option<GnssFix>> = Signal::new();
+/// 发射状态，发送任务和机群任务订阅，状态报告任务读取
+static STATE: Watch<CriticalSectionRawMutex, TransmitState, STATE_RECEIVERS> = Watch::new();
+/// 机群请求的架数，0 为关闭
+static SWARM_REQUESTS: Signal<CriticalSectionRawMutex, u8> = Signal::new();
+/// 正在模拟的架数，不为 0 时本机身份暂停发送
+static SWARM_SIZE: Watch<CriticalSectionRawMutex, usize, STATE_RECEIVERS> = Watch::new();
+/// 控制台的 status、report 请求
+static REPORT_REQUESTS: Signal<CriticalSectionRawMutex, ReportRequest> = Signal::new();
+/// 各发送方式的统计，按启动顺序
+static TRANSPORT_STATUS: Mutex<CriticalSectionRawMutex, RefCell<Vec<TransportStatus>>> =
+    Mutex::new(RefCell::new(Vec::new()));
+
+// 跨复位保留的复位记录，上电后内容随机，解码时校验
+#[ram(rtc_fast, persistent)]
+static mut RESET_RECORD: [u32; 2] = [0; 2];
+
+fn load_reset_record() -> Option<ResetRecord> {
+    ResetRecord::decode(unsafe { core::ptr::addr_of!(RESET_RECORD).read_volatile() })
+}
+
+fn store_reset_record(record: ResetRecord) {
+    unsafe { core::ptr::addr_of_mut!(RESET_RECORD).write_volatile(record.encode()) }
+}
+
+// esp-backtrace 打印 panic 信息之前调用，记下复位原因
+#[no_mangle]
+fn custom_pre_backtrace() {
+    let mut record = load_reset_record().unwrap_or_else(ResetRecord::running);
+    record.cause = ResetCause::Panic;
+    store_reset_record(record);
+}
+
+// esp-backtrace 打印完后调用，重启而不是停住
+#[no_mangle]
+fn custom_halt() -> ! {
+    esp_hal::system::software_reset()
+}
+
+// 启动步骤失败：记下失败的步骤后重启，下次启动按连续失败次数退避
+fn restart_after<E: Debug>(error: BringUpError<E>) -> ! {
+    error!("{} failed after {} attempts: {:?}, restarting", error.step.name(), error.attempts, error.error);
+    let mut record = load_reset_record().unwrap_or_else(ResetRecord::running);
+    record.cause = ResetCause::BringUpFailed;
+    record.step = Some(error.step);
+    store_reset_record(record);
+    esp_hal::system::software_reset()
+}
+
+// 等待 delay_ms 毫秒，期间喂狗
+fn wait_feeding(watchdog: &mut Rwdt, delay: &Delay, delay_ms: u32) {
+    for _ in 0..delay_ms.div_ceil(100) {
+        watchdog.feed();
+        delay.delay_millis(100);
+    }
+    watchdog.feed();
+}
+
+// 按退避重试一个启动步骤，wait 负责等待，用完次数后重启；只在启动执行器之前用
+fn bring_up<T, E: Debug>(step: BringUpStep, mut wait: impl FnMut(u32), attempt: impl FnMut() -> Result<T, E>) -> T {
+    let result = retry(step, BRING_UP_BACKOFF, attempt, |e, delay_ms| {
+        warn!("{} failed: {:?}, retrying in {} ms", step.name(), e, delay_ms);
+        wait(delay_ms);
+    });
+    match result {
+        Ok(value) => {
+            info!("{} ok", step.name());
+            value
+        }
+        Err(e) => restart_after(e),
+    }
+}
+
+// bring_up 在任务里的版本：重试之间用定时器等待，不阻塞执行器
+async fn bring_up_async<T, E: Debug>(step: BringUpStep, mut attempt: impl FnMut() -> Result<T, E>) -> T {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match attempt() {
+            Ok(value) => {
+                info!("{} ok", step.name());
+                return value;
+            }
+            Err(error) if attempts >= BRING_UP_BACKOFF.max_attempts => {
+                restart_after(BringUpError { step, attempts, error })
+            }
+            Err(error) => {
+                let delay_ms = BRING_UP_BACKOFF.delay_ms(attempts - 1);
+                warn!("{} failed: {:?}, retrying in {} ms", step.name(), error, delay_ms);
+                Timer::after_millis(delay_ms as u64).await;
+            }
+        }
+    }
+}
+
+// 设置发射信道，返回 esp-idf 错误码
+fn set_channel(channel: u8) -> i32 {
+    unsafe { esp_wifi_sys::include::esp_wifi_set_channel(channel, 0) }
+}
+
+// 设置国家码，不跟随 802.11d，返回 esp-idf 错误码
+fn set_country(country: [u8; 2]) -> i32 {
+    let code = [country[0], country[1], 0];
+    unsafe { esp_wifi_sys::include::esp_wifi_set_country_code(code.as_ptr() as *const _, false) }
+}
+
+// 设置最大发射功率，esp-idf 的单位是 0.25 dBm，要在 Wi-Fi 启动之后调用
+fn set_tx_power(tx_power_dbm: i8) -> i32 {
+    unsafe { esp_wifi_sys::include::esp_wifi_set_max_tx_power(tx_power_dbm * 4) }
+}
+
+// 设置 STA 接口发原始帧的速率，要在 Wi-Fi 启动之前调用
+fn set_tx_rate(rate: PhyRate) -> i32 {
+    use esp_wifi_sys::include::*;
+    let phy_rate = match rate {
+        PhyRate::Mbps1 => wifi_phy_rate_t_WIFI_PHY_RATE_1M_L,
+        PhyRate::Mbps2 => wifi_phy_rate_t_WIFI_PHY_RATE_2M_L,
+        PhyRate::Mbps5_5 => wifi_phy_rate_t_WIFI_PHY_RATE_5M_L,
+        PhyRate::Mbps11 => wifi_phy_rate_t_WIFI_PHY_RATE_11M_L,
+        PhyRate::Mbps6 => wifi_phy_rate_t_WIFI_PHY_RATE_6M,
+        PhyRate::Mbps9 => wifi_phy_rate_t_WIFI_PHY_RATE_9M,
+        PhyRate::Mbps12 => wifi_phy_rate_t_WIFI_PHY_RATE_12M,
+        PhyRate::Mbps18 => wifi_phy_rate_t_WIFI_PHY_RATE_18M,
+        PhyRate::Mbps24 => wifi_phy_rate_t_WIFI_PHY_RATE_24M,
+        PhyRate::Mbps36 => wifi_phy_rate_t_WIFI_PHY_RATE_36M,
+        PhyRate::Mbps48 => wifi_phy_rate_t_WIFI_PHY_RATE_48M,
+        PhyRate::Mbps54 => wifi_phy_rate_t_WIFI_PHY_RATE_54M,
+    };
+    unsafe { esp_wifi_config_80211_tx_rate(wifi_interface_t_WIFI_IF_STA, phy_rate) }
+}
+
+fn now_ms() -> u64 {
+    Instant::now().as_millis()
+}
+
+// 睡到 deadline_ms，没有截止时间就一直等
+async fn sleep_until(deadline_ms: Option<u64>) {
+    match deadline_ms {
+        Some(deadline_ms) => Timer::at(Instant::from_millis(deadline_ms)).await,
+        None => core::future::pending().await,
+    }
+}
+
+// 按当前状态组整包，有 GNSS 定位时位置和时间取自定位，丢失定位时退回配置里的位置并标为故障
+fn build_package(state: &TransmitState) -> PacketMessage {
+    let mut package = PacketMessage::from_config(&state.config);
+    // 没有授时来源时先用开机以来的秒数
+    package.system_message.timestamp = (now_ms() / 1000) as u32;
+    if let Some(fix) = &state.fix {
+        fix.apply(&mut package, state.config.profile);
+    } else if state.fix_lost {
+        mark_fix_lost(&mut package);
+    }
+    package
+}
+
+fn print_status(state: &TransmitState, swarm_len: usize) {
+    let config = &state.config;
+    let mac = config.mac_address;
+    println!("profile  {}", config.profile.name());
+    println!("uasid    {}", config.uas_id);
+    println!("selfid   {}", config.self_id);
+    println!("operator {}", config.operator_id);
+    println!("class    {} {}", config.ua_category, config.ua_class);
+    println!(
+        "mac      {:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X} ({})",
+        mac[0], mac[1], mac[2], mac[3], mac[4], mac[5], config.mac_policy.name()
+    );
+    println!("channel  {}", config.channel);
+    println!("country  {}", core::str::from_utf8(&config.country).unwrap_or("??"));
+    match config.regulatory_domain() {
+        Ok(domain) => println!("power    {} dBm (max {} dBm)", config.tx_power_dbm, domain.max_tx_power_dbm),
+        Err(_) => println!("power    {} dBm (country not in regulatory table)", config.tx_power_dbm),
+    }
+    println!("rate     {} Mbps", config.rate.name());
+    if config.channel_plan != ChannelPlan::Fixed {
+        println!("hop      {:?} dwell {} ms, on {}", planned_channels(config), config.dwell_ms, state.channel);
+    }
+    println!("interval {} ms", config.interval_ms);
+    println!("pos      {} {}", config.latitude, config.longitude);
+    match &state.fix {
+        Some(fix) => println!("gnss     {} {} ({} ms ago)", fix.latitude, fix.longitude, now_ms() - state.fix_ms),
+        None if state.fix_lost => println!("gnss     fix lost, broadcasting configured position"),
+        None => println!("gnss     no fix"),
+    }
+    println!("state    {}", if config.transmitting { "transmitting" } else { "stopped" });
+    if swarm_len > 0 {
+        println!("swarm    {} drones", swarm_len);
+    }
+    if let Err(e) = config.profile.check(config) {
+        println!("warning  {} requirements not met: {:?}", config.profile.name(), e);
+    }
+    for (kind, stats, _) in transport_rows() {
+        println!("{:<12} sent {} failed {}", kind.name(), stats.sent, stats.failed);
+    }
+}
+
+// 复制出各发送方式的统计和报告；串口输出很慢，不能在关中断的锁里打印
+fn transport_rows() -> Vec<(TransportKind, TransportStats, Option<ComplianceReport>)> {
+    TRANSPORT_STATUS.lock(|board| {
+        board.borrow().iter().map(|status| (status.kind, status.stats, status.report.clone())).collect()
+    })
+}
+
+fn print_help() {
+    println!("commands:");
+    println!("  set uasid <id>");
+    println!("  set selfid <text>");
+    println!("  set mac <address>|factory|uasid|random");
+    println!("  set channel <1-14>");
+    println!("  set country <code>");
+    println!("  set hop <1,6,11>|all|off");
+    println!("  set dwell <ms>");
+    println!("  set power <dBm>");
+    println!("  set rate 1|2|5.5|11|6|9|12|18|24|36|48|54");
+    println!("  set interval <ms>");
+    println!("  set pos <lat> <lon>");
+    println!("  set profile gb|astm|asd-stan|fr");
+    println!("  set operator <id>");
+    println!("  set class <category> <class>");
+    println!("  pcap on|off");
+    println!("  swarm <1-16>|off (uses the config at the time of the command)");
+    println!("  status | report | start | stop | save | help");
+}
+
+// 执行一条控制台命令；配置修改由状态任务随后发布，报告和机群交给各自的任务
+fn handle_command(command: Command, config: &mut TransmitterConfig, flash: &mut ConfigPartition, wifi: &RefCell<WifiRadio>) {
+    match command.apply(config) {
+        Ok(_) => match command {
+            Command::Status => REPORT_REQUESTS.signal(ReportRequest::Status),
+            Command::Report => REPORT_REQUESTS.signal(ReportRequest::Report),
+            Command::Help => print_help(),
+            Command::Save => match save_config(flash, config) {
+                Ok(_) => println!("ok"),
+                Err(e) => println!("error: {:?}", e),
+            },
+            Command::Pcap(on) => {
+                // 打开时先输出 pcap 文件头，主机端把 "PCAP " 行拼起来就是抓包文件
+                let mut radio = wifi.borrow_mut();
+                if on && !radio.capture {
+                    let mut header = Vec::new();
+                    pcap::write_global_header(&mut header);
+                    println!("{}", pcap::encode_serial_line(&header));
+                }
+                radio.capture = on;
+                println!("ok");
+            }
+            Command::Swarm(count) => SWARM_REQUESTS.signal(count),
+            _ => println!("ok"),
+        },
+        Err(e) => println!("error: {:?}", e),
+    }
+}
+
+// 串口控制台：读 USB 串口拼行，解析后按顺序交给状态任务
+#[embassy_executor::task]
+async fn console_task(mut serial: UsbSerialJtag<'static, Async>) {
+    let mut line_buffer = LineBuffer::new();
+    let mut bytes = [0u8; 64];
+    loop {
+        let Ok(count) = serial.read(&mut bytes).await;
+        for &byte in &bytes[..count] {
+            if let Some(line) = line_buffer.push(byte) {
+                match parse_command(line) {
+                    Ok(command) => COMMANDS.send(command).await,
+                    Err(e) => println!("error: {:?}", e),
+                }
+            }
+        }
+    }
+}
+
+// GNSS 接收：读 UART 上的 NMEA 语句，有定位就交给状态任务；串口再慢也不耽误发送任务
+#[embassy_executor::task]
+async fn gnss_task(mut uart: UartRx<'static, Async>) {
+    let mut line_buffer = LineBuffer::new();
+    let mut receiver = GnssReceiver::new();
+    let mut has_fix = false;
+    let mut bytes = [0u8; 64];
+    loop {
+        let count = match uart.read_async(&mut bytes).await {
+            Ok(count) => count,
+            Err(e) => {
+                warn!("GNSS UART error: {:?}", e);
+                continue;
+            }
+        };
+        for &byte in &bytes[..count] {
+            let Some(line) = line_buffer.push(byte) else { continue };
+            match receiver.push_line(line) {
+                Ok(Some(fix)) => {
+                    if !has_fix {
+                        info!("GNSS fix at {} {}", fix.latitude, fix.longitude);
+                        has_fix = true;
+                    }
+                    FIXES.signal(Some(fix));
+                }
+                Ok(None) => {
+                    if has_fix {
+                        warn!("GNSS fix lost");
+                        has_fix = false;
+                        FIXES.signal(None);
+                    }
+                }
+                Err(NmeaError::Unsupported) => {}
+                Err(e) => warn!("NMEA sentence rejected: {:?}", e),
+            }
+        }
+    }
+}
+
+// 实际轮换的信道；国家码在启动加载和 set country 时已按法规表检查过，
+// 万一查不到就报错并停在当前信道
+fn planned_channels(config: &TransmitterConfig) -> Vec<u8> {
+    config.hop_channels().unwrap_or_else(|e| {
+        error!("Cannot plan channels: {:?}", e);
+        alloc::vec![config.channel]
+    })
+}
+
+// 消息状态更新：执行控制台命令、合并 GNSS 定位、按驻留时间跳信道、调整射频，每次变化后发布新的发射状态
+#[embassy_executor::task]
+async fn state_task(
+    mut config: TransmitterConfig,
+    mut flash: ConfigPartition,
+    mut controller: WifiController<'static>,
+    wifi: &'static RefCell<WifiRadio>,
+    factory_mac: [u8; 6],
+    random_mac: [u8; 6],
+) {
+    // 信道计划只含一个信道时固定不动，否则按驻留时间轮换，信标的 DSSS 元素跟着当前信道
+    let mut hop_channels = planned_channels(&config);
+    let mut dwell_ms = config.dwell_ms;
+    let mut hopper = ChannelHopper::new(hop_channels.clone(), dwell_ms, now_ms());
+    let mut channel = hopper.channel();
+    let result = set_channel(channel);
+    wifi.borrow_mut().channel = channel;
+    info!("set channel {} result {:x}", channel, result);
+    let result = set_tx_power(config.tx_power_dbm);
+    info!("set tx power {} dBm result {:x}", config.tx_power_dbm, result);
+
+    let sender = STATE.sender();
+    let mut fix = None;
+    let mut fix_ms = 0;
+    let mut fix_lost = false;
+    loop {
+        sender.send(TransmitState { config: config.clone(), channel, fix, fix_ms, fix_lost });
+        // 定位过期时醒来作废，不再广播旧坐标
+        let fix_expiry_ms = fix.map(|_| fix_ms + GNSS_FIX_MAX_AGE_MS);
+        match select4(COMMANDS.receive(), FIXES.wait(), sleep_until(hopper.next_switch_ms()), sleep_until(fix_expiry_ms)).await {
+            Either4::First(command) => {
+                let previous = config.clone();
+                handle_command(command, &mut config, &mut flash, wifi);
+                // 策略或 UAS ID 改了，发射地址跟着更新
+                config.apply_mac_policy(factory_mac, random_mac);
+
+                // 信道、国家码、信道计划或驻留时间改了，从新计划的第一个信道开始
+                if planned_channels(&config) != hop_channels || config.dwell_ms != dwell_ms {
+                    hop_channels = planned_channels(&config);
+                    dwell_ms = config.dwell_ms;
+                    hopper = ChannelHopper::new(hop_channels.clone(), dwell_ms, now_ms());
+                }
+                // 换速率要先停下 Wi-Fi，重启后信道和功率要重新设置
+                if config.rate != previous.rate {
+                    if let Err(e) = controller.stop() {
+                        warn!("Failed to stop WiFi controller: {:?}", e);
+                    }
+                    let result = set_tx_rate(config.rate);
+                    if result != 0 {
+                        warn!("set rate {} Mbps result {:x}", config.rate.name(), result);
+                    }
+                    bring_up_async(BringUpStep::WifiStart, || controller.start()).await;
+                    set_channel(channel);
+                    set_tx_power(config.tx_power_dbm);
+                    wifi.borrow_mut().rate = config.rate;
+                }
+                // 换国家码后驱动会按新国家重新限制功率
+                if config.country != previous.country {
+                    let result = set_country(config.country);
+                    if result != 0 {
+                        warn!("set country result {:x}", result);
+                    }
+                    set_channel(channel);
+                    set_tx_power(config.tx_power_dbm);
+                }
+                if config.tx_power_dbm != previous.tx_power_dbm {
+                    let result = set_tx_power(config.tx_power_dbm);
+                    if result != 0 {
+                        warn!("set tx power {} dBm result {:x}", config.tx_power_dbm, result);
+                    }
+                }
+                if config.profile != previous.profile {
+                    if let Err(e) = config.profile.check(&config) {
+                        warn!("{} requirements not met: {:?}", config.profile.name(), e);
+                    }
+                }
+            }
+            Either4::Second(Some(new_fix)) => {
+                fix = Some(new_fix);
+                fix_ms = now_ms();
+                fix_lost = false;
+            }
+            Either4::Second(None) => {
+                fix = None;
+                fix_lost = true;
+            }
+            Either4::Third(()) => {}
+            Either4::Fourth(()) => {
+                warn!("GNSS fix older than {} ms, dropped", GNSS_FIX_MAX_AGE_MS);
+                fix = None;
+                fix_lost = true;
+            }
+        }
+
+        let new_channel = hopper.poll(now_ms()).unwrap_or(hopper.channel());
+        if new_channel != channel {
+            channel = new_channel;
+            let result = set_channel(channel);
+            wifi.borrow_mut().channel = channel;
+            if result != 0 {
+                warn!("set channel {} result {:x}", channel, result);
+            }
+        }
+    }
+}
+
+// 一种发送方式的发送调度：睡到下次到时间再按最新状态组包发送，发射状态或机群变化时提前醒来
+#[embassy_executor::task(pool_size = 4)]
+async fn transport_task(mut scheduler: TransportScheduler<'static>, kind: TransportKind, follows_interval: bool) {
+    let mut state_receiver = STATE.receiver().unwrap();
+    let mut swarm_receiver = SWARM_SIZE.receiver().unwrap();
+    let mut state = state_receiver.get().await;
+    let mut swarm_active = false;
+    loop {
+        let now_ms = now_ms();
+        let active = state.config.transmitting && !swarm_active;
+        if active && scheduler.is_due(now_ms) {
+            let package = build_package(&state);
+            // 没有 GNSS 时位置取自配置，组包即为最新；丢失定位时位置不算更新
+            if state.fix.is_none() && !state.fix_lost {
+                scheduler.data_updated(MessageKind::Location, now_ms);
+            }
+            let self_id = SelfIdMessage::new(&state.config.self_id);
+            scheduler.poll(now_ms, &Broadcast::new(&state.config, &package, &self_id).with_channel(state.channel));
+            TRANSPORT_STATUS.lock(|board| {
+                if let Some(status) = board.borrow_mut().iter_mut().find(|status| status.kind == kind) {
+                    status.stats = scheduler.stats().next().map(|(_, stats)| stats).unwrap_or_default();
+                    status.report = scheduler.report(now_ms).next().map(|(_, report)| report);
+                }
+            });
+        }
+
+        // 空闲或下次发送还早时也按心跳间隔醒来，醒来时间登记给看门狗
+        let heartbeat_ms = now_ms + HEARTBEAT_MS;
+        let wake_ms = match scheduler.next_due_ms() {
+            Some(due_ms) if active => due_ms.min(heartbeat_ms),
+            _ => heartbeat_ms,
+        };
+        TRANSPORT_STATUS.lock(|board| {
+            if let Some(status) = board.borrow_mut().iter_mut().find(|status| status.kind == kind) {
+                status.wake_by_ms = wake_ms;
+            }
+        });
+        match select3(state_receiver.changed(), swarm_receiver.changed(), sleep_until(Some(wake_ms))).await {
+            Either3::First(new_state) => {
+                if follows_interval && new_state.config.interval_ms != state.config.interval_ms {
+                    scheduler.set_interval(kind, new_state.config.interval_ms);
+                }
+                // 换了标准，按新标准的必发消息重新统计
+                if new_state.config.profile != state.config.profile {
+                    scheduler.set_rules(&new_state.config.profile.rules());
+                }
+                if new_state.fix.is_some() && new_state.fix_ms != state.fix_ms {
+                    scheduler.data_updated(MessageKind::Location, new_state.fix_ms);
+                }
+                state = new_state;
+            }
+            Either3::Second(swarm_size) => swarm_active = swarm_size > 0,
+            Either3::Third(()) => {}
+        }
+    }
+}
+
+// 机群模式：每架虚拟无人机发自己的信标和 NAN 帧，本机身份暂停发送，蓝牙不参与
+#[embassy_executor::task]
+async fn swarm_task(wifi: &'static RefCell<WifiRadio>) {
+    let mut state_receiver = STATE.receiver().unwrap();
+    let swarm_size = SWARM_SIZE.sender();
+    swarm_size.send(0);
+    let mut state = state_receiver.get().await;
+    let mut swarm: Option<Swarm<'static>> = None;
+    loop {
+        let transmitting = state.config.transmitting;
+        if let Some(swarm) = swarm.as_mut().filter(|_| transmitting) {
+            swarm.poll(now_ms());
+        }
+
+        let wake_ms = swarm.as_ref().filter(|_| transmitting).and_then(Swarm::next_due_ms);
+        match select3(SWARM_REQUESTS.wait(), state_receiver.changed(), sleep_until(wake_ms)).await {
+            Either3::First(count) => {
+                // 用命令之前最后发布的配置
+                if let Some(new_state) = state_receiver.try_changed() {
+                    state = new_state;
+                }
+                swarm = None;
+                if count > 0 {
+                    match Swarm::new(&state.config, count) {
+                        Ok(mut new_swarm) => {
+                            let start_ms = now_ms() as u32;
+                            let interval_ms = state.config.interval_ms;
+                            new_swarm.add(|| WifiBeaconTransport::new(wifi), interval_ms, start_ms);
+                            new_swarm.add(|| NanTransport::new(wifi), interval_ms, start_ms + interval_ms / 2);
+                            new_swarm.set_channel(state.channel);
+                            info!("Swarm of {} drones", count);
+                            swarm = Some(new_swarm);
+                            println!("ok");
+                        }
+                        Err(e) => println!("error: {:?}", e),
+                    }
+                } else {
+                    println!("ok");
+                }
+                swarm_size.send(swarm.as_ref().map_or(0, Swarm::len));
+            }
+            Either3::Second(new_state) => {
+                if let Some(swarm) = swarm.as_mut().filter(|_| new_state.channel != state.channel) {
+                    swarm.set_channel(new_state.channel);
+                }
+                state = new_state;
+            }
+            Either3::Third(()) => {}
+        }
+    }
+}
+
+// 状态报告：应控制台请求输出状态和合规报告，并定期在日志里记下发送计数
+#[embassy_executor::task]
+async fn status_task() {
+    loop {
+        match select(REPORT_REQUESTS.wait(), Timer::after_millis(STATUS_LOG_INTERVAL_MS)).await {
+            Either::First(ReportRequest::Status) => {
+                if let Some(state) = STATE.try_get() {
+                    print_status(&state, SWARM_SIZE.try_get().unwrap_or(0));
+                }
+            }
+            Either::First(ReportRequest::Report) => {
+                for (kind, _, report) in transport_rows() {
+                    println!("[{}]", kind.name());
+                    if let Some(report) = report {
+                        print!("{}", report);
+                    }
+                }
+            }
+            Either::Second(()) => {
+                let mut line = String::new();
+                TRANSPORT_STATUS.lock(|board| {
+                    for status in board.borrow().iter() {
+                        let _ = write!(line, " {} {}/{}", status.kind.name(), status.stats.sent, status.stats.failed);
+                    }
+                });
+                info!("sent/failed:{}", line);
+            }
+        }
+    }
+}
 
-#[main]
-fn main() -> ! {
+// 看门狗：发送任务都按约定时间醒来过才喂狗，有任务卡住或不让出 CPU 时复位
+#[embassy_executor::task]
+async fn watchdog_task(mut watchdog: Rwdt) {
+    loop {
+        let now_ms = now_ms();
+        let stalled = TRANSPORT_STATUS.lock(|board| {
+            board.borrow().iter().find(|status| now_ms > status.wake_by_ms + HEARTBEAT_GRACE_MS).map(|status| status.kind)
+        });
+        match stalled {
+            None => watchdog.feed(),
+            Some(kind) => warn!("{} transport stalled, watchdog not fed", kind.name()),
+        }
+        Timer::after_millis(WATCHDOG_FEED_MS).await;
+    }
+}
+
+// 每种发送方式一个调度器和一个发送任务，互不耽误
+fn spawn_transport(
+    spawner: &Spawner,
+    transport: impl RidTransport + 'static,
+    interval_ms: u32,
+    offset_ms: u32,
+    rules: &[MessageRule],
+    follows_interval: bool,
+) {
+    let kind = transport.kind();
+    let mut scheduler = TransportScheduler::new().with_rules(rules);
+    scheduler.add(transport, interval_ms, offset_ms);
+    TRANSPORT_STATUS.lock(|board| {
+        board.borrow_mut().push(TransportStatus {
+            kind,
+            stats: TransportStats::default(),
+            report: None,
+            wake_by_ms: now_ms() + HEARTBEAT_MS,
+        })
+    });
+    spawner.must_spawn(transport_task(scheduler, kind, follows_interval));
+}
+
+#[esp_hal_embassy::main]
+async fn main(spawner: Spawner) {
     esp_alloc::heap_allocator!(size: 72 * 1024);
     let config = esp_hal::Config::default().with_cpu_clock(CpuClock::max());
     let peripherals = esp_hal::init(config);
     esp_println::logger::init_logger(log::LevelFilter::Info);
-    
+
     info!("Drone RID Beacon Transmitter Starting...");
-    
+
+    // 报告上次复位的原因，再记下本次启动开始；任务启动前复位都算一次启动失败
+    let previous = load_reset_record();
+    let booting = ResetRecord::booting(previous);
+    store_reset_record(booting);
+    let hardware_reason = esp_hal::system::reset_reason();
+    match previous {
+        Some(record) => warn!("Last reset: {:?}, {}", hardware_reason, record),
+        None => info!("Last reset: {:?}", hardware_reason),
+    }
+
+    // 看门狗：任务卡住或启动挂死时复位
+    let mut watchdog = Rwdt::new();
+    watchdog.set_timeout(RwdtStage::Stage0, Duration::from_millis(WATCHDOG_TIMEOUT_MS));
+    watchdog.enable();
+    let delay = Delay::new();
+    if booting.boot_delay_ms() > 0 {
+        warn!("{} failed boots in a row, waiting {} ms before bring-up", booting.boot_failures - 1, booting.boot_delay_ms());
+        wait_feeding(&mut watchdog, &delay, booting.boot_delay_ms());
+    }
+
+    // 从 flash 恢复配置，分区为空时使用默认值
+    let mut flash = ConfigPartition::new();
+    let mut config = match load_config(&mut flash) {
+        Ok(config) => {
+            info!("Loaded configuration from flash");
+            config
+        }
+        Err(StorageError::Blank) => {
+            info!("No stored configuration, using defaults");
+            TransmitterConfig::default()
+        }
+        Err(e) => {
+            error!("Stored configuration rejected: {:?}, using defaults", e);
+            TransmitterConfig::default()
+        }
+    };
+
+    // 异步任务的时间基准用系统定时器，TIMG0 留给无线协议栈
+    let systimer = SystemTimer::new(peripherals.SYSTIMER);
+    esp_hal_embassy::init(systimer.alarm0);
+
     // Initialize WiFi
     let timg0 = TimerGroup::new(peripherals.TIMG0);
-    let init = esp_wifi::init(
-        timg0.timer0,
-        Rng::new(peripherals.RNG),
-    )
-    .unwrap();
-    
-    let wifi = peripherals.WIFI;
-    let (mut controller, interfaces) = esp_wifi::wifi::new(&init, wifi).unwrap();
-    match controller.set_mode(esp_wifi::wifi::WifiMode::ApSta) {
+    let mut rng = Rng::new(peripherals.RNG);
+
+    // 发射地址按策略确定：出厂 MAC、由 UAS ID 导出，或本次上电随机生成
+    let factory_mac = Efuse::read_base_mac_address();
+    let mut random_mac = [0u8; 6];
+    random_mac[..4].copy_from_slice(&rng.random().to_le_bytes());
+    random_mac[4..].copy_from_slice(&rng.random().to_le_bytes()[..2]);
+    config.apply_mac_policy(factory_mac, random_mac);
+
+    // 协议栈初始化和控制器创建会消耗外设，不能原地重试，失败就重启
+    static RADIO: StaticCell<EspWifiController<'static>> = StaticCell::new();
+    let init = match esp_wifi::init(timg0.timer0, rng) {
+        Ok(init) => &*RADIO.init(init),
+        Err(error) => restart_after(BringUpError { step: BringUpStep::RadioInit, attempts: 1, error }),
+    };
+
+    // 蓝牙传统广播和 Coded PHY 扩展广播，与 Wi-Fi 信标共存
+    static BLE: StaticCell<RefCell<HciAdvertiser<BleRadio<'static>>>> = StaticCell::new();
+    let ble = &*BLE.init(RefCell::new(HciAdvertiser::new(BleRadio::new(BleConnector::new(init, peripherals.BT)))));
+    let ble_legacy_ready = match ble.borrow_mut().start_legacy(BLE_ADVERTISING_INTERVAL_MS) {
         Ok(_) => {
-            info!("WiFi controller set mode successfully");
+            info!("BLE legacy advertising started");
+            true
         }
         Err(e) => {
-            error!("Failed to set WiFi mode: {:?}", e);
-            loop {} // Halt on startup failure
+            error!("Failed to start BLE advertising: {:?}", e);
+            false
         }
-    }
-    
-    // Configure STA settings
-    let sta_config = esp_wifi::wifi::Configuration::Client(esp_wifi::wifi::ClientConfiguration {
-        ssid: "RID-DRONE123456789".try_into().unwrap(),
-        channel: Some(6),
-        ..Default::default()
-    });
-    
-    match controller.set_configuration(&sta_config) {
+    };
+    let ble_extended_ready = match ble.borrow_mut().start_extended(BLE_EXTENDED_INTERVAL_MS) {
         Ok(_) => {
-            info!("STA configuration set successfully (channel 6)");
+            info!("BLE long range advertising started");
+            true
         }
         Err(e) => {
-            error!("Failed to set STA configuration: {:?}", e);
-            loop {} // Halt on startup failure
+            error!("Failed to start BLE long range advertising: {:?}", e);
+            false
         }
-    }
-    
-        
+    };
+
+    let (mut controller, interfaces) = match esp_wifi::wifi::new(init, peripherals.WIFI) {
+        Ok(wifi) => wifi,
+        Err(error) => restart_after(BringUpError { step: BringUpStep::WifiController, attempts: 1, error }),
+    };
+    let mut wait = |delay_ms| wait_feeding(&mut watchdog, &delay, delay_ms);
+    bring_up(BringUpStep::WifiMode, &mut wait, || controller.set_mode(esp_wifi::wifi::WifiMode::ApSta));
+
+    // Configure STA settings
+    let sta_config = esp_wifi::wifi::Configuration::Client(esp_wifi::wifi::ClientConfiguration {
+        ssid: "RID-DRONE123456789".into(),
+        channel: Some(config.channel),
+        ..Default::default()
+    });
+    bring_up(BringUpStep::WifiConfiguration, &mut wait, || controller.set_configuration(&sta_config));
+
     // Use the sniffer interface for raw frame transmission
-    let mut wifi_device = interfaces.sniffer;
-        info!("MAC Address: {:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}", 
-          MAC_ADDRESS[0], MAC_ADDRESS[1], MAC_ADDRESS[2], 
-          MAC_ADDRESS[3], MAC_ADDRESS[4], MAC_ADDRESS[5]);
-    
-    let delay = Delay::new();
-    let mut counter = 0;
-    
+    static WIFI: StaticCell<RefCell<WifiRadio>> = StaticCell::new();
+    let wifi_device = &*WIFI.init(RefCell::new(WifiRadio::new(interfaces.sniffer, config.channel, config.rate)));
+        info!("MAC Address: {:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X} ({})",
+          config.mac_address[0], config.mac_address[1], config.mac_address[2],
+          config.mac_address[3], config.mac_address[4], config.mac_address[5], config.mac_policy.name());
+
+    // 国家码和发射速率要在启动前设置
+    let result = set_country(config.country);
+    info!("set country {} result {:x}", core::str::from_utf8(&config.country).unwrap_or("??"), result);
+    let result = set_tx_rate(config.rate);
+    info!("set rate {} Mbps result {:x}", config.rate.name(), result);
+
     // Start WiFi for raw frame transmission
-    info!("Starting WiFi controller...");
-    match controller.start() {
-        Ok(_) => {
-            info!("WiFi controller started successfully");
-        }
-        Err(e) => {
-            error!("Failed to start WiFi controller: {:?}", e);
-            loop {} // Halt on startup failure
-        }
+    bring_up(BringUpStep::WifiStart, &mut wait, || controller.start());
+
+    // GNSS 接收机可选，串口打不开时位置取自配置
+    let gnss = UartRx::new(peripherals.UART1, uart::Config::default().with_baudrate(GNSS_BAUD_RATE))
+        .map(|uart| uart.with_rx(peripherals.GPIO5).into_async());
+
+    info!("Profile {}", config.profile.name());
+    if let Err(e) = config.profile.check(&config) {
+        warn!("{} requirements not met: {:?}", config.profile.name(), e);
     }
-    unsafe {
-        let result = esp_wifi_sys::include::esp_wifi_set_channel(6,  0);
-        info!("set channel result {:x}", result);
-    };
-    //--------------------test data --------------------------------//
-    let package = PacketMessage::build_rid_package();
-    let rid_data = package.encode();
-    let mut rid_element = Vec::new();
-    rid_element.extend_from_slice(&[0xfa, 0x0b, 0xbc]); // OUI
-    rid_element.push(0x0d); // OUI type
-    rid_element.extend_from_slice(rid_data.as_slice());
-    let rid_slice = rid_element.as_slice();
-    
-    let mut beacon = [0u8; 300];
-    let length = beacon
-        .pwrite(
-            BeaconFrame {
-                header: ManagementFrameHeader {
-                    fcf_flags: FCFFlags::new(),
-                    duration: 0,
-                    receiver_address: [0xff; 6].into(),
-                    transmitter_address: MAC_ADDRESS.into(),
-                    bssid: MAC_ADDRESS.into(),
-                    ..Default::default()
-                },
-                body: BeaconBody {
-                    timestamp: 0,
-                    // We transmit a beacon every 100 ms/TUs
-                    beacon_interval: 1000,
-                    capabilities_info: CapabilitiesInformation::new().with_is_ess(true),
-                    elements: element_chain! {
-                        SSIDElement::new(package.get_ssid()).unwrap(),
-                        // These are known good values.
-                        supported_rates![
-                            1 B
-                        ],
-                        DSSSParameterSetElement {
-                            current_channel: 6,
-                        },
-                        // RID data element (vendor-specific)
-                        RawIEEE80211Element {
-                            tlv_type: 221, // Vendor-specific element
-                            slice: rid_slice,
-                            _phantom: PhantomData
-                        }
-                    },
-                    _phantom: PhantomData,
-                },
-            },
-            0,
-        )
-        .unwrap();
-    // Only use the actually written bytes.
-    let beacon = &beacon[..length];
-    
-    // Main beacon transmission loop
-    info!("Entering main transmission loop");
-    loop {
-        counter += 1;
-        
-        if counter % 100 == 0 {
-            info!("Transmitted {} beacon frames", counter);
-        }
-        
-        // Send raw beacon frame using sniffer mode
-        match wifi_device.send_raw_frame(true, &beacon, false) {
-            Ok(_) => {
-                // Successfully sent beacon frame
-                info!("send success.");
-            }
-            Err(e) => {
-                error!("Failed to send beacon frame: {:?}", e);
-            }
-        }
-        
-        delay.delay_millis(500); // 100ms interval
+
+    // 各发送方式按自己的间隔发送同一份整包；NAN 帧落在两个信标中间
+    let start_ms = now_ms() as u32;
+    let rules = config.profile.rules();
+    spawn_transport(&spawner, WifiBeaconTransport::new(wifi_device), config.interval_ms, start_ms, &rules, true);
+    spawn_transport(&spawner, NanTransport::new(wifi_device), config.interval_ms, start_ms + config.interval_ms / 2, &rules, true);
+    if ble_legacy_ready {
+        spawn_transport(&spawner, BleLegacyTransport::new(ble), BLE_SLOT_MS, start_ms, &rules, false);
     }
-}
+    if ble_extended_ready {
+        spawn_transport(&spawner, BleExtendedTransport::new(ble), config.interval_ms, start_ms, &rules, true);
+    }
+    spawner.must_spawn(swarm_task(wifi_device));
+    spawner.must_spawn(state_task(config, flash, controller, wifi_device, factory_mac, random_mac));
+    spawner.must_spawn(console_task(UsbSerialJtag::new(peripherals.USB_DEVICE).into_async()));
+    match gnss {
+        Ok(uart) => spawner.must_spawn(gnss_task(uart)),
+        Err(e) => error!("Failed to open GNSS UART: {:?}", e),
+    }
+    spawner.must_spawn(status_task());
+    spawner.must_spawn(watchdog_task(watchdog));
 
+    info!("Tasks started, type 'help' for console commands");
+    store_reset_record(ResetRecord::running());
+}
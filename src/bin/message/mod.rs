@@ -1,5 +0,0 @@
-pub mod message;
-pub mod base_message;
-pub mod position_vector_message;
-pub mod system_message;
-pub mod packet_message;
\ No newline at end of file
@@ -0,0 +1,41 @@
+use alloc::vec::Vec;
+
+use esp32c6_test::pcap::{self, RadiotapInfo};
+use esp32c6_test::radio::PhyRate;
+use esp32c6_test::transport::RawFrameSink;
+use esp_hal::time::Instant;
+use esp_println::println;
+use esp_wifi::wifi::{Sniffer, WifiError};
+
+/// 用 sniffer 接口发原始帧，抓包打开时把发出的帧作为 pcap 记录打到串口
+pub struct WifiRadio {
+    sniffer: Sniffer,
+    pub capture: bool,
+    pub channel: u8,   // 只用于 radiotap 头
+    pub rate: PhyRate, // 只用于 radiotap 头
+}
+
+impl WifiRadio {
+    pub fn new(sniffer: Sniffer, channel: u8, rate: PhyRate) -> Self {
+        Self { sniffer, capture: false, channel, rate }
+    }
+}
+
+impl RawFrameSink for WifiRadio {
+    type Error = WifiError;
+
+    fn send_raw(&mut self, frame: &[u8]) -> Result<(), WifiError> {
+        self.sniffer.send_raw_frame(true, frame, false)?;
+        if self.capture {
+            let mut record = Vec::new();
+            let info = RadiotapInfo { rate: self.rate.radiotap_rate(), channel: self.channel, signal_dbm: None };
+            pcap::write_record(&mut record, Instant::now().duration_since_epoch().as_micros(), &info, frame);
+            println!("{}", pcap::encode_serial_line(&record));
+        }
+        Ok(())
+    }
+
+    fn now_us(&mut self) -> u64 {
+        Instant::now().duration_since_epoch().as_micros()
+    }
+}
@@ -0,0 +1,384 @@
+use alloc::vec::Vec;
+
+use crate::counter::MessageCounters;
+use crate::message::{
+    message::Message, packet_message::PacketMessage, self_id_message::SelfIdMessage,
+};
+use crate::schedule::MessageKind;
+
+/// ASTM F3411 蓝牙广播的 16 位服务 UUID
+pub const ODID_SERVICE_UUID: u16 = 0xFFFA;
+/// 服务数据里的应用码，表示 Open Drone ID
+pub const ODID_APP_CODE: u8 = 0x0D;
+/// 单条消息长度
+pub const MESSAGE_LENGTH: usize = 25;
+/// 传统广播数据最长 31 字节
+pub const LEGACY_ADV_DATA_LENGTH: usize = 31;
+/// 轮播时隙的上限：位置报文每 2 个时隙一次（不低于 1 Hz），静态报文每 6 个时隙一次（不超过 3 秒）
+pub const MAX_LEGACY_SLOT_MS: u32 = 500;
+/// AD 类型：16 位 UUID 服务数据
+const AD_TYPE_SERVICE_DATA_16: u8 = 0x16;
+
+/// 扩展广播数据在一条 HCI 命令里最多 251 字节
+pub const MAX_EXTENDED_ADV_DATA_LENGTH: usize = 251;
+
+/// HCI 命令包的 H4 标识
+pub const HCI_COMMAND_PACKET: u8 = 0x01;
+/// HCI 命令操作码
+const OPCODE_RESET: u16 = 0x0C03;
+const OPCODE_LE_SET_EXTENDED_ADVERTISING_PARAMETERS: u16 = 0x2036;
+const OPCODE_LE_SET_EXTENDED_ADVERTISING_DATA: u16 = 0x2037;
+const OPCODE_LE_SET_EXTENDED_ADVERTISING_ENABLE: u16 = 0x2039;
+/// 同一控制器上传统与扩展广播命令不能混用，两种广播都用扩展命令，各占一个广播集
+pub const LEGACY_ADVERTISING_HANDLE: u8 = 0;
+pub const EXTENDED_ADVERTISING_HANDLE: u8 = 1;
+/// 广播属性：传统 PDU 的不可连接广播 (ADV_NONCONN_IND)、不可连接不可扫描的扩展广播
+const PROPERTIES_LEGACY_NONCONN: u16 = 0x0010;
+const PROPERTIES_EXTENDED_NONCONN: u16 = 0x0000;
+/// 物理层：1M、Coded（远距离）
+pub const PHY_1M: u8 = 0x01;
+pub const PHY_CODED: u8 = 0x03;
+/// 扩展广播数据一次发完，不让控制器分片
+const OPERATION_COMPLETE: u8 = 0x03;
+const FRAGMENT_PREFERENCE_NONE: u8 = 0x01;
+/// 广播间隔范围，单位 0.625 ms
+const MIN_ADVERTISING_INTERVAL: u32 = 0x0020;
+const MAX_ADVERTISING_INTERVAL: u32 = 0xFF_FFFF;
+
+/// 蓝牙广播错误
+#[derive(Debug, PartialEq)]
+pub enum BleError {
+    InvalidMessageLength(usize), // 单条消息长度不是 25
+    PayloadTooLong(usize),       // 整包超出扩展广播数据长度
+}
+
+/// 把一条 25 字节的消息包装成传统广播数据：
+/// 长度、服务数据类型、UUID 0xFFFA（小端）、应用码、计数器、消息
+pub fn legacy_advertising_data(counter: u8, message: &[u8]) -> Result<[u8; LEGACY_ADV_DATA_LENGTH], BleError> {
+    if message.len() != MESSAGE_LENGTH {
+        return Err(BleError::InvalidMessageLength(message.len()));
+    }
+    let mut data = [0u8; LEGACY_ADV_DATA_LENGTH];
+    data[0] = (LEGACY_ADV_DATA_LENGTH - 1) as u8;
+    data[1] = AD_TYPE_SERVICE_DATA_16;
+    data[2..4].copy_from_slice(&ODID_SERVICE_UUID.to_le_bytes());
+    data[4] = ODID_APP_CODE;
+    data[5] = counter;
+    data[6..].copy_from_slice(message);
+    Ok(data)
+}
+
+/// 把 PacketMessage::encode 的输出（计数器加整包，与 Wi-Fi 信标的厂商元素内容相同）
+/// 包装成扩展广播数据；国标的校验和与预留字节只跟在 Wi-Fi 厂商元素后面，蓝牙按 F3411 去掉
+pub fn extended_advertising_data(payload: &[u8]) -> Result<Vec<u8>, BleError> {
+    // 按整包头里的单条长度和条数找到最后一条消息的结尾
+    let end = match payload.get(2..4) {
+        Some(&[size, quantity]) => (4 + size as usize * quantity as usize).min(payload.len()),
+        _ => payload.len(),
+    };
+    let payload = &payload[..end];
+    let length = 4 + payload.len();
+    if 1 + length > MAX_EXTENDED_ADV_DATA_LENGTH {
+        return Err(BleError::PayloadTooLong(payload.len()));
+    }
+    let mut data = Vec::with_capacity(1 + length);
+    data.push(length as u8);
+    data.push(AD_TYPE_SERVICE_DATA_16);
+    data.extend_from_slice(&ODID_SERVICE_UUID.to_le_bytes());
+    data.push(ODID_APP_CODE);
+    data.extend_from_slice(payload);
+    Ok(data)
+}
+
+/// 传统广播一次只能带一条消息，按时隙轮流发送
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LegacySlot {
+    BasicId,
+    Location,
+    System,
+    SelfId,
+}
+
+impl LegacySlot {
+    pub fn kind(self) -> MessageKind {
+        match self {
+            LegacySlot::BasicId => MessageKind::BasicId,
+            LegacySlot::Location => MessageKind::Location,
+            LegacySlot::System => MessageKind::System,
+            LegacySlot::SelfId => MessageKind::SelfId,
+        }
+    }
+}
+
+/// 位置报文隔一个时隙发一次，其余报文轮流填空
+const LEGACY_SEQUENCE: [LegacySlot; 6] = [
+    LegacySlot::Location,
+    LegacySlot::BasicId,
+    LegacySlot::Location,
+    LegacySlot::System,
+    LegacySlot::Location,
+    LegacySlot::SelfId,
+];
+
+/// 传统广播轮播状态，每种消息各自计数
+#[derive(Debug, Default)]
+pub struct LegacyRotation {
+    index: usize,
+    counters: MessageCounters,
+}
+
+impl LegacyRotation {
+    pub const fn new() -> Self {
+        Self { index: 0, counters: MessageCounters::new() }
+    }
+
+    /// 下一个时隙发送的消息类型
+    pub fn next_slot(&mut self) -> LegacySlot {
+        let slot = LEGACY_SEQUENCE[self.index];
+        self.index = (self.index + 1) % LEGACY_SEQUENCE.len();
+        slot
+    }
+
+    /// 组下一条广播数据，该类型的计数器加一
+    pub fn next(&mut self, packet: &PacketMessage, self_id: &SelfIdMessage) -> Result<[u8; LEGACY_ADV_DATA_LENGTH], BleError> {
+        let slot = self.next_slot();
+        let message = match slot {
+            LegacySlot::BasicId => packet.base_message.encode(),
+            LegacySlot::Location => packet.position_message.encode(),
+            LegacySlot::System => packet.system_message.encode(),
+            LegacySlot::SelfId => self_id.encode(),
+        };
+        let data = legacy_advertising_data(self.counters.message(slot.kind()), &message)?;
+        self.counters.message_sent(slot.kind());
+        Ok(data)
+    }
+}
+
+/// 组 H4 格式的 HCI 命令包
+pub fn hci_command(opcode: u16, params: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(4 + params.len());
+    packet.push(HCI_COMMAND_PACKET);
+    packet.extend_from_slice(&opcode.to_le_bytes());
+    packet.push(params.len() as u8);
+    packet.extend_from_slice(params);
+    packet
+}
+
+pub fn hci_reset() -> Vec<u8> {
+    hci_command(OPCODE_RESET, &[])
+}
+
+/// 广播集参数：三个主广播信道都用，间隔单位毫秒，phy 同时用于主副信道（传统 PDU 只能用 1M）
+pub fn le_set_extended_advertising_parameters(handle: u8, legacy: bool, interval_ms: u32, phy: u8) -> Vec<u8> {
+    let interval = (interval_ms.saturating_mul(8) / 5).clamp(MIN_ADVERTISING_INTERVAL, MAX_ADVERTISING_INTERVAL);
+    let properties = if legacy { PROPERTIES_LEGACY_NONCONN } else { PROPERTIES_EXTENDED_NONCONN };
+    let mut params = [0u8; 25];
+    params[0] = handle;
+    params[1..3].copy_from_slice(&properties.to_le_bytes());
+    params[3..6].copy_from_slice(&interval.to_le_bytes()[..3]); // 最小间隔
+    params[6..9].copy_from_slice(&interval.to_le_bytes()[..3]); // 最大间隔
+    params[9] = 0x07; // 信道 37、38、39
+    params[10] = 0x00; // 本机地址类型：公共地址
+    // params[11..18] 对端地址类型与地址，不使用
+    params[18] = 0x00; // 不过滤
+    params[19] = 0x7F; // 发射功率由控制器决定
+    params[20] = phy; // 主信道物理层
+    params[21] = 0x00; // 副信道不跳过
+    params[22] = phy; // 副信道物理层
+    params[23] = handle; // 广播 SID
+    params[24] = 0x00; // 不上报扫描请求
+    hci_command(OPCODE_LE_SET_EXTENDED_ADVERTISING_PARAMETERS, &params)
+}
+
+pub fn le_set_extended_advertising_data(handle: u8, data: &[u8]) -> Vec<u8> {
+    let length = data.len().min(MAX_EXTENDED_ADV_DATA_LENGTH);
+    let mut params = Vec::with_capacity(4 + length);
+    params.push(handle);
+    params.push(OPERATION_COMPLETE);
+    params.push(FRAGMENT_PREFERENCE_NONE);
+    params.push(length as u8);
+    params.extend_from_slice(&data[..length]);
+    hci_command(OPCODE_LE_SET_EXTENDED_ADVERTISING_DATA, &params)
+}
+
+/// 打开或关闭一个广播集，不限时长和次数
+pub fn le_set_extended_advertising_enable(handle: u8, enable: bool) -> Vec<u8> {
+    hci_command(OPCODE_LE_SET_EXTENDED_ADVERTISING_ENABLE, &[enable as u8, 1, handle, 0, 0, 0])
+}
+
+/// HCI 传输，固件里是片上蓝牙控制器，测试里记录发出的命令
+pub trait HciTransport {
+    type Error: core::fmt::Debug;
+
+    /// 发送一个 H4 格式的 HCI 包，返回前应等到控制器处理完
+    fn send(&mut self, packet: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// 蓝牙广播接口
+pub trait BleAdvertiser {
+    type Error: core::fmt::Debug;
+
+    /// 开始不可连接的传统广播
+    fn start_legacy(&mut self, interval_ms: u32) -> Result<(), Self::Error>;
+    /// 替换传统广播数据，控制器按广播间隔重复发送
+    fn set_legacy_data(&mut self, data: &[u8; LEGACY_ADV_DATA_LENGTH]) -> Result<(), Self::Error>;
+    /// 开始 Coded PHY 上的不可连接扩展广播
+    fn start_extended(&mut self, interval_ms: u32) -> Result<(), Self::Error>;
+    /// 替换扩展广播数据
+    fn set_extended_data(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// 用 HCI 命令实现广播，第一次开始广播前复位控制器
+pub struct HciAdvertiser<T: HciTransport> {
+    pub transport: T,
+    reset: bool,
+}
+
+impl<T: HciTransport> HciAdvertiser<T> {
+    pub fn new(transport: T) -> Self {
+        Self { transport, reset: false }
+    }
+
+    fn start(&mut self, handle: u8, legacy: bool, interval_ms: u32, phy: u8) -> Result<(), T::Error> {
+        if !self.reset {
+            self.transport.send(&hci_reset())?;
+            self.reset = true;
+        }
+        self.transport.send(&le_set_extended_advertising_parameters(handle, legacy, interval_ms, phy))?;
+        self.transport.send(&le_set_extended_advertising_enable(handle, true))
+    }
+}
+
+impl<T: HciTransport> BleAdvertiser for HciAdvertiser<T> {
+    type Error = T::Error;
+
+    fn start_legacy(&mut self, interval_ms: u32) -> Result<(), T::Error> {
+        self.start(LEGACY_ADVERTISING_HANDLE, true, interval_ms, PHY_1M)
+    }
+
+    fn set_legacy_data(&mut self, data: &[u8; LEGACY_ADV_DATA_LENGTH]) -> Result<(), T::Error> {
+        self.transport.send(&le_set_extended_advertising_data(LEGACY_ADVERTISING_HANDLE, data))
+    }
+
+    fn start_extended(&mut self, interval_ms: u32) -> Result<(), T::Error> {
+        self.start(EXTENDED_ADVERTISING_HANDLE, false, interval_ms, PHY_CODED)
+    }
+
+    fn set_extended_data(&mut self, data: &[u8]) -> Result<(), T::Error> {
+        self.transport.send(&le_set_extended_advertising_data(EXTENDED_ADVERTISING_HANDLE, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TransmitterConfig;
+
+    #[test]
+    fn legacy_payload_layout() {
+        let message = [0x42u8; MESSAGE_LENGTH];
+        let data = legacy_advertising_data(7, &message).unwrap();
+        assert_eq!(data[..6], [0x1e, 0x16, 0xfa, 0xff, 0x0d, 0x07]);
+        assert_eq!(data[6..], message);
+        assert_eq!(legacy_advertising_data(0, &message[..24]), Err(BleError::InvalidMessageLength(24)));
+    }
+
+    #[test]
+    fn rotation_meets_rates() {
+        let config = TransmitterConfig::default();
+        let packet = PacketMessage::from_config(&config);
+        let self_id = SelfIdMessage::new("Survey flight");
+        let mut rotation = LegacyRotation::new();
+
+        // 按最大时隙发 3 秒，位置报文间隔不超过 1 秒，其余报文都至少出现一次
+        let slots = (3000 / MAX_LEGACY_SLOT_MS) as usize;
+        let mut types = Vec::new();
+        for _ in 0..slots {
+            let data = rotation.next(&packet, &self_id).unwrap();
+            // 计数器按消息类型各自递增
+            let message_type = data[6] >> 4;
+            assert_eq!(data[5] as usize, types.iter().filter(|&&t| t == message_type).count());
+            types.push(message_type);
+        }
+        for window in types.windows((1000 / MAX_LEGACY_SLOT_MS) as usize) {
+            assert!(window.contains(&1));
+        }
+        for message_type in [0, 3, 4] {
+            assert!(types.contains(&message_type));
+        }
+    }
+
+    #[test]
+    fn extended_payload_layout() {
+        let config = TransmitterConfig::default();
+        let payload = PacketMessage::from_config(&config).encode();
+        let data = extended_advertising_data(&payload).unwrap();
+        // 国标整包后的校验和与 3 字节预留不进蓝牙广播
+        let pack = &payload[..4 + 3 * MESSAGE_LENGTH];
+        assert_eq!(payload.len(), pack.len() + 5);
+        assert_eq!(data[..5], [4 + pack.len() as u8, 0x16, 0xfa, 0xff, 0x0d]);
+        assert_eq!(data[5..], *pack);
+        // 计数器后紧跟整包头 0xf1、单条长度 25、条数 3
+        assert_eq!(data[6..9], [0xf1, 0x19, 0x03]);
+
+        // 没有校验和尾部的整包原样带上；长度按去掉尾部后的整包算
+        let mut long = vec![0, 0xf2, 25, 10];
+        long.resize(4 + 10 * MESSAGE_LENGTH, 0);
+        assert_eq!(extended_advertising_data(&long), Err(BleError::PayloadTooLong(254)));
+        long[3] = 9;
+        long.truncate(4 + 9 * MESSAGE_LENGTH + 5);
+        assert_eq!(extended_advertising_data(&long).unwrap().len(), 5 + 4 + 9 * MESSAGE_LENGTH);
+        let mut full = vec![0, 0xf2, 242, 1];
+        full.resize(246, 0);
+        assert_eq!(extended_advertising_data(&full).unwrap().len(), MAX_EXTENDED_ADV_DATA_LENGTH);
+    }
+
+    #[test]
+    fn hci_commands() {
+        assert_eq!(hci_reset(), [0x01, 0x03, 0x0c, 0x00]);
+        assert_eq!(
+            le_set_extended_advertising_parameters(EXTENDED_ADVERTISING_HANDLE, false, 100, PHY_CODED),
+            [
+                0x01, 0x36, 0x20, 0x19, // 命令头
+                0x01, 0x00, 0x00, // 广播集 1，扩展不可连接
+                0xa0, 0x00, 0x00, 0xa0, 0x00, 0x00, // 100 ms
+                0x07, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0x00, 0x7f, // 信道、地址、过滤、功率
+                0x03, 0x00, 0x03, 0x01, 0x00, // Coded PHY、SID
+            ]
+        );
+        let legacy = le_set_extended_advertising_parameters(LEGACY_ADVERTISING_HANDLE, true, 1, PHY_1M);
+        assert_eq!(legacy[4..10], [0x00, 0x10, 0x00, 0x20, 0x00, 0x00]);
+        assert_eq!(le_set_extended_advertising_enable(1, true), [0x01, 0x39, 0x20, 0x06, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00]);
+        let data = le_set_extended_advertising_data(0, &[0xaa; LEGACY_ADV_DATA_LENGTH]);
+        assert_eq!(data[..8], [0x01, 0x37, 0x20, 0x23, 0x00, 0x03, 0x01, 0x1f]);
+        assert_eq!(data.len(), 8 + LEGACY_ADV_DATA_LENGTH);
+    }
+
+    #[derive(Default)]
+    struct RecordingHci {
+        packets: Vec<Vec<u8>>,
+    }
+
+    impl HciTransport for RecordingHci {
+        type Error = ();
+
+        fn send(&mut self, packet: &[u8]) -> Result<(), ()> {
+            self.packets.push(packet.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn advertiser_sends_hci_sequence() {
+        let mut advertiser = HciAdvertiser::new(RecordingHci::default());
+        advertiser.start_legacy(100).unwrap();
+        advertiser.set_legacy_data(&[0; LEGACY_ADV_DATA_LENGTH]).unwrap();
+        advertiser.start_extended(1000).unwrap();
+        advertiser.set_extended_data(&[0; 10]).unwrap();
+        let packets = &advertiser.transport.packets;
+        let opcodes: Vec<u16> = packets.iter().map(|p| u16::from_le_bytes([p[1], p[2]])).collect();
+        assert_eq!(opcodes, [0x0c03, 0x2036, 0x2039, 0x2037, 0x2036, 0x2039, 0x2037]);
+        // 两种广播分别用广播集 0 和 1
+        assert_eq!((packets[3][4], packets[6][4]), (0, 1));
+    }
+}
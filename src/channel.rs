@@ -0,0 +1,172 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 信道计划里最多的信道数，2.4G 共 14 个信道
+pub const MAX_PLAN_CHANNELS: usize = 14;
+
+/// 一个国家或地区在 2.4G 允许的信道和最大发射功率
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegulatoryDomain {
+    pub country: [u8; 2],     // ISO 3166 国家码
+    pub last_channel: u8,     // 1 到 last_channel 可用，14 信道只有日本可用
+    pub max_tx_power_dbm: i8, // 最大发射功率 (EIRP)
+}
+
+impl RegulatoryDomain {
+    pub fn allows(&self, channel: u8) -> bool {
+        (1..=self.last_channel).contains(&channel)
+    }
+
+    /// 允许的全部信道
+    pub fn channels(&self) -> impl Iterator<Item = u8> {
+        1..=self.last_channel
+    }
+}
+
+/// 法规表：北美 12、13 信道不可用，14 信道只在日本可用
+pub const REGULATORY_DOMAINS: &[RegulatoryDomain] = &include!("regulatory_table.rs");
+
+/// 按国家码查法规表，不在表里的国家不支持
+pub fn regulatory_domain(country: [u8; 2]) -> Option<&'static RegulatoryDomain> {
+    REGULATORY_DOMAINS.iter().find(|domain| domain.country == country)
+}
+
+/// 信道计划：固定信道，或按驻留时间在几个信道间轮换
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ChannelPlan {
+    #[default]
+    Fixed,         // 固定在配置的信道
+    List(Vec<u8>), // 按给定顺序轮换
+    AllAllowed,    // 轮换所在国家允许的全部信道
+}
+
+impl ChannelPlan {
+    /// 解析 "off"、"all" 或逗号分隔的信道列表，如 "1,6,11"
+    pub fn parse(text: &str) -> Option<Self> {
+        if text.eq_ignore_ascii_case("off") {
+            return Some(ChannelPlan::Fixed);
+        }
+        if text.eq_ignore_ascii_case("all") {
+            return Some(ChannelPlan::AllAllowed);
+        }
+        let mut channels = Vec::new();
+        for part in text.split(',') {
+            let channel: u8 = part.parse().ok()?;
+            if !(1..=MAX_PLAN_CHANNELS as u8).contains(&channel) || channels.contains(&channel) {
+                return None;
+            }
+            channels.push(channel);
+        }
+        Some(ChannelPlan::List(channels))
+    }
+
+    /// 实际轮换的信道：固定时只有 channel，全部信道时按 domain 展开
+    pub fn channels(&self, channel: u8, domain: &RegulatoryDomain) -> Vec<u8> {
+        match self {
+            ChannelPlan::Fixed => vec![channel],
+            ChannelPlan::List(channels) => channels.clone(),
+            ChannelPlan::AllAllowed => domain.channels().collect(),
+        }
+    }
+}
+
+/// 按驻留时间轮换信道，时间由调用方传入
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelHopper {
+    channels: Vec<u8>,
+    dwell_ms: u32,
+    index: usize,
+    next_switch_ms: u64,
+}
+
+impl ChannelHopper {
+    /// 从第一个信道开始，channels 不能为空
+    pub fn new(channels: Vec<u8>, dwell_ms: u32, now_ms: u64) -> Self {
+        assert!(!channels.is_empty());
+        Self { channels, dwell_ms, index: 0, next_switch_ms: now_ms + dwell_ms as u64 }
+    }
+
+    /// 当前信道
+    pub fn channel(&self) -> u8 {
+        self.channels[self.index]
+    }
+
+    /// 下次换信道的时刻，只有一个信道时为 None
+    pub fn next_switch_ms(&self) -> Option<u64> {
+        (self.channels.len() >= 2).then_some(self.next_switch_ms)
+    }
+
+    /// 驻留时间到了就换到下一个信道并返回它；只有一个信道时从不切换
+    /// 落后多个驻留时间时只换一次，从现在起重新计时
+    pub fn poll(&mut self, now_ms: u64) -> Option<u8> {
+        if self.channels.len() < 2 || now_ms < self.next_switch_ms {
+            return None;
+        }
+        self.index = (self.index + 1) % self.channels.len();
+        self.next_switch_ms += self.dwell_ms as u64;
+        if self.next_switch_ms <= now_ms {
+            self.next_switch_ms = now_ms + self.dwell_ms as u64;
+        }
+        Some(self.channel())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigError, TransmitterConfig};
+
+    #[test]
+    fn regulatory_table_limits_high_channels() {
+        let us = regulatory_domain(*b"US").unwrap();
+        assert!(us.allows(11) && !us.allows(12) && !us.allows(13));
+        let cn = regulatory_domain(*b"CN").unwrap();
+        assert!(cn.allows(13) && !cn.allows(14));
+        assert!(regulatory_domain(*b"JP").unwrap().allows(14));
+        // 只有日本能用 14 信道
+        assert_eq!(REGULATORY_DOMAINS.iter().filter(|domain| domain.allows(14)).count(), 1);
+        assert_eq!(regulatory_domain(*b"ZZ"), None);
+    }
+
+    #[test]
+    fn unknown_country_is_an_error() {
+        // 直接写进不在表里的国家码时报错，不再按中国处理
+        let config = TransmitterConfig { country: *b"ZZ", ..TransmitterConfig::default() };
+        assert_eq!(config.regulatory_domain(), Err(ConfigError::InvalidCountry));
+        assert_eq!(config.country_element(), Err(ConfigError::InvalidCountry));
+        assert_eq!(config.hop_channels(), Err(ConfigError::InvalidCountry));
+        assert_eq!(config.clone().set_tx_power(10), Err(ConfigError::InvalidCountry));
+    }
+
+    #[test]
+    fn plan_parsing_and_expansion() {
+        assert_eq!(ChannelPlan::parse("1,6,11"), Some(ChannelPlan::List(vec![1, 6, 11])));
+        assert_eq!(ChannelPlan::parse("ALL"), Some(ChannelPlan::AllAllowed));
+        assert_eq!(ChannelPlan::parse("off"), Some(ChannelPlan::Fixed));
+        for bad in ["", "0", "15", "1,,6", "6,6", "a"] {
+            assert_eq!(ChannelPlan::parse(bad), None, "{:?}", bad);
+        }
+        let us = regulatory_domain(*b"US").unwrap();
+        assert_eq!(ChannelPlan::AllAllowed.channels(6, us), (1..=11).collect::<Vec<u8>>());
+        assert_eq!(ChannelPlan::Fixed.channels(6, us), [6]);
+    }
+
+    #[test]
+    fn hopper_cycles_with_dwell() {
+        let mut hopper = ChannelHopper::new(vec![1, 6, 11], 300, 0);
+        let mut switches = Vec::new();
+        for now_ms in 0..1000 {
+            if let Some(channel) = hopper.poll(now_ms) {
+                switches.push((now_ms, channel));
+            }
+        }
+        assert_eq!(switches, [(300, 6), (600, 11), (900, 1)]);
+        assert_eq!(hopper.next_switch_ms(), Some(1200));
+        // 停了很久之后只换一个信道
+        assert_eq!(hopper.poll(5000), Some(6));
+        assert_eq!(hopper.poll(5299), None);
+
+        let mut fixed = ChannelHopper::new(vec![6], 300, 0);
+        assert_eq!((fixed.poll(10_000), fixed.channel(), fixed.next_switch_ms()), (None, 6, None));
+    }
+}
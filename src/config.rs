@@ -0,0 +1,293 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::beacon::CountryElement;
+use crate::channel::{regulatory_domain, ChannelPlan, RegulatoryDomain};
+use crate::mac::{is_transmitter_address, locally_administered, mac_from_uas_id, MacPolicy};
+use crate::radio::{PhyRate, MAX_TX_POWER_DBM, MIN_TX_POWER_DBM};
+
+// 构建时生成的默认 UAS ID、MAC 与其策略、信道、国家码、发射功率、速率、发送间隔和标准，见 build.rs 与 rid.toml.example
+include!(concat!(env!("OUT_DIR"), "/rid_defaults.rs"));
+
+/// 默认 ID 类型（1 = 序列号）与 UA 类型（1 = 固定翼）
+pub const DEFAULT_ID_TYPE: u8 = 1;
+pub const DEFAULT_UA_TYPE: u8 = 1;
+/// 默认纬度、经度（1e-7 度）
+pub const DEFAULT_LATITUDE: i32 = 417144677;
+pub const DEFAULT_LONGITUDE: i32 = 1234844601;
+
+/// 2.4G 信道范围，实际可用的信道还要看国家码
+pub const MIN_CHANNEL: u8 = 1;
+pub const MAX_CHANNEL: u8 = 14;
+/// 跳信道时每个信道的驻留时间范围（毫秒）
+pub const MIN_DWELL_MS: u32 = 50;
+pub const MAX_DWELL_MS: u32 = 60_000;
+pub const DEFAULT_DWELL_MS: u32 = 1000;
+/// 发送间隔范围（毫秒）
+pub const MIN_INTERVAL_MS: u32 = 20;
+pub const MAX_INTERVAL_MS: u32 = 60_000;
+/// UAS ID 最长 20 字节
+pub const MAX_UAS_ID_LENGTH: usize = 20;
+/// 运营人 ID 最长 20 字节
+pub const MAX_OPERATOR_ID_LENGTH: usize = 20;
+/// 自我描述最长 23 字节
+pub const MAX_SELF_ID_LENGTH: usize = 23;
+/// UA 运行类别、等级各占 4 位
+pub const MAX_UA_CATEGORY: u8 = 0x0F;
+pub const MAX_UA_CLASS: u8 = 0x0F;
+
+/// 遵循的标准
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StandardProfile {
+    #[default]
+    Gb = 0,      // 国标/仿大疆
+    Astm = 1,    // ASTM F3411
+    AsdStan = 2, // ASD-STAN EN 4709-002
+    French = 3,  // 法国电子信号，信标用 TLV 厂商元素
+}
+
+impl StandardProfile {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(StandardProfile::Gb),
+            1 => Some(StandardProfile::Astm),
+            2 => Some(StandardProfile::AsdStan),
+            3 => Some(StandardProfile::French),
+            _ => None,
+        }
+    }
+}
+
+/// 运行区域，对应系统报文里的可选字段
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OperationArea {
+    pub operation_count: u16, // 运行区域计数
+    pub operation_radius: u8, // 运行区域半径 (*10)
+    pub altitude_upper: u16,  // 运行区域高度上限
+    pub altitude_lower: u16,  // 运行区域高度下限
+}
+
+/// 配置错误
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    InvalidUasId,        // 空、超长或含非 ASCII 字符
+    InvalidOperatorId,   // 超长或含非 ASCII 字符
+    InvalidMacAddress,   // 组播或全 0 地址
+    InvalidSelfId,       // 超长或含非 ASCII 字符
+    InvalidChannel(u8),  // 信道超出 1-14 或所在国家不允许
+    InvalidCountry,      // 不在法规表里的国家码
+    InvalidDwell(u32),   // 驻留时间超出范围
+    InvalidTxPower(i8),  // 发射功率超出芯片范围或所在国家的上限
+    InvalidInterval(u32), // 间隔超出范围
+    InvalidPosition,     // 经纬度超出范围
+    InvalidClassification, // 运行类别或等级超出 4 位
+}
+
+/// 发射机运行时配置，由控制台修改，下一个信标生效
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransmitterConfig {
+    pub uas_id: String,       // UAS 识别身份信息
+    pub id_type: u8,          // ID 类型
+    pub ua_type: u8,          // UA 类型
+    pub operator_id: String,  // 运营人 ID，可为空
+    pub self_id: String,      // 自我描述，可为空
+    pub profile: StandardProfile,
+    pub ua_category: u8,      // UA 运行类别，欧盟 1-3 为开放/特定/审定类
+    pub ua_class: u8,         // UA 等级，欧盟 1-7 为 C0-C6
+    pub operation_area: OperationArea,
+    pub mac_address: [u8; 6], // 发射地址，同时作为 BSSID；策略不是 Fixed 时开机按策略重新生成
+    pub mac_policy: MacPolicy,
+    pub channel: u8,          // 信道，不跳信道时固定在这个信道
+    pub country: [u8; 2],     // 国家码，决定可用信道
+    pub channel_plan: ChannelPlan,
+    pub dwell_ms: u32,        // 跳信道时每个信道的驻留时间（毫秒）
+    pub tx_power_dbm: i8,     // Wi-Fi 发射功率（dBm）
+    pub rate: PhyRate,        // 信标发射速率，也是信标声明的唯一速率
+    pub interval_ms: u32,     // 信标发送间隔（毫秒）
+    pub latitude: i32,        // 纬度（1e-7 度）
+    pub longitude: i32,       // 经度（1e-7 度）
+    pub transmitting: bool,   // 是否正在发射
+}
+
+impl Default for TransmitterConfig {
+    fn default() -> Self {
+        Self {
+            uas_id: DEFAULT_UAS_ID.to_string(),
+            id_type: DEFAULT_ID_TYPE,
+            ua_type: DEFAULT_UA_TYPE,
+            operator_id: String::new(),
+            self_id: String::new(),
+            profile: DEFAULT_PROFILE,
+            ua_category: 0,
+            ua_class: 0,
+            operation_area: OperationArea { operation_count: 1, ..Default::default() },
+            mac_address: DEFAULT_MAC_ADDRESS,
+            mac_policy: DEFAULT_MAC_POLICY,
+            channel: DEFAULT_CHANNEL,
+            country: DEFAULT_COUNTRY,
+            channel_plan: ChannelPlan::Fixed,
+            dwell_ms: DEFAULT_DWELL_MS,
+            tx_power_dbm: DEFAULT_TX_POWER_DBM,
+            rate: DEFAULT_RATE,
+            interval_ms: DEFAULT_INTERVAL_MS,
+            latitude: DEFAULT_LATITUDE,
+            longitude: DEFAULT_LONGITUDE,
+            transmitting: true,
+        }
+    }
+}
+
+impl TransmitterConfig {
+    pub fn set_uas_id(&mut self, uas_id: &str) -> Result<(), ConfigError> {
+        if uas_id.is_empty()
+            || uas_id.len() > MAX_UAS_ID_LENGTH
+            || !uas_id.bytes().all(|b| b.is_ascii_graphic())
+        {
+            return Err(ConfigError::InvalidUasId);
+        }
+        self.uas_id = uas_id.to_string();
+        Ok(())
+    }
+
+    pub fn set_operator_id(&mut self, operator_id: &str) -> Result<(), ConfigError> {
+        if operator_id.len() > MAX_OPERATOR_ID_LENGTH || !operator_id.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(ConfigError::InvalidOperatorId);
+        }
+        self.operator_id = operator_id.to_string();
+        Ok(())
+    }
+
+    pub fn set_self_id(&mut self, self_id: &str) -> Result<(), ConfigError> {
+        if self_id.len() > MAX_SELF_ID_LENGTH || !self_id.bytes().all(|b| b == b' ' || b.is_ascii_graphic()) {
+            return Err(ConfigError::InvalidSelfId);
+        }
+        self.self_id = self_id.to_string();
+        Ok(())
+    }
+
+    /// 固定使用 mac 作发射地址
+    pub fn set_mac_address(&mut self, mac: [u8; 6]) -> Result<(), ConfigError> {
+        if !is_transmitter_address(mac) {
+            return Err(ConfigError::InvalidMacAddress);
+        }
+        self.mac_address = mac;
+        self.mac_policy = MacPolicy::Fixed;
+        Ok(())
+    }
+
+    /// 按 MAC 策略更新发射地址，factory 为芯片出厂 MAC，random 为本次上电生成的随机数；
+    /// Fixed 时保持不变。换了 UAS ID 或策略后要重新调用
+    pub fn apply_mac_policy(&mut self, factory: [u8; 6], random: [u8; 6]) {
+        self.mac_address = match self.mac_policy {
+            MacPolicy::Fixed => self.mac_address,
+            MacPolicy::Factory => factory,
+            MacPolicy::FromUasId => mac_from_uas_id(&self.uas_id),
+            MacPolicy::Random => locally_administered(random),
+        };
+    }
+
+    pub fn set_channel(&mut self, channel: u8) -> Result<(), ConfigError> {
+        if !(MIN_CHANNEL..=MAX_CHANNEL).contains(&channel) || !self.regulatory_domain()?.allows(channel) {
+            return Err(ConfigError::InvalidChannel(channel));
+        }
+        self.channel = channel;
+        Ok(())
+    }
+
+    /// 换国家码，当前信道、信道计划和发射功率必须在新国家允许的范围内
+    pub fn set_country(&mut self, country: &str) -> Result<(), ConfigError> {
+        let code: [u8; 2] = country.as_bytes().try_into().map_err(|_| ConfigError::InvalidCountry)?;
+        let domain = regulatory_domain(code.map(|b| b.to_ascii_uppercase())).ok_or(ConfigError::InvalidCountry)?;
+        if let Some(&channel) = self.channel_plan.channels(self.channel, domain).iter().find(|&&c| !domain.allows(c)) {
+            return Err(ConfigError::InvalidChannel(channel));
+        }
+        if self.tx_power_dbm > domain.max_tx_power_dbm {
+            return Err(ConfigError::InvalidTxPower(self.tx_power_dbm));
+        }
+        self.country = domain.country;
+        Ok(())
+    }
+
+    pub fn set_channel_plan(&mut self, plan: ChannelPlan) -> Result<(), ConfigError> {
+        let domain = self.regulatory_domain()?;
+        if let Some(&channel) = plan.channels(self.channel, domain).iter().find(|&&c| !domain.allows(c)) {
+            return Err(ConfigError::InvalidChannel(channel));
+        }
+        self.channel_plan = plan;
+        Ok(())
+    }
+
+    pub fn set_dwell(&mut self, dwell_ms: u32) -> Result<(), ConfigError> {
+        if !(MIN_DWELL_MS..=MAX_DWELL_MS).contains(&dwell_ms) {
+            return Err(ConfigError::InvalidDwell(dwell_ms));
+        }
+        self.dwell_ms = dwell_ms;
+        Ok(())
+    }
+
+    pub fn set_tx_power(&mut self, tx_power_dbm: i8) -> Result<(), ConfigError> {
+        if !(MIN_TX_POWER_DBM..=MAX_TX_POWER_DBM).contains(&tx_power_dbm)
+            || tx_power_dbm > self.regulatory_domain()?.max_tx_power_dbm
+        {
+            return Err(ConfigError::InvalidTxPower(tx_power_dbm));
+        }
+        self.tx_power_dbm = tx_power_dbm;
+        Ok(())
+    }
+
+    /// 国家码对应的法规，不在法规表里时报错
+    pub fn regulatory_domain(&self) -> Result<&'static RegulatoryDomain, ConfigError> {
+        regulatory_domain(self.country).ok_or(ConfigError::InvalidCountry)
+    }
+
+    /// 信标里的国家码元素，声明所在国家的全部信道和最大发射功率
+    pub fn country_element(&self) -> Result<CountryElement, ConfigError> {
+        let domain = self.regulatory_domain()?;
+        Ok(CountryElement {
+            country: domain.country,
+            environment: b' ',
+            first_channel: MIN_CHANNEL,
+            channel_count: domain.last_channel,
+            max_tx_power: domain.max_tx_power_dbm,
+        })
+    }
+
+    /// 实际轮换的信道，去掉所在国家不允许的；都不允许时退回 1 信道
+    pub fn hop_channels(&self) -> Result<Vec<u8>, ConfigError> {
+        let domain = self.regulatory_domain()?;
+        let mut channels = self.channel_plan.channels(self.channel, domain);
+        channels.retain(|&channel| domain.allows(channel));
+        if channels.is_empty() {
+            channels.push(MIN_CHANNEL);
+        }
+        Ok(channels)
+    }
+
+    pub fn set_interval(&mut self, interval_ms: u32) -> Result<(), ConfigError> {
+        if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&interval_ms) {
+            return Err(ConfigError::InvalidInterval(interval_ms));
+        }
+        self.interval_ms = interval_ms;
+        Ok(())
+    }
+
+    pub fn set_classification(&mut self, ua_category: u8, ua_class: u8) -> Result<(), ConfigError> {
+        if ua_category > MAX_UA_CATEGORY || ua_class > MAX_UA_CLASS {
+            return Err(ConfigError::InvalidClassification);
+        }
+        self.ua_category = ua_category;
+        self.ua_class = ua_class;
+        Ok(())
+    }
+
+    pub fn set_position(&mut self, latitude: i32, longitude: i32) -> Result<(), ConfigError> {
+        if !(-900_000_000..=900_000_000).contains(&latitude)
+            || !(-1_800_000_000..=1_800_000_000).contains(&longitude)
+        {
+            return Err(ConfigError::InvalidPosition);
+        }
+        self.latitude = latitude;
+        self.longitude = longitude;
+        Ok(())
+    }
+}
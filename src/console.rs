@@ -0,0 +1,414 @@
+use alloc::string::{String, ToString};
+
+use crate::channel::ChannelPlan;
+use crate::config::{ConfigError, StandardProfile, TransmitterConfig};
+use crate::mac::{parse_mac, MacPolicy};
+use crate::radio::PhyRate;
+
+/// 一行命令的最大长度
+pub const MAX_LINE_LENGTH: usize = 96;
+
+/// 控制台命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    SetUasId(String),                             // set uasid <id>
+    SetSelfId(String),                            // set selfid <文字>，可含空格
+    SetMacAddress([u8; 6]),                       // set mac <地址>，固定发射地址
+    SetMacPolicy(MacPolicy),                      // set mac <fixed|factory|uasid|random>
+    SetChannel(u8),                               // set channel <1-14>，受国家码限制
+    SetCountry(String),                           // set country <国家码>
+    SetChannelPlan(ChannelPlan),                  // set hop <1,6,11|all|off>
+    SetDwell(u32),                                // set dwell <ms>，跳信道时每个信道的驻留时间
+    SetTxPower(i8),                               // set power <dBm>
+    SetRate(PhyRate),                             // set rate <Mbps>，如 1、5.5、24
+    SetInterval(u32),                             // set interval <ms>
+    SetPosition { latitude: i32, longitude: i32 }, // set pos <纬度> <经度>，单位度
+    SetProfile(StandardProfile),                  // set profile <gb|astm|asd-stan|fr>
+    SetOperatorId(String),                        // set operator <id>
+    SetClassification { category: u8, class: u8 }, // set class <类别> <等级>，欧盟分类
+    Status,                                       // status
+    Report,                                       // report，各发送方式的合规报告
+    Start,                                        // start
+    Stop,                                         // stop
+    Save,                                         // save，写入 flash
+    Pcap(bool),                                   // pcap on|off，串口输出抓包数据
+    Swarm(u8),                                    // swarm <n>|off，模拟 n 架无人机
+    Help,                                         // help
+}
+
+/// 命令解析错误
+#[derive(Debug, PartialEq)]
+pub enum CommandError {
+    Empty,                         // 空行
+    UnknownCommand,                // 未知命令
+    MissingArgument(&'static str), // 缺少参数，参数名
+    InvalidArgument(&'static str), // 参数格式错误，参数名
+    TooManyArguments,              // 多余参数
+    Config(ConfigError),           // 参数超出配置允许范围
+}
+
+impl From<ConfigError> for CommandError {
+    fn from(e: ConfigError) -> Self {
+        CommandError::Config(e)
+    }
+}
+
+/// 解析一行命令，大小写不敏感，参数以空白分隔
+pub fn parse_command(line: &str) -> Result<Command, CommandError> {
+    let mut words = line.split_whitespace();
+    let verb = words.next().ok_or(CommandError::Empty)?;
+
+    let command = if verb.eq_ignore_ascii_case("set") {
+        let key = words.next().ok_or(CommandError::MissingArgument("key"))?;
+        if key.eq_ignore_ascii_case("uasid") {
+            let id = words.next().ok_or(CommandError::MissingArgument("uasid"))?;
+            Command::SetUasId(id.to_string())
+        } else if key.eq_ignore_ascii_case("selfid") {
+            // 描述文字取到行尾，单词间保留一个空格
+            let mut text = String::new();
+            for word in words.by_ref() {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(word);
+            }
+            Command::SetSelfId(text)
+        } else if key.eq_ignore_ascii_case("mac") {
+            let value = words.next().ok_or(CommandError::MissingArgument("mac"))?;
+            match MacPolicy::from_name(value) {
+                Some(policy) => Command::SetMacPolicy(policy),
+                None => Command::SetMacAddress(parse_mac(value).ok_or(CommandError::InvalidArgument("mac"))?),
+            }
+        } else if key.eq_ignore_ascii_case("channel") {
+            let value = words.next().ok_or(CommandError::MissingArgument("channel"))?;
+            Command::SetChannel(value.parse().map_err(|_| CommandError::InvalidArgument("channel"))?)
+        } else if key.eq_ignore_ascii_case("country") {
+            let value = words.next().ok_or(CommandError::MissingArgument("country"))?;
+            Command::SetCountry(value.to_string())
+        } else if key.eq_ignore_ascii_case("hop") {
+            let value = words.next().ok_or(CommandError::MissingArgument("hop"))?;
+            Command::SetChannelPlan(ChannelPlan::parse(value).ok_or(CommandError::InvalidArgument("hop"))?)
+        } else if key.eq_ignore_ascii_case("dwell") {
+            let value = words.next().ok_or(CommandError::MissingArgument("dwell"))?;
+            Command::SetDwell(value.parse().map_err(|_| CommandError::InvalidArgument("dwell"))?)
+        } else if key.eq_ignore_ascii_case("power") {
+            let value = words.next().ok_or(CommandError::MissingArgument("power"))?;
+            Command::SetTxPower(value.parse().map_err(|_| CommandError::InvalidArgument("power"))?)
+        } else if key.eq_ignore_ascii_case("rate") {
+            let value = words.next().ok_or(CommandError::MissingArgument("rate"))?;
+            Command::SetRate(PhyRate::from_name(value).ok_or(CommandError::InvalidArgument("rate"))?)
+        } else if key.eq_ignore_ascii_case("interval") {
+            let value = words.next().ok_or(CommandError::MissingArgument("interval"))?;
+            Command::SetInterval(value.parse().map_err(|_| CommandError::InvalidArgument("interval"))?)
+        } else if key.eq_ignore_ascii_case("profile") {
+            let value = words.next().ok_or(CommandError::MissingArgument("profile"))?;
+            Command::SetProfile(StandardProfile::from_name(value).ok_or(CommandError::InvalidArgument("profile"))?)
+        } else if key.eq_ignore_ascii_case("operator") {
+            let id = words.next().ok_or(CommandError::MissingArgument("operator"))?;
+            Command::SetOperatorId(id.to_string())
+        } else if key.eq_ignore_ascii_case("class") {
+            let category = words.next().ok_or(CommandError::MissingArgument("category"))?;
+            let class = words.next().ok_or(CommandError::MissingArgument("class"))?;
+            Command::SetClassification {
+                category: category.parse().map_err(|_| CommandError::InvalidArgument("category"))?,
+                class: class.parse().map_err(|_| CommandError::InvalidArgument("class"))?,
+            }
+        } else if key.eq_ignore_ascii_case("pos") {
+            let latitude = words.next().ok_or(CommandError::MissingArgument("latitude"))?;
+            let longitude = words.next().ok_or(CommandError::MissingArgument("longitude"))?;
+            Command::SetPosition {
+                latitude: parse_degrees(latitude).ok_or(CommandError::InvalidArgument("latitude"))?,
+                longitude: parse_degrees(longitude).ok_or(CommandError::InvalidArgument("longitude"))?,
+            }
+        } else {
+            return Err(CommandError::UnknownCommand);
+        }
+    } else if verb.eq_ignore_ascii_case("status") {
+        Command::Status
+    } else if verb.eq_ignore_ascii_case("report") {
+        Command::Report
+    } else if verb.eq_ignore_ascii_case("start") {
+        Command::Start
+    } else if verb.eq_ignore_ascii_case("stop") {
+        Command::Stop
+    } else if verb.eq_ignore_ascii_case("save") {
+        Command::Save
+    } else if verb.eq_ignore_ascii_case("pcap") {
+        let value = words.next().ok_or(CommandError::MissingArgument("pcap"))?;
+        if value.eq_ignore_ascii_case("on") {
+            Command::Pcap(true)
+        } else if value.eq_ignore_ascii_case("off") {
+            Command::Pcap(false)
+        } else {
+            return Err(CommandError::InvalidArgument("pcap"));
+        }
+    } else if verb.eq_ignore_ascii_case("swarm") {
+        let value = words.next().ok_or(CommandError::MissingArgument("swarm"))?;
+        if value.eq_ignore_ascii_case("off") {
+            Command::Swarm(0)
+        } else {
+            Command::Swarm(value.parse().map_err(|_| CommandError::InvalidArgument("swarm"))?)
+        }
+    } else if verb.eq_ignore_ascii_case("help") {
+        Command::Help
+    } else {
+        return Err(CommandError::UnknownCommand);
+    };
+
+    if words.next().is_some() {
+        return Err(CommandError::TooManyArguments);
+    }
+    Ok(command)
+}
+
+/// 十进制度数转为 1e-7 度的整数，不使用浮点，最多保留 7 位小数
+pub fn parse_degrees(text: &str) -> Option<i32> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+    if !int_part.bytes().all(|b| b.is_ascii_digit()) || !frac_part.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if int_part.len() > 3 || frac_part.len() > 7 {
+        return None;
+    }
+
+    let mut value: i64 = 0;
+    for b in int_part.bytes() {
+        value = value * 10 + (b - b'0') as i64;
+    }
+    let mut frac: i64 = 0;
+    for i in 0..7 {
+        let digit = frac_part.as_bytes().get(i).map_or(0, |b| (b - b'0') as i64);
+        frac = frac * 10 + digit;
+    }
+    value = value * 10_000_000 + frac;
+    if negative {
+        value = -value;
+    }
+    i32::try_from(value).ok()
+}
+
+impl Command {
+    /// 把命令作用到配置上，返回配置是否被修改（需要重建信标）
+    pub fn apply(&self, config: &mut TransmitterConfig) -> Result<bool, CommandError> {
+        match self {
+            Command::SetUasId(id) => config.set_uas_id(id)?,
+            Command::SetSelfId(text) => config.set_self_id(text)?,
+            Command::SetMacAddress(mac) => config.set_mac_address(*mac)?,
+            Command::SetMacPolicy(policy) => config.mac_policy = *policy,
+            Command::SetChannel(channel) => config.set_channel(*channel)?,
+            Command::SetCountry(country) => config.set_country(country)?,
+            Command::SetChannelPlan(plan) => config.set_channel_plan(plan.clone())?,
+            Command::SetDwell(dwell) => config.set_dwell(*dwell)?,
+            Command::SetTxPower(tx_power) => config.set_tx_power(*tx_power)?,
+            Command::SetRate(rate) => config.rate = *rate,
+            Command::SetInterval(interval) => config.set_interval(*interval)?,
+            Command::SetPosition { latitude, longitude } => config.set_position(*latitude, *longitude)?,
+            Command::SetProfile(profile) => config.profile = *profile,
+            Command::SetOperatorId(id) => config.set_operator_id(id)?,
+            Command::SetClassification { category, class } => config.set_classification(*category, *class)?,
+            Command::Start => config.transmitting = true,
+            Command::Stop => config.transmitting = false,
+            Command::Status | Command::Report | Command::Save | Command::Pcap(_) | Command::Swarm(_) | Command::Help => {
+                return Ok(false)
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// 把串口收到的字节拼成行，遇到 '\r' 或 '\n' 结束一行
+pub struct LineBuffer {
+    buffer: [u8; MAX_LINE_LENGTH],
+    length: usize,
+    overflow: bool, // 本行超长，整行丢弃
+}
+
+impl Default for LineBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineBuffer {
+    pub const fn new() -> Self {
+        Self { buffer: [0; MAX_LINE_LENGTH], length: 0, overflow: false }
+    }
+
+    /// 输入一个字节，凑满一行时返回该行（不含换行符），空行与超长行不返回
+    pub fn push(&mut self, byte: u8) -> Option<&str> {
+        match byte {
+            b'\r' | b'\n' => {
+                let length = core::mem::replace(&mut self.length, 0);
+                let overflow = core::mem::replace(&mut self.overflow, false);
+                if overflow || length == 0 {
+                    return None;
+                }
+                core::str::from_utf8(&self.buffer[..length]).ok()
+            }
+            // 退格
+            0x08 | 0x7f => {
+                self.length = self.length.saturating_sub(1);
+                None
+            }
+            _ => {
+                if self.length < MAX_LINE_LENGTH {
+                    self.buffer[self.length] = byte;
+                    self.length += 1;
+                } else {
+                    self.overflow = true;
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_set_commands() {
+        assert_eq!(parse_command("set uasid 1581F7FVC251A00CQ211"), Ok(Command::SetUasId("1581F7FVC251A00CQ211".to_string())));
+        assert_eq!(parse_command("SET Channel 11"), Ok(Command::SetChannel(11)));
+        assert_eq!(parse_command("set selfid  Survey   flight"), Ok(Command::SetSelfId("Survey flight".to_string())));
+        assert_eq!(parse_command("  set interval 100  "), Ok(Command::SetInterval(100)));
+        assert_eq!(
+            parse_command("set pos 41.7144677 -123.48"),
+            Ok(Command::SetPosition { latitude: 417144677, longitude: -1234800000 })
+        );
+        assert_eq!(parse_command("set profile ASD-STAN"), Ok(Command::SetProfile(StandardProfile::AsdStan)));
+        assert_eq!(parse_command("set operator FIN87astrdge12k8"), Ok(Command::SetOperatorId("FIN87astrdge12k8".to_string())));
+        assert_eq!(parse_command("set class 1 2"), Ok(Command::SetClassification { category: 1, class: 2 }));
+        assert_eq!(parse_command("set country us"), Ok(Command::SetCountry("us".to_string())));
+        assert_eq!(parse_command("set hop 1,6,11"), Ok(Command::SetChannelPlan(ChannelPlan::List(alloc::vec![1, 6, 11]))));
+        assert_eq!(parse_command("set hop all"), Ok(Command::SetChannelPlan(ChannelPlan::AllAllowed)));
+        assert_eq!(parse_command("set dwell 300"), Ok(Command::SetDwell(300)));
+    }
+
+    #[test]
+    fn parses_simple_commands() {
+        assert_eq!(parse_command("status"), Ok(Command::Status));
+        assert_eq!(parse_command("report"), Ok(Command::Report));
+        assert_eq!(parse_command("start"), Ok(Command::Start));
+        assert_eq!(parse_command("stop"), Ok(Command::Stop));
+        assert_eq!(parse_command("save"), Ok(Command::Save));
+        assert_eq!(parse_command("help"), Ok(Command::Help));
+        assert_eq!(parse_command("pcap ON"), Ok(Command::Pcap(true)));
+        assert_eq!(parse_command("pcap off"), Ok(Command::Pcap(false)));
+        assert_eq!(parse_command("swarm 8"), Ok(Command::Swarm(8)));
+        assert_eq!(parse_command("swarm OFF"), Ok(Command::Swarm(0)));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(parse_command("   "), Err(CommandError::Empty));
+        assert_eq!(parse_command("launch"), Err(CommandError::UnknownCommand));
+        assert_eq!(parse_command("set ssid foo"), Err(CommandError::UnknownCommand));
+        assert_eq!(parse_command("set channel"), Err(CommandError::MissingArgument("channel")));
+        assert_eq!(parse_command("set channel six"), Err(CommandError::InvalidArgument("channel")));
+        assert_eq!(parse_command("set pos 41.7"), Err(CommandError::MissingArgument("longitude")));
+        assert_eq!(parse_command("set pos 41.12345678 0"), Err(CommandError::InvalidArgument("latitude")));
+        assert_eq!(parse_command("stop now"), Err(CommandError::TooManyArguments));
+        assert_eq!(parse_command("set profile faa"), Err(CommandError::InvalidArgument("profile")));
+        assert_eq!(parse_command("set class 1"), Err(CommandError::MissingArgument("class")));
+        assert_eq!(parse_command("swarm many"), Err(CommandError::InvalidArgument("swarm")));
+        assert_eq!(parse_command("set hop 1,6,16"), Err(CommandError::InvalidArgument("hop")));
+    }
+
+    #[test]
+    fn apply_validates_ranges() {
+        let mut config = TransmitterConfig::default();
+        assert_eq!(Command::SetChannel(14).apply(&mut config), Err(CommandError::Config(ConfigError::InvalidChannel(14))));
+        assert_eq!(Command::SetChannel(1).apply(&mut config), Ok(true));
+        assert_eq!(config.channel, 1);
+        assert_eq!(
+            Command::SetPosition { latitude: 910_000_000, longitude: 0 }.apply(&mut config),
+            Err(CommandError::Config(ConfigError::InvalidPosition))
+        );
+        assert_eq!(Command::Stop.apply(&mut config), Ok(true));
+        assert!(!config.transmitting);
+        assert_eq!(Command::Status.apply(&mut config), Ok(false));
+        assert_eq!(
+            Command::SetClassification { category: 16, class: 0 }.apply(&mut config),
+            Err(CommandError::Config(ConfigError::InvalidClassification))
+        );
+        assert_eq!(Command::SetProfile(StandardProfile::Astm).apply(&mut config), Ok(true));
+        assert_eq!(config.profile, StandardProfile::Astm);
+    }
+
+    #[test]
+    fn country_restricts_channels() {
+        let mut config = TransmitterConfig::default();
+        assert_eq!(Command::SetChannel(13).apply(&mut config), Ok(true));
+        // 美国不能用 13 信道，先换信道才能换国家
+        assert_eq!(
+            Command::SetCountry("us".to_string()).apply(&mut config),
+            Err(CommandError::Config(ConfigError::InvalidChannel(13)))
+        );
+        assert_eq!(Command::SetChannel(11).apply(&mut config), Ok(true));
+        assert_eq!(Command::SetCountry("us".to_string()).apply(&mut config), Ok(true));
+        assert_eq!(config.country, *b"US");
+        assert_eq!(
+            Command::SetChannelPlan(ChannelPlan::List(alloc::vec![1, 6, 12])).apply(&mut config),
+            Err(CommandError::Config(ConfigError::InvalidChannel(12)))
+        );
+        assert_eq!(Command::SetChannelPlan(ChannelPlan::AllAllowed).apply(&mut config), Ok(true));
+        assert_eq!(config.hop_channels(), Ok((1..=11).collect::<Vec<u8>>()));
+        assert_eq!(Command::SetCountry("XX".to_string()).apply(&mut config), Err(CommandError::Config(ConfigError::InvalidCountry)));
+        assert_eq!(Command::SetDwell(10).apply(&mut config), Err(CommandError::Config(ConfigError::InvalidDwell(10))));
+    }
+
+    #[test]
+    fn mac_address_and_policy() {
+        assert_eq!(parse_command("set mac 02:11:22:33:44:55"), Ok(Command::SetMacAddress([0x02, 0x11, 0x22, 0x33, 0x44, 0x55])));
+        assert_eq!(parse_command("set mac random"), Ok(Command::SetMacPolicy(MacPolicy::Random)));
+        assert_eq!(parse_command("set mac 01:00:5e:00:00:01"), Err(CommandError::InvalidArgument("mac")));
+
+        let mut config = TransmitterConfig::default();
+        assert_eq!(Command::SetMacPolicy(MacPolicy::FromUasId).apply(&mut config), Ok(true));
+        config.apply_mac_policy([0x10; 6], [0x21; 6]);
+        assert_eq!(config.mac_address, crate::mac::mac_from_uas_id(&config.uas_id));
+        assert_eq!(Command::SetMacPolicy(MacPolicy::Random).apply(&mut config), Ok(true));
+        config.apply_mac_policy([0x10; 6], [0x21; 6]);
+        assert_eq!(config.mac_address, [0x22, 0x21, 0x21, 0x21, 0x21, 0x21]);
+        // 固定地址不受策略更新影响
+        assert_eq!(Command::SetMacAddress([0x02, 0, 0, 0, 0, 1]).apply(&mut config), Ok(true));
+        config.apply_mac_policy([0x10; 6], [0x21; 6]);
+        assert_eq!((config.mac_policy, config.mac_address), (MacPolicy::Fixed, [0x02, 0, 0, 0, 0, 1]));
+    }
+
+    #[test]
+    fn power_and_rate() {
+        assert_eq!(parse_command("set power 8"), Ok(Command::SetTxPower(8)));
+        assert_eq!(parse_command("set rate 5.5"), Ok(Command::SetRate(PhyRate::Mbps5_5)));
+        assert_eq!(parse_command("set rate 7"), Err(CommandError::InvalidArgument("rate")));
+        assert_eq!(parse_command("set power high"), Err(CommandError::InvalidArgument("power")));
+
+        let mut config = TransmitterConfig::default();
+        assert_eq!(Command::SetTxPower(8).apply(&mut config), Ok(true));
+        assert_eq!(Command::SetRate(PhyRate::Mbps24).apply(&mut config), Ok(true));
+        assert_eq!((config.tx_power_dbm, config.rate), (8, PhyRate::Mbps24));
+        assert_eq!(Command::SetTxPower(21).apply(&mut config), Err(CommandError::Config(ConfigError::InvalidTxPower(21))));
+        assert_eq!(Command::SetTxPower(1).apply(&mut config), Err(CommandError::Config(ConfigError::InvalidTxPower(1))));
+    }
+
+    #[test]
+    fn line_buffer_splits_lines() {
+        let mut buffer = LineBuffer::new();
+        let mut lines = Vec::new();
+        for &b in b"stat\x08tus\r\n\nstart\n" {
+            if let Some(line) = buffer.push(b) {
+                lines.push(line.to_string());
+            }
+        }
+        assert_eq!(lines, ["status", "start"]);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::schedule::MessageKind;
+
+/// 消息种类数，按 MessageKind 的顺序各占一个计数器
+const MESSAGE_KIND_COUNT: usize = 5;
+
+/// 一个发射身份在一种发送方式上的计数器，属于广播上下文而不是报文：
+/// 整包计数器每发出一包加一，蓝牙传统广播一次一条消息，按消息类型各自计数
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MessageCounters {
+    pack: u8,
+    messages: [u8; MESSAGE_KIND_COUNT],
+}
+
+impl MessageCounters {
+    pub const fn new() -> Self {
+        Self { pack: 0, messages: [0; MESSAGE_KIND_COUNT] }
+    }
+
+    /// 下一个整包的计数器
+    pub fn pack(&self) -> u8 {
+        self.pack
+    }
+
+    /// 下一条 kind 类型单条消息的计数器
+    pub fn message(&self, kind: MessageKind) -> u8 {
+        self.messages[kind as usize]
+    }
+
+    /// 整包已发出
+    pub fn pack_sent(&mut self) {
+        self.pack = self.pack.wrapping_add(1);
+    }
+
+    /// 一条单条消息已发出
+    pub fn message_sent(&mut self, kind: MessageKind) {
+        let counter = &mut self.messages[kind as usize];
+        *counter = counter.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_are_independent_and_wrap() {
+        let mut counters = MessageCounters::new();
+        counters.pack_sent();
+        counters.message_sent(MessageKind::Location);
+        counters.message_sent(MessageKind::Location);
+        assert_eq!((counters.pack(), counters.message(MessageKind::Location)), (1, 2));
+        assert_eq!((counters.message(MessageKind::BasicId), counters.message(MessageKind::OperatorId)), (0, 0));
+        for _ in 0..255 {
+            counters.pack_sent();
+        }
+        assert_eq!(counters.pack(), 0);
+    }
+}
@@ -0,0 +1,398 @@
+use crate::config::StandardProfile;
+use crate::message::packet_message::PacketMessage;
+
+/// 一次定位，由 GGA 和 RMC 语句合并而成
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GnssFix {
+    pub latitude: i32,                    // 纬度（1e-7 度）
+    pub longitude: i32,                   // 经度（1e-7 度）
+    pub altitude_dm: Option<i32>,         // 海拔（分米），来自 GGA
+    pub geoid_separation_dm: Option<i32>, // 大地水准面差距（分米），来自 GGA，海拔加上它为椭球高
+    pub speed_cm_s: Option<u32>,          // 地速（厘米/秒），来自 RMC
+    pub track_deg: Option<u16>,           // 航迹角（度，0-359），来自 RMC
+    pub unix_time: Option<u32>,           // UTC 时间（Unix 秒），来自 RMC
+    pub hour_tenths: Option<u16>,         // 整点以来的 0.1 秒（UTC），来自最近一条有定位的语句
+}
+
+impl GnssFix {
+    /// WGS-84 椭球高（分米），GGA 没给大地水准面差距时不知道
+    pub fn ellipsoid_height_dm(&self) -> Option<i32> {
+        Some(self.altitude_dm? + self.geoid_separation_dm?)
+    }
+
+    /// 把定位写进整包的位置报文：几何高度为椭球高，不知道时记为无效；
+    /// 有 UTC 时间时同时作为位置报文和系统报文的时间戳，系统报文按标准的纪元换算
+    pub fn apply(&self, packet: &mut PacketMessage, profile: StandardProfile) {
+        let position = &mut packet.position_message;
+        position.latitude = self.latitude;
+        position.longitude = self.longitude;
+        position.geometric_altitude = match self.ellipsoid_height_dm() {
+            Some(height_dm) => encode_altitude(height_dm),
+            None => ALTITUDE_UNKNOWN,
+        };
+        if let Some(speed_cm_s) = self.speed_cm_s {
+            position.set_track_and_speed(self.track_deg.unwrap_or(0), speed_cm_s);
+        }
+        if let Some(hour_tenths) = self.hour_tenths {
+            position.timestamp = hour_tenths;
+        }
+        if let Some(unix_time) = self.unix_time {
+            packet.system_message.timestamp = profile.system_timestamp(unix_time);
+        }
+    }
+}
+
+/// 高度无效时的编码，即 -1000 米
+pub const ALTITUDE_UNKNOWN: i16 = 0;
+/// 位置报文时间戳未知
+pub const TIMESTAMP_UNKNOWN: u16 = 0xFFFF;
+/// 运行状态：RID 系统故障（F3411-22a），丢失定位时使用
+pub const RUN_STATUS_SYSTEM_FAILURE: u8 = 4;
+
+/// 丢失定位后的位置报文：位置保持配置里的静态位置，运行状态标为系统故障，
+/// 精度、几何高度和时间戳记为未知，不再重放最后一次定位
+pub fn mark_fix_lost(packet: &mut PacketMessage) {
+    let position = &mut packet.position_message;
+    position.run_status = RUN_STATUS_SYSTEM_FAILURE;
+    position.geometric_altitude = ALTITUDE_UNKNOWN;
+    position.horizontal_accuracy = 0;
+    position.vertical_accuracy = 0;
+    position.speed_accuracy = 0;
+    position.timestamp = TIMESTAMP_UNKNOWN;
+}
+
+/// 高度编码为 (高度 + 1000 米) / 0.5 米
+pub fn encode_altitude(altitude_dm: i32) -> i16 {
+    ((altitude_dm + 10_000) / 5).clamp(0, i16::MAX as i32) as i16
+}
+
+/// NMEA 解析错误
+#[derive(Debug, PartialEq)]
+pub enum NmeaError {
+    NotNmea,                    // 不以 '$' 开头或没有校验和
+    Checksum,                   // 校验和不对
+    Unsupported,                // 不处理的语句
+    InvalidField(&'static str), // 字段格式错误，字段名
+}
+
+/// 解析出的语句，没有定位时 position 为 None
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NmeaSentence {
+    Gga { position: Option<(i32, i32)>, altitude_dm: Option<i32>, geoid_separation_dm: Option<i32>, hour_tenths: Option<u16> },
+    Rmc {
+        position: Option<(i32, i32)>,
+        speed_cm_s: Option<u32>,
+        track_deg: Option<u16>,
+        unix_time: Option<u32>,
+        hour_tenths: Option<u16>,
+    },
+}
+
+/// 解析一行 GGA 或 RMC 语句（任意发送者，如 GP、GN），必须带校验和
+pub fn parse_sentence(line: &str) -> Result<NmeaSentence, NmeaError> {
+    let body = line.trim_end().strip_prefix('$').ok_or(NmeaError::NotNmea)?;
+    let (body, checksum) = body.split_once('*').ok_or(NmeaError::NotNmea)?;
+    let expected = u8::from_str_radix(checksum, 16).map_err(|_| NmeaError::Checksum)?;
+    if checksum.len() != 2 || body.bytes().fold(0, |sum, b| sum ^ b) != expected {
+        return Err(NmeaError::Checksum);
+    }
+
+    let mut fields = body.split(',');
+    let address = fields.next().unwrap_or("");
+    let mut field = || fields.next().unwrap_or("");
+    // 地址按字节切分，非 ASCII 的地址不是 NMEA 语句
+    if address.len() != 5 || !address.is_ascii() {
+        return Err(NmeaError::Unsupported);
+    }
+    match &address[2..] {
+        "GGA" => {
+            let time = field();
+            let (latitude, north_south, longitude, east_west) = (field(), field(), field(), field());
+            let quality = field();
+            let (_satellites, _hdop) = (field(), field());
+            let (altitude, _altitude_unit, separation) = (field(), field(), field());
+            // 定位质量 0 为无效
+            if quality.is_empty() || quality == "0" {
+                return Ok(NmeaSentence::Gga { position: None, altitude_dm: None, geoid_separation_dm: None, hour_tenths: None });
+            }
+            let position = parse_position(latitude, north_south, longitude, east_west)?;
+            let altitude_dm = match altitude {
+                "" => None,
+                text => Some(parse_signed(text, 1).ok_or(NmeaError::InvalidField("altitude"))? as i32),
+            };
+            let geoid_separation_dm = match separation {
+                "" => None,
+                text => Some(parse_signed(text, 1).ok_or(NmeaError::InvalidField("separation"))? as i32),
+            };
+            Ok(NmeaSentence::Gga { position: Some(position), altitude_dm, geoid_separation_dm, hour_tenths: parse_hour_tenths(time) })
+        }
+        "RMC" => {
+            let time = field();
+            let status = field();
+            let (latitude, north_south, longitude, east_west) = (field(), field(), field(), field());
+            let (speed, course, date) = (field(), field(), field());
+            // 状态 V 为无效
+            if status != "A" {
+                return Ok(NmeaSentence::Rmc { position: None, speed_cm_s: None, track_deg: None, unix_time: None, hour_tenths: None });
+            }
+            let position = parse_position(latitude, north_south, longitude, east_west)?;
+            // 1 节 = 51.4444 厘米/秒，速度按千分之一节解析
+            let speed_cm_s = match speed {
+                "" => None,
+                text => Some((parse_fixed(text, 3).ok_or(NmeaError::InvalidField("speed"))? * 514_444 / 10_000_000) as u32),
+            };
+            let track_deg = match course {
+                "" => None,
+                text => Some(((parse_fixed(text, 1).ok_or(NmeaError::InvalidField("course"))? + 5) / 10 % 360) as u16),
+            };
+            Ok(NmeaSentence::Rmc {
+                position: Some(position),
+                speed_cm_s,
+                track_deg,
+                unix_time: parse_date_time(date, time),
+                hour_tenths: parse_hour_tenths(time),
+            })
+        }
+        _ => Err(NmeaError::Unsupported),
+    }
+}
+
+/// 合并接收机的 GGA 和 RMC：每条有定位的语句更新一次，另一种语句带的字段保留上次的值
+#[derive(Debug)]
+pub struct GnssReceiver {
+    fix: GnssFix,
+}
+
+impl Default for GnssReceiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GnssReceiver {
+    pub const fn new() -> Self {
+        Self {
+            fix: GnssFix {
+                latitude: 0,
+                longitude: 0,
+                altitude_dm: None,
+                geoid_separation_dm: None,
+                speed_cm_s: None,
+                track_deg: None,
+                unix_time: None,
+                hour_tenths: None,
+            },
+        }
+    }
+
+    /// 处理一行语句，有定位时返回更新后的定位，接收机报告没有定位时返回 None
+    pub fn push_line(&mut self, line: &str) -> Result<Option<GnssFix>, NmeaError> {
+        let position = match parse_sentence(line)? {
+            NmeaSentence::Gga { position, altitude_dm, geoid_separation_dm, hour_tenths } => {
+                if position.is_some() {
+                    self.fix.altitude_dm = altitude_dm;
+                    self.fix.geoid_separation_dm = geoid_separation_dm;
+                    self.fix.hour_tenths = hour_tenths;
+                }
+                position
+            }
+            NmeaSentence::Rmc { position, speed_cm_s, track_deg, unix_time, hour_tenths } => {
+                if position.is_some() {
+                    self.fix.speed_cm_s = speed_cm_s;
+                    self.fix.track_deg = track_deg;
+                    self.fix.unix_time = unix_time;
+                    self.fix.hour_tenths = hour_tenths;
+                }
+                position
+            }
+        };
+        Ok(position.map(|(latitude, longitude)| {
+            self.fix.latitude = latitude;
+            self.fix.longitude = longitude;
+            self.fix
+        }))
+    }
+}
+
+fn parse_position(latitude: &str, north_south: &str, longitude: &str, east_west: &str) -> Result<(i32, i32), NmeaError> {
+    let latitude = parse_coordinate(latitude, 2, north_south, "N", "S", 90).ok_or(NmeaError::InvalidField("latitude"))?;
+    let longitude = parse_coordinate(longitude, 3, east_west, "E", "W", 180).ok_or(NmeaError::InvalidField("longitude"))?;
+    Ok((latitude, longitude))
+}
+
+/// "dddmm.mmmm" 转为 1e-7 度，不使用浮点
+fn parse_coordinate(text: &str, degree_digits: usize, hemisphere: &str, positive: &str, negative: &str, limit: i64) -> Option<i32> {
+    let degrees = text.get(..degree_digits).filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))?;
+    let minutes_text = &text[degree_digits..];
+    if minutes_text.split('.').next()?.len() != 2 {
+        return None;
+    }
+    let minutes_e7 = parse_fixed(minutes_text, 7)?;
+    if minutes_e7 >= 60 * 10_000_000 {
+        return None;
+    }
+    let value = degrees.parse::<i64>().ok()? * 10_000_000 + (minutes_e7 + 30) / 60;
+    if value > limit * 10_000_000 {
+        return None;
+    }
+    match hemisphere {
+        h if h == positive => Some(value as i32),
+        h if h == negative => Some(-value as i32),
+        _ => None,
+    }
+}
+
+/// 无符号十进制小数转为 10^decimals 倍的整数，多余的小数位截掉
+fn parse_fixed(text: &str, decimals: u32) -> Option<i64> {
+    let (int_part, frac_part) = text.split_once('.').unwrap_or((text, ""));
+    if int_part.is_empty() || int_part.len() > 9 || !text.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        return None;
+    }
+    let mut value: i64 = int_part.parse().ok()?;
+    let mut frac = frac_part.bytes();
+    for _ in 0..decimals {
+        let digit = match frac.next() {
+            Some(b) if b.is_ascii_digit() => (b - b'0') as i64,
+            Some(_) => return None,
+            None => 0,
+        };
+        value = value * 10 + digit;
+    }
+    Some(value)
+}
+
+fn parse_signed(text: &str, decimals: u32) -> Option<i64> {
+    match text.strip_prefix('-') {
+        Some(rest) => parse_fixed(rest, decimals).map(|value| -value),
+        None => parse_fixed(text, decimals),
+    }
+}
+
+/// 时间 "hhmmss.ss" 转为整点以来的 0.1 秒，即位置报文的时间戳
+fn parse_hour_tenths(time: &str) -> Option<u16> {
+    let two_digits = |at: usize| time.get(at..at + 2)?.parse::<u16>().ok();
+    let (minute, second) = (two_digits(2)?, two_digits(4)?);
+    let tenths = match time.get(6..) {
+        None | Some("") => 0,
+        Some(fraction) => {
+            let digit = fraction.strip_prefix('.')?.bytes().next().unwrap_or(b'0');
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            (digit - b'0') as u16
+        }
+    };
+    if two_digits(0)? > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some(minute * 600 + second * 10 + tenths)
+}
+
+/// RMC 的日期 "ddmmyy" 和时间 "hhmmss.ss" 转为 Unix 秒，两位年份 80-99 为 19xx，其余为 20xx
+fn parse_date_time(date: &str, time: &str) -> Option<u32> {
+    let two_digits = |text: &str, at: usize| text.get(at..at + 2)?.parse::<u32>().ok();
+    let (day, month, year) = (two_digits(date, 0)?, two_digits(date, 2)?, two_digits(date, 4)?);
+    let year = if year >= 80 { 1900 + year } else { 2000 + year };
+    let (hour, minute, second) = (two_digits(time, 0)?, two_digits(time, 2)?, two_digits(time, 4)?);
+    if date.len() != 6 || !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    // 公历日期转为 1970-01-01 起的天数
+    let (y, m) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+    let era_day = 365 * y + y / 4 - y / 100 + y / 400 + (153 * m + 2) / 5 + day - 1;
+    let days = era_day - 719_468;
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TransmitterConfig;
+    use crate::profile::F3411_EPOCH_UNIX;
+
+    const GGA: &str = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+    const RMC: &str = "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A";
+
+    #[test]
+    fn gga_and_rmc_are_merged() {
+        let mut receiver = GnssReceiver::new();
+        let fix = receiver.push_line(GGA).unwrap().unwrap();
+        // 48°07.038' = 48.1173°，11°31.000' = 11.5166667°
+        assert_eq!((fix.latitude, fix.longitude, fix.altitude_dm, fix.speed_cm_s), (481_173_000, 115_166_667, Some(5454), None));
+
+        let fix = receiver.push_line(RMC).unwrap().unwrap();
+        // 22.4 节约 11.52 米/秒；1994-03-23 12:35:19 UTC
+        assert_eq!((fix.speed_cm_s, fix.track_deg, fix.unix_time), (Some(1152), Some(84), Some(764_426_119)));
+        assert_eq!(fix.altitude_dm, Some(5454));
+
+        // 椭球高 545.4 + 46.9 = 592.3 米；12:35:19 即整点后 35 分 19 秒
+        assert_eq!((fix.geoid_separation_dm, fix.ellipsoid_height_dm(), fix.hour_tenths), (Some(469), Some(5923), Some(21_190)));
+
+        let mut packet = PacketMessage::from_config(&TransmitterConfig::default());
+        fix.apply(&mut packet, StandardProfile::Gb);
+        let position = &packet.position_message;
+        assert_eq!((position.latitude, position.longitude, position.geometric_altitude), (481_173_000, 115_166_667, 3184));
+        assert_eq!((position.track_angle, position.ground_speed, position.timestamp), (84, 46, 21_190));
+        assert_eq!(packet.system_message.timestamp, 764_426_119);
+    }
+
+    #[test]
+    fn timestamps_and_height_follow_the_profile() {
+        let mut receiver = GnssReceiver::new();
+        receiver.push_line("$GNGGA,083015.40,5230.7407,N,00459.2593,E,1,12,0.8,-3.2,M,47.3,M,,*5B").unwrap();
+        let fix = receiver.push_line("$GNRMC,083015.40,A,5230.7407,N,00459.2593,E,010.0,270.0,181025,,,A*45").unwrap().unwrap();
+        // 2025-10-18 08:30:15.4 UTC
+        assert_eq!((fix.unix_time, fix.hour_tenths), (Some(1_760_776_215), Some(18_154)));
+
+        // 国标用 Unix 秒，F3411 从 2019-01-01 起算
+        let mut packet = PacketMessage::from_config(&TransmitterConfig::default());
+        fix.apply(&mut packet, StandardProfile::Gb);
+        assert_eq!(packet.system_message.timestamp, 1_760_776_215);
+        for profile in [StandardProfile::Astm, StandardProfile::AsdStan] {
+            fix.apply(&mut packet, profile);
+            assert_eq!(packet.system_message.timestamp, 1_760_776_215 - F3411_EPOCH_UNIX);
+        }
+        // 椭球高 -3.2 + 47.3 = 44.1 米
+        assert_eq!((packet.position_message.geometric_altitude, packet.position_message.timestamp), (2088, 18_154));
+
+        // 没有大地水准面差距时椭球高未知，不拿海拔冒充
+        let fix = receiver.push_line("$GPGGA,083015.40,5230.7407,N,00459.2593,E,1,12,0.8,-3.2,M,,M,,*5B").unwrap().unwrap();
+        assert_eq!((fix.altitude_dm, fix.ellipsoid_height_dm()), (Some(-32), None));
+        fix.apply(&mut packet, StandardProfile::Astm);
+        assert_eq!(packet.position_message.geometric_altitude, ALTITUDE_UNKNOWN);
+        assert_eq!(parse_hour_tenths("0830"), None);
+        assert_eq!(parse_hour_tenths("086015"), None);
+    }
+
+    #[test]
+    fn lost_fix_falls_back_to_configured_position() {
+        let config = TransmitterConfig::default();
+        let mut packet = PacketMessage::from_config(&config);
+        mark_fix_lost(&mut packet);
+        let position = &packet.position_message;
+        assert_eq!((position.latitude, position.longitude), (config.latitude, config.longitude));
+        assert_eq!((position.run_status, position.geometric_altitude, position.timestamp), (4, ALTITUDE_UNKNOWN, TIMESTAMP_UNKNOWN));
+        assert_eq!((position.horizontal_accuracy, position.vertical_accuracy, position.speed_accuracy), (0, 0, 0));
+    }
+
+    #[test]
+    fn southern_western_and_invalid_sentences() {
+        let mut receiver = GnssReceiver::new();
+        let fix = receiver.push_line("$GPRMC,235959.50,A,3351.5000,S,15112.6000,W,000.0,,311299,,,A*7B").unwrap().unwrap();
+        assert_eq!((fix.latitude, fix.longitude), (-338_583_333, -1_512_100_000));
+        assert_eq!((fix.speed_cm_s, fix.track_deg, fix.unix_time), (Some(0), None, Some(946_684_799)));
+
+        // 接收机还没定位
+        assert_eq!(receiver.push_line("$GNGGA,000000,,,,,0,00,99.99,,,,,,*56"), Ok(None));
+        assert_eq!(receiver.push_line("$GPGSV,1,1,00*79"), Err(NmeaError::Unsupported));
+        // 校验和对但地址不是 ASCII
+        assert_eq!(receiver.push_line("$AéBC,1*37"), Err(NmeaError::Unsupported));
+        assert_eq!(receiver.push_line(&GGA.replace("*47", "*48")), Err(NmeaError::Checksum));
+        assert_eq!(receiver.push_line(GGA.trim_end_matches("*47")), Err(NmeaError::NotNmea));
+        assert_eq!(receiver.push_line("GPGGA,123519"), Err(NmeaError::NotNmea));
+        for bad in ["4860.000", "48.07038", "4807.0x8"] {
+            assert_eq!(parse_position(bad, "N", "01131.000", "E"), Err(NmeaError::InvalidField("latitude")), "{}", bad);
+        }
+        assert_eq!(parse_position("4807.038", "N", "01131.000", "X"), Err(NmeaError::InvalidField("longitude")));
+    }
+}
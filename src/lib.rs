@@ -1 +1,22 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+pub mod beacon;
+pub mod channel;
+pub mod ble;
+pub mod config;
+pub mod console;
+pub mod counter;
+pub mod gnss;
+pub mod mac;
+pub mod message;
+pub mod nan;
+pub mod pcap;
+pub mod profile;
+pub mod radio;
+pub mod recovery;
+pub mod schedule;
+pub mod storage;
+pub mod swarm;
+pub mod transport;
@@ -0,0 +1,87 @@
+/// 发射地址的取法，选定的地址同时用作信标的发射地址和 BSSID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MacPolicy {
+    Fixed = 0,     // 配置里的地址
+    #[default]
+    Factory = 1,   // 芯片 efuse 里的出厂 MAC
+    FromUasId = 2, // 由 UAS ID 导出的本地管理地址，换板子地址不变
+    Random = 3,    // 每次上电随机生成的本地管理地址（隐私模式）
+}
+
+impl MacPolicy {
+    pub const ALL: [MacPolicy; 4] = [MacPolicy::Fixed, MacPolicy::Factory, MacPolicy::FromUasId, MacPolicy::Random];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MacPolicy::Fixed => "fixed",
+            MacPolicy::Factory => "factory",
+            MacPolicy::FromUasId => "uasid",
+            MacPolicy::Random => "random",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|policy| name.eq_ignore_ascii_case(policy.name()))
+    }
+}
+
+/// 能作为发射地址：单播且不全为 0
+pub fn is_transmitter_address(mac: [u8; 6]) -> bool {
+    mac[0] & 0x01 == 0 && mac != [0; 6]
+}
+
+/// 置本地管理位、清组播位
+pub fn locally_administered(mut mac: [u8; 6]) -> [u8; 6] {
+    mac[0] = (mac[0] & !0x01) | 0x02;
+    mac
+}
+
+/// 由 UAS ID 导出本地管理地址：取 FNV-1a 64 位散列的低 48 位
+pub fn mac_from_uas_id(uas_id: &str) -> [u8; 6] {
+    let hash = uas_id.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    let bytes = hash.to_be_bytes();
+    locally_administered([bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]])
+}
+
+/// 解析 "00:80:41:13:37:42" 或以 '-' 分隔的地址，只接受可作发射地址的
+pub fn parse_mac(text: &str) -> Option<[u8; 6]> {
+    let mut mac = [0u8; 6];
+    let mut parts = text.split([':', '-']);
+    for octet in mac.iter_mut() {
+        let part = parts.next()?;
+        if part.len() != 2 {
+            return None;
+        }
+        *octet = u8::from_str_radix(part, 16).ok()?;
+    }
+    (parts.next().is_none() && is_transmitter_address(mac)).then_some(mac)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_addresses_are_local_unicast() {
+        let mac = mac_from_uas_id("1581F5BKD21340001234");
+        assert_eq!(mac, mac_from_uas_id("1581F5BKD21340001234"));
+        assert_ne!(mac, mac_from_uas_id("1581F5BKD21340001235"));
+        assert_eq!(mac[0] & 0x03, 0x02);
+        assert_eq!(locally_administered([0xff; 6]), [0xfe, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        assert!(is_transmitter_address(mac));
+    }
+
+    #[test]
+    fn policy_names_and_address_parsing() {
+        assert_eq!(MacPolicy::from_name("UASID"), Some(MacPolicy::FromUasId));
+        assert!(MacPolicy::ALL.iter().all(|&policy| MacPolicy::from_u8(policy as u8) == Some(policy)));
+        assert_eq!(parse_mac("02-11-22-33-44-55"), Some([0x02, 0x11, 0x22, 0x33, 0x44, 0x55]));
+        for bad in ["01:00:5e:00:00:01", "00:00:00:00:00:00", "00:80:41:13:37", "00:80:41:13:37:42:00", "0:80:41:13:37:42", "zz:80:41:13:37:42"] {
+            assert_eq!(parse_mac(bad), None, "{}", bad);
+        }
+    }
+}
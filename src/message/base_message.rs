@@ -1,7 +1,7 @@
 
 use crate::message::message::MessageType;
 extern crate alloc;
-use super::message::Message;
+use super::message::{check_header, Message, MessageError};
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use alloc::vec;
@@ -36,18 +36,37 @@ impl Message for BaseMessage {
         let type_byte = (self.id_type << 4) | (self.ua_type & 0x0F);
         bytes.push(type_byte);
         
-        // 编码UAS ID（最多20字节）
-        let uas_bytes = self.uas_id.as_bytes().to_vec();
-        bytes.extend_from_slice(&uas_bytes);
+        // 编码UAS ID（最多20字节，超出部分截掉）
+        let uas_bytes = self.uas_id.as_bytes();
+        let id_len = uas_bytes.len().min(20);
+        bytes.extend_from_slice(&uas_bytes[..id_len]);
         
         //不足的位置写0
-        let id_len = uas_bytes.len();
-        let reserved = vec![0u8; 23-id_len];
-        bytes.extend(&reserved);
+        bytes.extend(vec![0u8; 20 - id_len]);
+        bytes.extend_from_slice(&self.reserved);
         
         bytes
     }
 
+    fn decode(bytes: &[u8]) -> Result<Self, MessageError> {
+        let body = check_header(bytes, MessageType::BaseMessageType, Self::EXPECTED_LENGTH)?;
+        let type_byte = body[0];
+
+        // UAS ID 占 20 字节，后面补 0
+        let id_bytes = &body[1..21];
+        let id_len = id_bytes.iter().position(|&b| b == 0).unwrap_or(id_bytes.len());
+        let uas_id = core::str::from_utf8(&id_bytes[..id_len])
+            .map_err(|e| MessageError::InvalidUtf8(id_bytes[e.valid_up_to()]))?;
+
+        let mut reserved = [0u8; 3];
+        reserved.copy_from_slice(&body[21..24]);
+        Ok(Self {
+            id_type: type_byte >> 4,
+            ua_type: type_byte & 0x0F,
+            uas_id: uas_id.to_string(),
+            reserved,
+        })
+    }
 }
 
 
@@ -0,0 +1,125 @@
+use alloc::vec::Vec;
+
+/// 整包可选的 CRC-16 算法，国标整包默认 XMODEM
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CrcAlgorithm {
+    #[default]
+    Xmodem,     // 多项式 0x1021，初值 0
+    CcittFalse, // 多项式 0x1021，初值 0xFFFF
+    Kermit,     // 多项式 0x1021 反射，初值 0
+    Modbus,     // 多项式 0x8005 反射，初值 0xFFFF
+}
+
+impl CrcAlgorithm {
+    /// 检测时依次尝试的算法
+    pub const ALL: [CrcAlgorithm; 4] = [CrcAlgorithm::Xmodem, CrcAlgorithm::CcittFalse, CrcAlgorithm::Kermit, CrcAlgorithm::Modbus];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CrcAlgorithm::Xmodem => "xmodem",
+            CrcAlgorithm::CcittFalse => "ccitt-false",
+            CrcAlgorithm::Kermit => "kermit",
+            CrcAlgorithm::Modbus => "modbus",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|algorithm| algorithm.name() == name)
+    }
+
+    pub fn calculate(self, bytes: &[u8]) -> u16 {
+        match self {
+            CrcAlgorithm::Xmodem => crc16::State::<crc16::XMODEM>::calculate(bytes),
+            CrcAlgorithm::CcittFalse => crc16::State::<crc16::CCITT_FALSE>::calculate(bytes),
+            CrcAlgorithm::Kermit => crc16::State::<crc16::KERMIT>::calculate(bytes),
+            CrcAlgorithm::Modbus => crc16::State::<crc16::MODBUS>::calculate(bytes),
+        }
+    }
+
+    pub fn verify(self, bytes: &[u8], checksum: u16) -> bool {
+        self.calculate(bytes) == checksum
+    }
+}
+
+/// 校验和覆盖的起点：编码时从计数器开始，检测时还尝试从整包头、从第一条消息开始
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumRange {
+    FromCounter = 0,
+    FromHeader = 1,
+    MessagesOnly = 4,
+}
+
+impl ChecksumRange {
+    pub const ALL: [ChecksumRange; 3] = [ChecksumRange::FromCounter, ChecksumRange::FromHeader, ChecksumRange::MessagesOnly];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumRange::FromCounter => "counter..messages",
+            ChecksumRange::FromHeader => "header..messages",
+            ChecksumRange::MessagesOnly => "messages",
+        }
+    }
+}
+
+/// 检测到的一种与抓包校验和一致的算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumMatch {
+    pub algorithm: CrcAlgorithm,
+    pub range: ChecksumRange,
+    pub big_endian: bool, // 校验和按大端存放
+}
+
+/// 整包里最后一条消息之后的位置，即校验和所在的偏移；长度不够时为 None
+pub fn checksum_offset(pack: &[u8]) -> Option<usize> {
+    let (size, quantity) = (*pack.get(2)? as usize, *pack.get(3)? as usize);
+    let offset = 4 + size * quantity;
+    (pack.len() >= offset + 2).then_some(offset)
+}
+
+/// 检测模式：用所有算法、覆盖范围和字节序计算抓到的整包，返回与其校验和一致的组合
+pub fn detect(pack: &[u8]) -> Vec<ChecksumMatch> {
+    let Some(offset) = checksum_offset(pack) else {
+        return Vec::new();
+    };
+    let stored = [pack[offset], pack[offset + 1]];
+    let mut matches = Vec::new();
+    for algorithm in CrcAlgorithm::ALL {
+        for range in ChecksumRange::ALL {
+            let checksum = algorithm.calculate(&pack[range as usize..offset]);
+            for big_endian in [false, true] {
+                let expected = if big_endian { checksum.to_be_bytes() } else { checksum.to_le_bytes() };
+                if expected == stored {
+                    matches.push(ChecksumMatch { algorithm, range, big_endian });
+                }
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_values() {
+        // 各算法对 "123456789" 的标准校验值
+        let check = b"123456789";
+        let values: Vec<u16> = CrcAlgorithm::ALL.iter().map(|algorithm| algorithm.calculate(check)).collect();
+        assert_eq!(values, [0x31c3, 0x29b1, 0x2189, 0x4b37]);
+        assert!(CrcAlgorithm::Modbus.verify(check, 0x4b37));
+        assert_eq!(CrcAlgorithm::from_name("ccitt-false"), Some(CrcAlgorithm::CcittFalse));
+        assert_eq!(CrcAlgorithm::from_name("crc32"), None);
+    }
+
+    #[test]
+    fn detect_finds_algorithm_range_and_order() {
+        let mut pack = [0u8; 4 + 25 + 5];
+        pack[..4].copy_from_slice(&[0x07, 0xf1, 0x19, 0x01]);
+        pack[4] = 0x12;
+        let checksum = CrcAlgorithm::Kermit.calculate(&pack[1..29]);
+        pack[29..31].copy_from_slice(&checksum.to_be_bytes());
+        assert!(detect(&pack).contains(&ChecksumMatch { algorithm: CrcAlgorithm::Kermit, range: ChecksumRange::FromHeader, big_endian: true }));
+        assert!(detect(&pack[..30]).is_empty());
+    }
+}
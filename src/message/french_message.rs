@@ -0,0 +1,184 @@
+use super::message::{Message, MessageError};
+use super::packet_message::PacketMessage;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// 法国“电子信号”信标的厂商元素：OUI 6A:5C:35，类型 1，负载为 TLV（类型、长度、值），多字节值为大端序
+pub const FRENCH_VENDOR_PREFIX: [u8; 4] = [0x6a, 0x5c, 0x35, 0x01];
+
+/// TLV 类型
+pub const TLV_VERSION: u8 = 1;
+pub const TLV_FR_ID: u8 = 2;
+pub const TLV_ANSI_ID: u8 = 3;
+pub const TLV_LATITUDE: u8 = 4;
+pub const TLV_LONGITUDE: u8 = 5;
+pub const TLV_ALTITUDE: u8 = 6;
+pub const TLV_HEIGHT: u8 = 7;
+pub const TLV_HOME_LATITUDE: u8 = 8;
+pub const TLV_HOME_LONGITUDE: u8 = 9;
+pub const TLV_GROUND_SPEED: u8 = 10;
+pub const TLV_HEADING: u8 = 11;
+
+/// 协议版本
+pub const FRENCH_PROTOCOL_VERSION: u8 = 1;
+/// 法国 ID：制造商代码 3 位、型号 3 位、序列号 24 位
+pub const FR_ID_LENGTH: usize = 30;
+/// ANSI/CTA-2063 序列号
+pub const ANSI_ID_LENGTH: usize = 20;
+
+/// 法国信标的内容，法国 ID 与 ANSI ID 至少要有一个
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrenchMessage {
+    pub version: u8,             // 协议版本
+    pub fr_id: Option<String>,   // 法国 ID，30 字节
+    pub ansi_id: Option<String>, // ANSI/CTA-2063 序列号，不足 20 字节补 0
+    pub latitude: i32,           // 纬度 (1e-5 度)
+    pub longitude: i32,          // 经度 (1e-5 度)
+    pub altitude: i16,           // 海拔高度 (米)
+    pub height: i16,             // 相对起飞点高度 (米)
+    pub home_latitude: i32,      // 起飞点纬度 (1e-5 度)
+    pub home_longitude: i32,     // 起飞点经度 (1e-5 度)
+    pub ground_speed: u8,        // 地速 (米/秒)
+    pub heading: u16,            // 真航向 (度, 0-359)
+}
+
+/// ASTM 高度编码：0.5 米一档，偏移 -1000 米
+fn astm_altitude_m(raw: i16) -> i16 {
+    (raw as u16 as i32 / 2 - 1000) as i16
+}
+
+/// 写一个 TLV
+fn push_tlv(bytes: &mut Vec<u8>, tlv_type: u8, value: &[u8]) {
+    bytes.push(tlv_type);
+    bytes.push(value.len() as u8);
+    bytes.extend_from_slice(value);
+}
+
+/// 定长 ASCII 字段，超出截掉，不足补 0
+fn padded(text: &str, length: usize) -> Vec<u8> {
+    let mut value = text.as_bytes()[..text.len().min(length)].to_vec();
+    value.resize(length, 0);
+    value
+}
+
+/// 去掉补齐的 0
+fn unpadded(value: &[u8]) -> Result<String, MessageError> {
+    let length = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+    core::str::from_utf8(&value[..length])
+        .map(ToString::to_string)
+        .map_err(|e| MessageError::InvalidUtf8(value[e.valid_up_to()]))
+}
+
+impl FrenchMessage {
+    /// 从整包换算：1e-7 度改为 1e-5 度，高度、速度按 ASTM 编码换成米和米/秒，起飞点取系统报文的位置
+    pub fn from_packet(packet: &PacketMessage) -> Self {
+        let position = &packet.position_message;
+        let system = &packet.system_message;
+        // 速度乘数为 0 时 0.25 米/秒一档，为 1 时 0.75 米/秒一档并偏移 63.75
+        let raw_speed = position.ground_speed as u8 as u16;
+        let ground_speed = match position.speed_multiplier {
+            0 => raw_speed / 4,
+            _ => (raw_speed * 3 + 255) / 4,
+        };
+        Self {
+            version: FRENCH_PROTOCOL_VERSION,
+            fr_id: None,
+            ansi_id: Some(packet.base_message.uas_id.clone()),
+            latitude: position.latitude / 100,
+            longitude: position.longitude / 100,
+            altitude: astm_altitude_m(position.geometric_altitude),
+            height: astm_altitude_m(position.ground_altitude),
+            home_latitude: system.latitude / 100,
+            home_longitude: system.longitude / 100,
+            ground_speed: ground_speed.min(u8::MAX as u16) as u8,
+            heading: position.calculate_full_track_angle() % 360,
+        }
+    }
+}
+
+impl Message for FrenchMessage {
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        push_tlv(&mut bytes, TLV_VERSION, &[self.version]);
+        if let Some(fr_id) = &self.fr_id {
+            push_tlv(&mut bytes, TLV_FR_ID, &padded(fr_id, FR_ID_LENGTH));
+        }
+        if let Some(ansi_id) = &self.ansi_id {
+            push_tlv(&mut bytes, TLV_ANSI_ID, &padded(ansi_id, ANSI_ID_LENGTH));
+        }
+        push_tlv(&mut bytes, TLV_LATITUDE, &self.latitude.to_be_bytes());
+        push_tlv(&mut bytes, TLV_LONGITUDE, &self.longitude.to_be_bytes());
+        push_tlv(&mut bytes, TLV_ALTITUDE, &self.altitude.to_be_bytes());
+        push_tlv(&mut bytes, TLV_HEIGHT, &self.height.to_be_bytes());
+        push_tlv(&mut bytes, TLV_HOME_LATITUDE, &self.home_latitude.to_be_bytes());
+        push_tlv(&mut bytes, TLV_HOME_LONGITUDE, &self.home_longitude.to_be_bytes());
+        push_tlv(&mut bytes, TLV_GROUND_SPEED, &[self.ground_speed]);
+        push_tlv(&mut bytes, TLV_HEADING, &self.heading.to_be_bytes());
+        bytes
+    }
+
+    // 解码厂商元素前缀之后的 TLV，顺序不限，不认识的类型跳过
+    fn decode(bytes: &[u8]) -> Result<Self, MessageError> {
+        let mut version = None;
+        let mut fr_id = None;
+        let mut ansi_id = None;
+        let mut fields = [None::<i32>; 9];
+        let mut offset = 0;
+        while offset < bytes.len() {
+            if bytes.len() < offset + 2 {
+                return Err(MessageError::InsufficientLength(offset + 2, bytes.len()));
+            }
+            let (tlv_type, length) = (bytes[offset], bytes[offset + 1] as usize);
+            let value = bytes
+                .get(offset + 2..offset + 2 + length)
+                .ok_or(MessageError::InsufficientLength(offset + 2 + length, bytes.len()))?;
+            offset += 2 + length;
+
+            let expected = match tlv_type {
+                TLV_VERSION | TLV_GROUND_SPEED => 1,
+                TLV_FR_ID => FR_ID_LENGTH,
+                TLV_ANSI_ID => ANSI_ID_LENGTH,
+                TLV_ALTITUDE | TLV_HEIGHT | TLV_HEADING => 2,
+                TLV_LATITUDE | TLV_LONGITUDE | TLV_HOME_LATITUDE | TLV_HOME_LONGITUDE => 4,
+                _ => continue,
+            };
+            if length != expected {
+                return Err(MessageError::InvalidTlv(tlv_type));
+            }
+            match tlv_type {
+                TLV_VERSION => version = Some(value[0]),
+                TLV_FR_ID => fr_id = Some(unpadded(value)?),
+                TLV_ANSI_ID => ansi_id = Some(unpadded(value)?),
+                _ => {
+                    // 数值字段统一按大端有符号数取出，下面再换成各自的类型
+                    let number = match *value {
+                        [a] => a as i32,
+                        [a, b] => i16::from_be_bytes([a, b]) as i32,
+                        [a, b, c, d] => i32::from_be_bytes([a, b, c, d]),
+                        _ => unreachable!(),
+                    };
+                    fields[(tlv_type - TLV_LATITUDE) as usize] = Some(number);
+                }
+            }
+        }
+
+        if fr_id.is_none() && ansi_id.is_none() {
+            return Err(MessageError::MissingMessage(TLV_FR_ID));
+        }
+        let field = |tlv_type: u8| fields[(tlv_type - TLV_LATITUDE) as usize].ok_or(MessageError::MissingMessage(tlv_type));
+        Ok(Self {
+            version: version.ok_or(MessageError::MissingMessage(TLV_VERSION))?,
+            fr_id,
+            ansi_id,
+            latitude: field(TLV_LATITUDE)?,
+            longitude: field(TLV_LONGITUDE)?,
+            altitude: field(TLV_ALTITUDE)? as i16,
+            height: field(TLV_HEIGHT)? as i16,
+            home_latitude: field(TLV_HOME_LATITUDE)?,
+            home_longitude: field(TLV_HOME_LONGITUDE)?,
+            ground_speed: field(TLV_GROUND_SPEED)? as u8,
+            heading: field(TLV_HEADING)? as u16,
+        })
+    }
+}
@@ -0,0 +1,58 @@
+extern crate alloc;
+use alloc::vec::Vec;
+// 公共消息错误类型
+#[derive(Debug, PartialEq)]
+pub enum MessageError {
+    InsufficientLength(usize, usize),  // 期望长度, 实际长度
+    InvalidUtf8(u8),        // UTF-8 格式错误
+    UnknownMessageType(u8),             // 未知消息类型
+    MissingMessage(u8),                 // 整包中缺少的消息类型，法国信标中缺少的 TLV 类型
+    InvalidTlv(u8),                     // TLV 长度与类型不符，类型
+    ChecksumMismatch(u16, u16),         // 整包校验和不对：期望校验, 实际校验
+}
+
+// 公共消息类型，大疆整包里有 3 种，ASTM 和欧盟的整包还有自我描述和运营人 ID
+#[derive(Debug, PartialEq)]
+pub enum MessageType {
+    BaseMessageType = 0,
+    PositionVectorMessageType = 1,
+    SelfIdMessageType = 3,
+    SystemMessageType = 4,
+    OperatorIdMessageType = 5,
+}
+
+impl TryFrom<u8> for MessageType {
+    type Error = MessageError;
+
+    // 从报文头高 4 位得到消息类型
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MessageType::BaseMessageType),
+            1 => Ok(MessageType::PositionVectorMessageType),
+            3 => Ok(MessageType::SelfIdMessageType),
+            4 => Ok(MessageType::SystemMessageType),
+            5 => Ok(MessageType::OperatorIdMessageType),
+            _ => Err(MessageError::UnknownMessageType(value)),
+        }
+    }
+}
+
+/// 检查长度和报文头（1 字节），返回报文头后 expected_length 字节的内容
+pub fn check_header(bytes: &[u8], message_type: MessageType, expected_length: usize) -> Result<&[u8], MessageError> {
+    if bytes.len() < expected_length + 1 {
+        return Err(MessageError::InsufficientLength(expected_length + 1, bytes.len()));
+    }
+    let found = bytes[0] >> 4;
+    if found != message_type as u8 {
+        return Err(MessageError::UnknownMessageType(found));
+    }
+    Ok(&bytes[1..expected_length + 1])
+}
+
+/// 所有消息类型必须实现的 trait
+pub trait Message {
+    // 从结构体到字节的编码
+    fn encode(&self) -> Vec<u8> ;
+    // 从字节解码，与 encode 对应
+    fn decode(bytes: &[u8]) -> Result<Self, MessageError> where Self: Sized;
+}
\ No newline at end of file
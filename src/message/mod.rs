@@ -0,0 +1,14 @@
+#[allow(clippy::module_inception)]
+pub mod message;
+pub mod base_message;
+pub mod position_vector_message;
+pub mod system_message;
+pub mod self_id_message;
+pub mod operator_id_message;
+pub mod packet_message;
+pub mod french_message;
+pub mod checksum;
+#[cfg(test)]
+mod vectors;
+#[cfg(test)]
+mod roundtrip;
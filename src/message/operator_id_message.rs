@@ -0,0 +1,57 @@
+use crate::message::message::MessageType;
+
+use super::message::{check_header, Message, MessageError};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 运营人 ID 报文，欧盟要求发送运营人注册号
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorIdMessage {
+    pub operator_id_type: u8, // ID 类型 (0 = 民航局注册号)
+    pub operator_id: String,  // 运营人 ID，最多 20 字节 ASCII
+    pub reserved: [u8; 3],    // 预留
+}
+
+impl OperatorIdMessage {
+    pub const MESSAGE_TYPE: u8 = 0x05;
+    const EXPECTED_LENGTH: usize = 24;
+    /// 运营人 ID 最长 20 字节
+    pub const MAX_OPERATOR_ID_LENGTH: usize = 20;
+
+    pub fn new(operator_id: &str) -> Self {
+        Self { operator_id_type: 0, operator_id: operator_id.to_string(), reserved: [0; 3] }
+    }
+}
+
+impl Message for OperatorIdMessage {
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        let message_type = MessageType::OperatorIdMessageType as u8;
+        let message_protocol = (message_type << 4) | 0x01;
+        bytes.push(message_protocol);
+        bytes.push(self.operator_id_type);
+
+        // 运营人 ID，超出部分截掉，不足补 0
+        let id = self.operator_id.as_bytes();
+        let length = id.len().min(Self::MAX_OPERATOR_ID_LENGTH);
+        bytes.extend_from_slice(&id[..length]);
+        bytes.extend(vec![0u8; Self::MAX_OPERATOR_ID_LENGTH - length]);
+        bytes.extend_from_slice(&self.reserved);
+
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, MessageError> {
+        let body = check_header(bytes, MessageType::OperatorIdMessageType, Self::EXPECTED_LENGTH)?;
+        let id = &body[1..1 + Self::MAX_OPERATOR_ID_LENGTH];
+        let length = id.iter().position(|&b| b == 0).unwrap_or(id.len());
+        let operator_id = core::str::from_utf8(&id[..length])
+            .map_err(|e| MessageError::InvalidUtf8(id[e.valid_up_to()]))?;
+        let mut reserved = [0u8; 3];
+        reserved.copy_from_slice(&body[21..24]);
+        Ok(Self { operator_id_type: body[0], operator_id: operator_id.to_string(), reserved })
+    }
+}
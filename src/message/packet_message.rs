@@ -0,0 +1,252 @@
+use crate::message::{
+    base_message::BaseMessage, operator_id_message::OperatorIdMessage, position_vector_message::PositionVectorMessage,
+    self_id_message::SelfIdMessage, system_message::SystemMessage,
+};
+use super::checksum::{checksum_offset, CrcAlgorithm};
+use super::message::{Message, MessageError, MessageType};
+use alloc::vec::Vec;
+use crate::config::TransmitterConfig;
+use crate::profile::CLASSIFICATION_UNDECLARED;
+use crate::schedule::MessageKind;
+
+/// 以整包形式发送，其中包含了BaseMessage， SystemMessage, PositionVectorMessage，默认模仿收到大疆的结构类型；
+/// ASTM 与欧盟格式还可以带自我描述和运营人 ID，没有校验和
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacketMessage {
+    protocol_version: u8,          // 整包头，高 4 位 0xF，低 4 位为各消息的协议版本
+    message_counter: u8,          // 消息计数器，encode 时写入；发送时用广播上下文的计数器
+    message_size: u8,             // 消息总大小（2字节）
+    pub base_message: BaseMessage,
+    pub system_message: SystemMessage,
+    pub position_message: PositionVectorMessage,
+    pub self_id_message: Option<SelfIdMessage>,
+    pub operator_id_message: Option<OperatorIdMessage>,
+    has_checksum: bool,            // 是否带校验和与预留
+    checksum_algorithm: CrcAlgorithm, // 校验和算法，覆盖计数器到最后一条消息
+    checksum: u16,                 // CRC16校验和（2字节）
+    reserved: [u8; 3],             // 3字节预留
+}
+
+impl PacketMessage {
+    // 每一帧的大小
+    const MESSAGE_SIZE:u8 = 25;
+    pub fn new(
+        base: BaseMessage,
+        system: SystemMessage,
+        position: PositionVectorMessage
+    ) -> Self {
+        Self {
+            protocol_version: 0xf1,
+            message_counter: 0,
+            message_size: Self::MESSAGE_SIZE,
+            base_message: base,
+            system_message: system,
+            position_message: position,
+            self_id_message: None,
+            operator_id_message: None,
+            has_checksum: true,
+            checksum_algorithm: CrcAlgorithm::Xmodem,
+            checksum: 0,
+            reserved: [0; 3],
+        }
+    }
+
+    // 换用其他 CRC-16 算法计算校验和
+    pub fn with_checksum_algorithm(mut self, algorithm: CrcAlgorithm) -> Self {
+        self.checksum_algorithm = algorithm;
+        self
+    }
+
+    pub fn checksum_algorithm(&self) -> CrcAlgorithm {
+        self.checksum_algorithm
+    }
+
+    // 整包头，低 4 位是协议版本
+    pub fn protocol_version(&self) -> u8 {
+        self.protocol_version
+    }
+
+    // 整包后是否带校验和
+    pub fn has_checksum(&self) -> bool {
+        self.has_checksum
+    }
+
+    // 整包里的消息，按发送顺序
+    pub fn message_kinds(&self) -> Vec<MessageKind> {
+        let mut kinds = Vec::new();
+        kinds.push(MessageKind::BasicId);
+        kinds.push(MessageKind::Location);
+        if self.self_id_message.is_some() {
+            kinds.push(MessageKind::SelfId);
+        }
+        kinds.push(MessageKind::System);
+        if self.operator_id_message.is_some() {
+            kinds.push(MessageKind::OperatorId);
+        }
+        kinds
+    }
+    // 解码得到的计数器
+    pub fn message_counter(&self) -> u8 {
+        self.message_counter
+    }
+
+    // 解码得到的校验和，没有校验字段时为 0
+    pub fn checksum(&self) -> u16 {
+        self.checksum
+    }
+
+    // 按运行时配置组包
+    pub fn from_config(config: &TransmitterConfig) -> Self {
+        let mut base = BaseMessage::new(&config.uas_id);
+        base.id_type = config.id_type;
+        base.ua_type = config.ua_type;
+        let mut system = SystemMessage::new(config.latitude, config.longitude);
+        system.operation_count = config.operation_area.operation_count;
+        system.operation_radius = config.operation_area.operation_radius;
+        system.altitude_upper = config.operation_area.altitude_upper;
+        system.altitude_lower = config.operation_area.altitude_lower;
+        let profile = config.profile;
+        system.classification_region = profile.classification_type();
+        if system.classification_region != CLASSIFICATION_UNDECLARED {
+            system.ua_category = config.ua_category;
+            system.ua_level = config.ua_class;
+        }
+        let position = PositionVectorMessage::new(config.latitude, config.longitude);
+
+        let mut packet = Self::new(base, system, position);
+        packet.protocol_version = profile.pack_header();
+        packet.has_checksum = profile.has_checksum();
+        for kind in profile.pack_messages(config) {
+            match kind {
+                MessageKind::SelfId => packet.self_id_message = Some(SelfIdMessage::new(&config.self_id)),
+                MessageKind::OperatorId => packet.operator_id_message = Some(OperatorIdMessage::new(&config.operator_id)),
+                _ => {}
+            }
+        }
+        packet
+    }
+
+    // 按协议版本编码一条消息：报文头低 4 位换成整包的版本
+    pub fn encode_message(&self, kind: MessageKind) -> Option<Vec<u8>> {
+        let mut bytes = match kind {
+            MessageKind::BasicId => self.base_message.encode(),
+            MessageKind::Location => self.position_message.encode(),
+            MessageKind::SelfId => self.self_id_message.as_ref()?.encode(),
+            MessageKind::System => self.system_message.encode(),
+            MessageKind::OperatorId => self.operator_id_message.as_ref()?.encode(),
+        };
+        bytes[0] = (bytes[0] & 0xF0) | (self.protocol_version & 0x0F);
+        Some(bytes)
+    }
+
+    // 用给定的计数器编码，不改报文本身；各发送方式用自己的计数器
+    pub fn encode_with_counter(&self, rid_counter: u8) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        
+        // 编码头部
+        bytes.push(rid_counter);
+        bytes.push(self.protocol_version);
+        
+        let kinds = self.message_kinds();
+        bytes.push(self.message_size);
+        bytes.push(kinds.len() as u8);
+        
+        // 编码子消息
+        for kind in kinds {
+            bytes.extend(self.encode_message(kind).unwrap_or_default());
+        }
+
+        if self.has_checksum {
+            // 计算校验和
+            let checksum = self.checksum_algorithm.calculate(&bytes);
+            bytes.extend_from_slice(&checksum.to_le_bytes());
+
+            // 添加预留字段
+            bytes.extend_from_slice(&self.reserved);
+        }
+        
+        bytes
+    }
+
+    // 按给定算法解码：带校验和时先校验，不对返回 ChecksumMismatch
+    pub fn decode_with_checksum(bytes: &[u8], algorithm: CrcAlgorithm) -> Result<Self, MessageError> {
+        if bytes.len() < 4 {
+            return Err(MessageError::InsufficientLength(4, bytes.len()));
+        }
+        let message_counter = bytes[0];
+        let protocol_version = bytes[1];
+        if protocol_version >> 4 != 0x0F {
+            return Err(MessageError::UnknownMessageType(protocol_version >> 4));
+        }
+        let message_size = bytes[2];
+        if message_size != Self::MESSAGE_SIZE {
+            return Err(MessageError::InsufficientLength(Self::MESSAGE_SIZE as usize, message_size as usize));
+        }
+        let message_quantity = bytes[3];
+        let end = 4 + message_size as usize * message_quantity as usize;
+        if bytes.len() < end {
+            return Err(MessageError::InsufficientLength(end, bytes.len()));
+        }
+
+        let mut base = None;
+        let mut position = None;
+        let mut system = None;
+        let mut self_id = None;
+        let mut operator_id = None;
+        for chunk in bytes[4..end].chunks(message_size as usize) {
+            // 不认识的消息类型跳过
+            match MessageType::try_from(chunk[0] >> 4) {
+                Ok(MessageType::BaseMessageType) => base = Some(BaseMessage::decode(chunk)?),
+                Ok(MessageType::PositionVectorMessageType) => position = Some(PositionVectorMessage::decode(chunk)?),
+                Ok(MessageType::SystemMessageType) => system = Some(SystemMessage::decode(chunk)?),
+                Ok(MessageType::SelfIdMessageType) => self_id = Some(SelfIdMessage::decode(chunk)?),
+                Ok(MessageType::OperatorIdMessageType) => operator_id = Some(OperatorIdMessage::decode(chunk)?),
+                Err(_) => {}
+            }
+        }
+
+        let trailer = &bytes[end..];
+        let checksum = match trailer {
+            [lo, hi, ..] => u16::from_le_bytes([*lo, *hi]),
+            _ => 0,
+        };
+        if let Some(offset) = checksum_offset(bytes) {
+            let expected = algorithm.calculate(&bytes[..offset]);
+            if expected != checksum {
+                return Err(MessageError::ChecksumMismatch(expected, checksum));
+            }
+        }
+        let mut reserved = [0u8; 3];
+        if let Some(tail) = trailer.get(2..5) {
+            reserved.copy_from_slice(tail);
+        }
+
+        Ok(Self {
+            protocol_version,
+            message_counter,
+            message_size,
+            base_message: base.ok_or(MessageError::MissingMessage(MessageType::BaseMessageType as u8))?,
+            system_message: system.ok_or(MessageError::MissingMessage(MessageType::SystemMessageType as u8))?,
+            position_message: position.ok_or(MessageError::MissingMessage(MessageType::PositionVectorMessageType as u8))?,
+            self_id_message: self_id,
+            operator_id_message: operator_id,
+            has_checksum: trailer.len() >= 2,
+            checksum_algorithm: algorithm,
+            checksum,
+            reserved,
+        })
+    }
+}
+
+impl Message for PacketMessage {
+
+    // 纯函数：同一份报文编码多次结果相同，计数器取 message_counter
+    fn encode(&self) -> Vec<u8> {
+        self.encode_with_counter(self.message_counter)
+    }
+
+    // 解码 encode 的输出：计数器、整包头、各子消息，以及可选的校验和与预留字段，校验和按 XMODEM 校验
+    fn decode(bytes: &[u8]) -> Result<Self, MessageError> {
+        Self::decode_with_checksum(bytes, CrcAlgorithm::Xmodem)
+    }
+}
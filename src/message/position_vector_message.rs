@@ -1,6 +1,6 @@
 use crate::message::message::MessageType;
 
-use super::message::Message;
+use super::message::{check_header, Message, MessageError};
 use alloc::vec::Vec;
 
 
@@ -10,7 +10,7 @@ pub struct PositionVectorMessage {
     // 第1字节 (运行状态和标志位)
     pub run_status: u8,         // 运行状态 (7-4位)
     pub reserved_flag: bool,     // 预留标志位 (3位)
-    pub height_type: u8,        // 高度类型位 (2位) - 0-3
+    pub height_type: u8,        // 高度类型位 (2位) - 0-1
     pub track_direction: u8,   // 航迹角 E/W 方向标志 (1位)
     pub speed_multiplier: u8,  // 速度乘数 (0位)
 
@@ -29,6 +29,7 @@ pub struct PositionVectorMessage {
     // 第19-22字节
     pub vertical_accuracy: u8,   // 垂直精度 (7-4位, 4 bits)
     pub horizontal_accuracy: u8, // 水平精度 (3-0位, 4 bits)
+    pub baro_accuracy: u8,       // 气压高度精度 (7-4位, 4 bits)
     pub speed_accuracy: u8,      // 速度精度 (3-0位, 4 bits)
     pub timestamp: u16,          // 时间戳 (2字节小端序)
 
@@ -41,7 +42,7 @@ impl PositionVectorMessage {
     pub const MESSAGE_TYPE: u8 = 0x01;
     const EXPECTED_LENGTH: usize = 24;
 
-    fn calculate_full_track_angle(&self) -> u16 {
+    pub fn calculate_full_track_angle(&self) -> u16 {
         if self.track_direction == 1 {
             self.track_angle as u16 + 180
         } else {
@@ -49,7 +50,7 @@ impl PositionVectorMessage {
         }
     }
     
-    fn calculate_ground_speed_knots(&self) -> f32 {
+    pub fn calculate_ground_speed_knots(&self) -> f32 {
         if self.speed_multiplier == 1{
             self.ground_speed as f32 * 10.0
         } else {
@@ -67,13 +68,14 @@ impl PositionVectorMessage {
             track_angle: 181, 
             ground_speed: 0, 
             vertical_speed: 0, 
-            latitude: latitude, 
-            longitude: longitude, 
+            latitude,
+            longitude,
             pressure_altitude: 2271, 
             geometric_altitude: 2116, 
             ground_altitude: 2000, 
             vertical_accuracy: 12, 
             horizontal_accuracy: 2, 
+            baro_accuracy: 0, 
             speed_accuracy: 4, 
             timestamp: 32777, 
             timestamp_accuracy: 0, 
@@ -81,6 +83,21 @@ impl PositionVectorMessage {
          }
     }
 
+    /// 写入航迹角（度，0-359）和地速（厘米/秒）
+    pub fn set_track_and_speed(&mut self, heading_deg: u16, speed_cm_s: u32) {
+        // 航迹角超过 180 度时置 E/W 标志，只存余下的部分
+        self.track_direction = (heading_deg >= 180) as u8;
+        self.track_angle = (heading_deg % 180) as u8;
+        // 地速 0.25 米/秒一档，超出一个字节就用 0.75 米/秒一档的乘数
+        let quarter_m_s = speed_cm_s / 25;
+        if quarter_m_s <= u8::MAX as u32 {
+            self.speed_multiplier = 0;
+            self.ground_speed = quarter_m_s as u8 as i8;
+        } else {
+            self.speed_multiplier = 1;
+            self.ground_speed = ((speed_cm_s.saturating_sub(6375) / 75).min(u8::MAX as u32)) as u8 as i8;
+        }
+    }
 }
 
 impl Message for PositionVectorMessage {
@@ -92,11 +109,11 @@ impl Message for PositionVectorMessage {
         let message_protocol = (message_type << 4) | 0x01;
         bytes.push(message_protocol);
         // 第1字节编码
-        let mut byte1 = (self.run_status << 4) as u8;
+        let mut byte1 = self.run_status << 4;
         byte1 |= (self.reserved_flag as u8) << 3;
-        byte1 |= (self.height_type & 0x03) << 2;
-        byte1 |= self.track_direction << 1;
-        byte1 |= self.speed_multiplier;
+        byte1 |= (self.height_type & 0x01) << 2;
+        byte1 |= (self.track_direction & 0x01) << 1;
+        byte1 |= self.speed_multiplier & 0x01;
         bytes.push(byte1);
         
         // 第2-4字节
@@ -116,14 +133,43 @@ impl Message for PositionVectorMessage {
         // 精度和时间戳
         let accuracy_byte = (self.vertical_accuracy << 4) | (self.horizontal_accuracy & 0x0F);
         bytes.push(accuracy_byte);
-        bytes.push(self.speed_accuracy & 0x0F);
+        bytes.push((self.baro_accuracy << 4) | (self.speed_accuracy & 0x0F));
         bytes.extend_from_slice(&self.timestamp.to_le_bytes());
         
         // 最后2字节
-        bytes.push((self.timestamp_accuracy << 4) | (self.reserved & 0x0F));
+        bytes.push(self.timestamp_accuracy & 0x0F);
         bytes.push(self.reserved);
         
         bytes
     }
 
+    fn decode(bytes: &[u8]) -> Result<Self, MessageError> {
+        let body = check_header(bytes, MessageType::PositionVectorMessageType, Self::EXPECTED_LENGTH)?;
+        let byte1 = body[0];
+        let i16_at = |i: usize| i16::from_le_bytes([body[i], body[i + 1]]);
+        let i32_at = |i: usize| i32::from_le_bytes([body[i], body[i + 1], body[i + 2], body[i + 3]]);
+
+        Ok(Self {
+            run_status: byte1 >> 4,
+            reserved_flag: byte1 & 0x08 != 0,
+            height_type: (byte1 >> 2) & 0x01,
+            track_direction: (byte1 >> 1) & 0x01,
+            speed_multiplier: byte1 & 0x01,
+            track_angle: body[1],
+            ground_speed: body[2] as i8,
+            vertical_speed: body[3] as i8,
+            latitude: i32_at(4),
+            longitude: i32_at(8),
+            pressure_altitude: i16_at(12),
+            geometric_altitude: i16_at(14),
+            ground_altitude: i16_at(16),
+            vertical_accuracy: body[18] >> 4,
+            horizontal_accuracy: body[18] & 0x0F,
+            baro_accuracy: body[19] >> 4,
+            speed_accuracy: body[19] & 0x0F,
+            timestamp: u16::from_le_bytes([body[20], body[21]]),
+            timestamp_accuracy: body[22] & 0x0F,
+            reserved: body[23],
+        })
+    }
 }
@@ -0,0 +1,192 @@
+//! 随机生成各字段取值范围内的报文，检查 decode(encode(m)) == m、编码长度恒定、整包校验和正确
+
+use proptest::prelude::*;
+
+use super::base_message::BaseMessage;
+use super::checksum::CrcAlgorithm;
+use super::french_message::FrenchMessage;
+use super::message::Message;
+use super::operator_id_message::OperatorIdMessage;
+use super::packet_message::PacketMessage;
+use super::position_vector_message::PositionVectorMessage;
+use super::self_id_message::SelfIdMessage;
+use super::system_message::SystemMessage;
+
+/// 每条消息编码后的长度（含报文头）
+const MESSAGE_LENGTH: usize = 25;
+/// 计数器 + 整包头(3) + 3 条消息 + 校验和(2) + 预留(3)
+const PACKET_LENGTH: usize = 4 + 3 * MESSAGE_LENGTH + 2 + 3;
+
+fn base_message() -> impl Strategy<Value = BaseMessage> {
+    (0u8..16, 0u8..16, "[0-9A-Z]{0,20}", any::<[u8; 3]>())
+        .prop_map(|(id_type, ua_type, uas_id, reserved)| BaseMessage { id_type, ua_type, uas_id, reserved })
+}
+
+fn position_vector_message() -> impl Strategy<Value = PositionVectorMessage> {
+    (
+        (0u8..16, any::<bool>(), 0u8..2, 0u8..2, 0u8..2),
+        (any::<u8>(), any::<i8>(), any::<i8>()),
+        (any::<i32>(), any::<i32>(), any::<i16>(), any::<i16>(), any::<i16>()),
+        (0u8..16, 0u8..16, 0u8..16, 0u8..16, any::<u16>()),
+        (0u8..16, any::<u8>()),
+    )
+        .prop_map(|(flags, motion, place, accuracy, tail)| PositionVectorMessage {
+            run_status: flags.0,
+            reserved_flag: flags.1,
+            height_type: flags.2,
+            track_direction: flags.3,
+            speed_multiplier: flags.4,
+            track_angle: motion.0,
+            ground_speed: motion.1,
+            vertical_speed: motion.2,
+            latitude: place.0,
+            longitude: place.1,
+            pressure_altitude: place.2,
+            geometric_altitude: place.3,
+            ground_altitude: place.4,
+            vertical_accuracy: accuracy.0,
+            horizontal_accuracy: accuracy.1,
+            baro_accuracy: accuracy.2,
+            speed_accuracy: accuracy.3,
+            timestamp: accuracy.4,
+            timestamp_accuracy: tail.0,
+            reserved: tail.1,
+        })
+}
+
+fn self_id_message() -> impl Strategy<Value = SelfIdMessage> {
+    (any::<u8>(), "[ -~]{0,23}").prop_map(|(description_type, description)| SelfIdMessage { description_type, description })
+}
+
+fn operator_id_message() -> impl Strategy<Value = OperatorIdMessage> {
+    (any::<u8>(), "[0-9A-Za-z]{0,20}", any::<[u8; 3]>())
+        .prop_map(|(operator_id_type, operator_id, reserved)| OperatorIdMessage { operator_id_type, operator_id, reserved })
+}
+
+fn system_message() -> impl Strategy<Value = SystemMessage> {
+    (
+        (0u8..2, 0u8..4, 0u8..8, 0u8..4),
+        (any::<i32>(), any::<i32>()),
+        (any::<u16>(), any::<u8>(), any::<u16>(), any::<u16>()),
+        (0u8..16, 0u8..16, any::<u16>(), any::<u32>(), any::<u8>()),
+    )
+        .prop_map(|(flags, place, area, tail)| SystemMessage {
+            coordinate_system: flags.0,
+            reserved_bits: flags.1,
+            classification_region: flags.2,
+            station_type: flags.3,
+            latitude: place.0,
+            longitude: place.1,
+            operation_count: area.0,
+            operation_radius: area.1,
+            altitude_upper: area.2,
+            altitude_lower: area.3,
+            ua_category: tail.0,
+            ua_level: tail.1,
+            station_altitude: tail.2,
+            timestamp: tail.3,
+            reserved: tail.4,
+        })
+}
+
+fn french_message() -> impl Strategy<Value = FrenchMessage> {
+    (
+        (any::<u8>(), proptest::option::of("[0-9A-Z]{30}"), proptest::option::of("[0-9A-Z]{1,20}")),
+        (any::<i32>(), any::<i32>(), any::<i16>(), any::<i16>()),
+        (any::<i32>(), any::<i32>(), any::<u8>(), 0u16..360),
+    )
+        .prop_filter("需要法国 ID 或 ANSI ID", |(ids, _, _)| ids.1.is_some() || ids.2.is_some())
+        .prop_map(|(ids, place, tail)| FrenchMessage {
+            version: ids.0,
+            fr_id: ids.1,
+            ansi_id: ids.2,
+            latitude: place.0,
+            longitude: place.1,
+            altitude: place.2,
+            height: place.3,
+            home_latitude: tail.0,
+            home_longitude: tail.1,
+            ground_speed: tail.2,
+            heading: tail.3,
+        })
+}
+
+proptest! {
+    #[test]
+    fn base_message_round_trip(message in base_message()) {
+        let bytes = message.encode();
+        prop_assert_eq!(bytes.len(), MESSAGE_LENGTH);
+        prop_assert_eq!(BaseMessage::decode(&bytes), Ok(message));
+    }
+
+    #[test]
+    fn position_vector_message_round_trip(message in position_vector_message()) {
+        let bytes = message.encode();
+        prop_assert_eq!(bytes.len(), MESSAGE_LENGTH);
+        prop_assert_eq!(PositionVectorMessage::decode(&bytes), Ok(message));
+    }
+
+    #[test]
+    fn self_id_message_round_trip(message in self_id_message()) {
+        let bytes = message.encode();
+        prop_assert_eq!(bytes.len(), MESSAGE_LENGTH);
+        prop_assert_eq!(SelfIdMessage::decode(&bytes), Ok(message));
+    }
+
+    #[test]
+    fn system_message_round_trip(message in system_message()) {
+        let bytes = message.encode();
+        prop_assert_eq!(bytes.len(), MESSAGE_LENGTH);
+        prop_assert_eq!(SystemMessage::decode(&bytes), Ok(message));
+    }
+
+    #[test]
+    fn operator_id_message_round_trip(message in operator_id_message()) {
+        let bytes = message.encode();
+        prop_assert_eq!(bytes.len(), MESSAGE_LENGTH);
+        prop_assert_eq!(OperatorIdMessage::decode(&bytes), Ok(message));
+    }
+
+    #[test]
+    fn french_message_round_trip(message in french_message()) {
+        prop_assert_eq!(FrenchMessage::decode(&message.encode()), Ok(message));
+    }
+
+    #[test]
+    fn packet_round_trip(base in base_message(), position in position_vector_message(), system in system_message()) {
+        let packet = PacketMessage::new(base, system, position);
+        let bytes = packet.encode();
+        prop_assert_eq!(bytes.len(), PACKET_LENGTH);
+
+        let decoded = PacketMessage::decode(&bytes).unwrap();
+        prop_assert_eq!(&decoded.base_message, &packet.base_message);
+        prop_assert_eq!(&decoded.position_message, &packet.position_message);
+        prop_assert_eq!(&decoded.system_message, &packet.system_message);
+        prop_assert_eq!(decoded.message_counter(), bytes[0]);
+
+        // 校验和覆盖计数器到最后一条消息
+        let checksum_offset = PACKET_LENGTH - 5;
+        let expected = crc16::State::<crc16::XMODEM>::calculate(&bytes[..checksum_offset]);
+        prop_assert_eq!(decoded.checksum(), expected);
+    }
+
+    #[test]
+    fn packet_checksum_algorithms(
+        base in base_message(),
+        position in position_vector_message(),
+        system in system_message(),
+        algorithm in proptest::sample::select(CrcAlgorithm::ALL.to_vec()),
+        flip in 0usize..PACKET_LENGTH - 5,
+    ) {
+        let packet = PacketMessage::new(base, system, position).with_checksum_algorithm(algorithm);
+        let bytes = packet.encode();
+        let decoded = PacketMessage::decode_with_checksum(&bytes, algorithm).unwrap();
+        prop_assert_eq!(decoded.checksum_algorithm(), algorithm);
+        prop_assert_eq!(decoded.encode(), bytes.clone());
+
+        // 校验和覆盖的字节错一位就解码失败
+        let mut corrupted = bytes;
+        corrupted[flip] ^= 0x80;
+        prop_assert!(PacketMessage::decode_with_checksum(&corrupted, algorithm).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use crate::message::message::MessageType;
+
+use super::message::{check_header, Message, MessageError};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 自我描述报文，运营人填写的飞行目的等文字说明
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfIdMessage {
+    pub description_type: u8, // 描述类型 (0 = 文字说明)
+    pub description: String,  // 描述文字，最多 23 字节 ASCII
+}
+
+impl SelfIdMessage {
+    pub const MESSAGE_TYPE: u8 = 0x03;
+    const EXPECTED_LENGTH: usize = 24;
+    /// 描述文字最长 23 字节
+    pub const MAX_DESCRIPTION_LENGTH: usize = 23;
+
+    pub fn new(description: &str) -> Self {
+        Self { description_type: 0, description: description.to_string() }
+    }
+}
+
+impl Message for SelfIdMessage {
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        let message_type = MessageType::SelfIdMessageType as u8;
+        let message_protocol = (message_type << 4) | 0x01;
+        bytes.push(message_protocol);
+        bytes.push(self.description_type);
+
+        // 描述文字，超出部分截掉，不足补 0
+        let text = self.description.as_bytes();
+        let length = text.len().min(Self::MAX_DESCRIPTION_LENGTH);
+        bytes.extend_from_slice(&text[..length]);
+        bytes.extend(vec![0u8; Self::MAX_DESCRIPTION_LENGTH - length]);
+
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, MessageError> {
+        let body = check_header(bytes, MessageType::SelfIdMessageType, Self::EXPECTED_LENGTH)?;
+        let text = &body[1..];
+        let length = text.iter().position(|&b| b == 0).unwrap_or(text.len());
+        let description = core::str::from_utf8(&text[..length])
+            .map_err(|e| MessageError::InvalidUtf8(text[e.valid_up_to()]))?;
+        Ok(Self { description_type: body[0], description: description.to_string() })
+    }
+}
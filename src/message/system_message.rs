@@ -1,6 +1,5 @@
-use super::message::{Message, MessageType};
+use super::message::{check_header, Message, MessageError, MessageType};
 use alloc::vec::Vec;
-use esp_hal::timer::timg::TimerGroup;
 
 // SystemMessage 结构体，系统报文（报文类型 0x4）为周期性，强制静态报文，用于描述无人驾驶航空器控制站位置和高度 、 航空器组群及额外的系统信息
 #[derive(Debug, Clone, PartialEq)]
@@ -47,8 +46,8 @@ impl SystemMessage {
             reserved_bits: 0, 
             classification_region: 2, 
             station_type: 1, 
-            latitude: latitude, 
-            longitude: longitude, 
+            latitude,
+            longitude,
             operation_count: 1, 
             operation_radius: 0, 
             altitude_upper: 0, 
@@ -74,11 +73,11 @@ impl Message for SystemMessage {
         bytes.push(message_protocol);
 
         // 第1字节编码
-        let mut byte1 = (self.coordinate_system & 0x7F) << 1;
+        let mut byte1 = (self.coordinate_system & 0x01) << 7;
         byte1 |= (self.reserved_bits & 0x03) << 5;
         byte1 |= (self.classification_region & 0x07) << 2;
         byte1 |= self.station_type & 0x03;
-        bytes.push(byte1 as u8);
+        bytes.push(byte1);
         
         // 经纬度编码（小端序）
         bytes.extend_from_slice(&self.latitude.to_le_bytes());
@@ -91,33 +90,42 @@ impl Message for SystemMessage {
         bytes.extend_from_slice(&self.altitude_lower.to_le_bytes());
         
         // UA类别和等级
-        let ua_category_level = self.ua_category << 4 | self.ua_level;
+        let ua_category_level = self.ua_category << 4 | (self.ua_level & 0x0F);
         bytes.push(ua_category_level);
         
         // 控制站高度
         bytes.extend_from_slice(&self.station_altitude.to_le_bytes());
         
-        // 时间戳和预留 - 使用ESP32-C6定时器获取系统时间
-        let timestamp = {
-            let timg0 = unsafe { &*esp_hal::peripherals::TIMG0::ptr() };
-            let t = timg0.t(0); // 访问定时器0
-            
-            // 触发更新以捕获当前值
-            t.update().write(|w| w.update().set_bit());
-            while t.update().read().update().bit_is_set() {
-                // 等待更新完成
-            }
-            
-            // 读取定时器值
-            let value_lo = t.lo().read().bits() as u64;
-            let value_hi = t.hi().read().bits() as u64;
-            let timer_value = (value_hi << 32) | value_lo;
-            timer_value as u32
-        };
-        bytes.extend_from_slice(&timestamp.to_le_bytes());
+        // 时间戳和预留，时间戳由调用方在发送前填入
+        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
 
         bytes.push(self.reserved);
         
         bytes
     }
+
+    fn decode(bytes: &[u8]) -> Result<Self, MessageError> {
+        let body = check_header(bytes, MessageType::SystemMessageType, Self::EXPECTED_LENGTH)?;
+        let byte1 = body[0];
+        let u16_at = |i: usize| u16::from_le_bytes([body[i], body[i + 1]]);
+        let i32_at = |i: usize| i32::from_le_bytes([body[i], body[i + 1], body[i + 2], body[i + 3]]);
+
+        Ok(Self {
+            coordinate_system: byte1 >> 7,
+            reserved_bits: (byte1 >> 5) & 0x03,
+            classification_region: (byte1 >> 2) & 0x07,
+            station_type: byte1 & 0x03,
+            latitude: i32_at(1),
+            longitude: i32_at(5),
+            operation_count: u16_at(9),
+            operation_radius: body[11],
+            altitude_upper: u16_at(12),
+            altitude_lower: u16_at(14),
+            ua_category: body[16] >> 4,
+            ua_level: body[16] & 0x0F,
+            station_altitude: u16_at(17),
+            timestamp: u32::from_le_bytes([body[19], body[20], body[21], body[22]]),
+            reserved: body[23],
+        })
+    }
 }
@@ -0,0 +1,237 @@
+//! 用 testdata/vectors 下的厂商元素校验编解码与已知字节完全一致
+//!
+//! 这些向量按公开的字段布局手工拼出，只能锁定字段顺序和编码自洽，
+//! 不能证明与真机空口报文兼容，见 testdata/vectors/README.md
+
+use super::checksum::{detect, ChecksumMatch, ChecksumRange, CrcAlgorithm};
+use super::french_message::{FrenchMessage, FRENCH_VENDOR_PREFIX};
+use super::message::{Message, MessageError};
+use super::packet_message::PacketMessage;
+use super::self_id_message::SelfIdMessage;
+use crate::beacon::RID_VENDOR_PREFIX;
+use crate::config::{StandardProfile, TransmitterConfig};
+use crate::schedule::MessageKind;
+
+const DJI_GB_PACK: &str = include_str!("../../testdata/vectors/dji_gb_pack.hex");
+const ASTM_F3411_PACK: &str = include_str!("../../testdata/vectors/astm_f3411_pack.hex");
+const PROFILE_GB: &str = include_str!("../../testdata/vectors/profile_gb.hex");
+const PROFILE_ASTM: &str = include_str!("../../testdata/vectors/profile_astm.hex");
+const PROFILE_ASD_STAN: &str = include_str!("../../testdata/vectors/profile_asd_stan.hex");
+const FRENCH_BEACON: &str = include_str!("../../testdata/vectors/french_beacon.hex");
+
+/// 解析向量文件：'#' 之后为注释，其余为空白分隔的十六进制字节
+fn parse_vector(text: &str) -> Vec<u8> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap())
+        .flat_map(str::split_whitespace)
+        .map(|byte| u8::from_str_radix(byte, 16).unwrap())
+        .collect()
+}
+
+/// 去掉元素 ID、长度和 RID 前缀，得到 PacketMessage 的输入
+fn vendor_payload(element: &[u8]) -> &[u8] {
+    assert_eq!(element[0], 0xdd);
+    assert_eq!(element[1] as usize, element.len() - 2);
+    assert_eq!(element[2..6], RID_VENDOR_PREFIX);
+    &element[6..]
+}
+
+#[test]
+fn dji_gb_pack() {
+    let element = parse_vector(DJI_GB_PACK);
+    let payload = vendor_payload(&element);
+    let packet = PacketMessage::decode(payload).unwrap();
+
+    assert_eq!(packet.message_counter(), 0x2a);
+    assert_eq!(packet.checksum(), 0x3a83);
+    let base = &packet.base_message;
+    assert_eq!((base.id_type, base.ua_type), (1, 2));
+    assert_eq!(base.uas_id, "1581F4XFC236N002LH7N");
+    let position = &packet.position_message;
+    assert_eq!((position.run_status, position.height_type, position.track_direction), (2, 1, 0));
+    assert_eq!((position.track_angle, position.ground_speed, position.vertical_speed), (90, 20, 2));
+    assert_eq!((position.latitude, position.longitude), (225431234, 1139412345));
+    assert_eq!((position.pressure_altitude, position.geometric_altitude, position.ground_altitude), (2240, 2236, 2100));
+    assert_eq!((position.vertical_accuracy, position.horizontal_accuracy, position.speed_accuracy), (4, 10, 3));
+    assert_eq!(position.timestamp, 21345);
+    let system = &packet.system_message;
+    assert_eq!((system.classification_region, system.station_type), (2, 1));
+    assert_eq!((system.latitude, system.longitude), (225430000, 1139410000));
+    assert_eq!((system.operation_count, system.operation_radius), (1, 10));
+    assert_eq!((system.altitude_upper, system.altitude_lower), (2600, 2000));
+    assert_eq!((system.ua_category, system.ua_level, system.station_altitude), (1, 2, 2030));
+    assert_eq!(system.timestamp, 1760790000);
+
+    // 计数器随解码保留，重新编码应与抓包完全一致
+    assert_eq!(packet.encode(), payload);
+}
+
+#[test]
+fn dji_gb_checksum() {
+    let element = parse_vector(DJI_GB_PACK);
+    let payload = vendor_payload(&element);
+    // 检测模式只认出编码所用的 XMODEM、从计数器起算、小端
+    assert_eq!(
+        detect(payload),
+        [ChecksumMatch { algorithm: CrcAlgorithm::Xmodem, range: ChecksumRange::FromCounter, big_endian: false }]
+    );
+
+    // 改动任何一个被覆盖的字节都被拒绝
+    let mut corrupted = payload.to_vec();
+    corrupted[40] ^= 0x01;
+    assert!(matches!(PacketMessage::decode(&corrupted), Err(MessageError::ChecksumMismatch(_, 0x3a83))));
+    // 用别的算法校验同样不通过
+    let expected = CrcAlgorithm::Modbus.calculate(&payload[..79]);
+    assert_eq!(PacketMessage::decode_with_checksum(payload, CrcAlgorithm::Modbus), Err(MessageError::ChecksumMismatch(expected, 0x3a83)));
+}
+
+#[test]
+fn astm_f3411_pack() {
+    let element = parse_vector(ASTM_F3411_PACK);
+    let payload = vendor_payload(&element);
+    let packet = PacketMessage::decode(payload).unwrap();
+
+    assert_eq!(packet.message_counter(), 0x07);
+    assert_eq!(packet.checksum(), 0);
+    let base = &packet.base_message;
+    assert_eq!((base.id_type, base.ua_type), (1, 2));
+    assert_eq!(base.uas_id, "1596FQ9X2R7T4W8Y3Z61");
+    let position = &packet.position_message;
+    assert_eq!((position.run_status, position.height_type, position.track_direction), (2, 1, 1));
+    assert_eq!(position.calculate_full_track_angle(), 195);
+    assert_eq!((position.ground_speed, position.vertical_speed), (44, -2));
+    assert_eq!((position.latitude, position.longitude), (525123456, 49876543));
+    assert_eq!((position.pressure_altitude, position.geometric_altitude, position.ground_altitude), (2410, 2424, 2160));
+    assert_eq!((position.vertical_accuracy, position.horizontal_accuracy), (3, 11));
+    assert_eq!((position.baro_accuracy, position.speed_accuracy), (4, 2));
+    assert_eq!((position.timestamp, position.timestamp_accuracy), (18000, 3));
+    let system = &packet.system_message;
+    assert_eq!((system.coordinate_system, system.classification_region, system.station_type), (0, 1, 1));
+    assert_eq!((system.latitude, system.longitude), (525120000, 49870000));
+    assert_eq!((system.ua_category, system.ua_level, system.station_altitude), (1, 2, 2030));
+    assert_eq!(system.timestamp, 1760790123);
+
+    // 整包重新编码，五条报文都经 PacketMessage 按序输出
+    assert_eq!(packet.encode(), payload);
+
+    let messages: Vec<&[u8]> = payload[4..].chunks(25).collect();
+
+    let self_id = packet.self_id_message.as_ref().unwrap();
+    assert_eq!((self_id.description_type, self_id.description.as_str()), (0, "Survey flight"));
+    assert_eq!(self_id.encode(), messages[2]);
+    assert_eq!(SelfIdMessage::decode(messages[2]).as_ref(), Ok(self_id));
+
+    let operator = packet.operator_id_message.as_ref().unwrap();
+    assert_eq!((operator.operator_id_type, operator.operator_id.as_str()), (0, "FIN87astrdge12k8"));
+    assert_eq!(operator.encode(), messages[4]);
+}
+
+/// 三个标准共用的配置，时间戳固定
+fn profile_config(profile: StandardProfile) -> TransmitterConfig {
+    let mut config = TransmitterConfig { profile, ..TransmitterConfig::default() };
+    config.set_uas_id("1596FQ9X2R7T4W8Y3Z61").unwrap();
+    config.set_position(525123456, 49876543).unwrap();
+    config.set_self_id("Survey flight").unwrap();
+    config.set_operator_id("FIN87astrdge12k8").unwrap();
+    config.set_classification(1, 2).unwrap();
+    config
+}
+
+/// 按配置编码，用向量里的计数器编码后应与向量完全一致
+fn encode_profile(profile: StandardProfile, payload: &[u8]) -> PacketMessage {
+    let mut packet = PacketMessage::from_config(&profile_config(profile));
+    packet.system_message.timestamp = 1760790123;
+    assert_eq!(packet.encode_with_counter(payload[0]), payload);
+    packet
+}
+
+#[test]
+fn gb_profile_pack() {
+    let element = parse_vector(PROFILE_GB);
+    let payload = vendor_payload(&element);
+    let packet = encode_profile(StandardProfile::Gb, payload);
+    assert_eq!((packet.protocol_version() & 0x0f, packet.has_checksum()), (1, true));
+    assert_eq!(packet.message_kinds(), [MessageKind::BasicId, MessageKind::Location, MessageKind::System]);
+
+    let decoded = PacketMessage::decode(payload).unwrap();
+    assert_eq!(decoded.checksum(), 0xaee9);
+    assert!(decoded.self_id_message.is_none() && decoded.operator_id_message.is_none());
+    let system = &decoded.system_message;
+    assert_eq!((system.classification_region, system.ua_category, system.ua_level), (2, 1, 2));
+}
+
+#[test]
+fn astm_profile_pack() {
+    let element = parse_vector(PROFILE_ASTM);
+    let payload = vendor_payload(&element);
+    let packet = encode_profile(StandardProfile::Astm, payload);
+    assert_eq!((packet.protocol_version() & 0x0f, packet.has_checksum()), (2, false));
+    assert!(payload[4..].chunks(25).all(|message| message[0] & 0x0f == 2));
+
+    let decoded = PacketMessage::decode(payload).unwrap();
+    assert!(!decoded.has_checksum());
+    assert_eq!(decoded.self_id_message.unwrap().description, "Survey flight");
+    assert_eq!(decoded.operator_id_message.unwrap().operator_id, "FIN87astrdge12k8");
+    // 不声明分类
+    let system = &decoded.system_message;
+    assert_eq!((system.classification_region, system.ua_category, system.ua_level), (0, 0, 0));
+}
+
+#[test]
+fn asd_stan_profile_pack() {
+    let element = parse_vector(PROFILE_ASD_STAN);
+    let payload = vendor_payload(&element);
+    let packet = encode_profile(StandardProfile::AsdStan, payload);
+    assert_eq!((packet.protocol_version() & 0x0f, packet.has_checksum()), (1, false));
+    assert_eq!(
+        packet.message_kinds(),
+        [MessageKind::BasicId, MessageKind::Location, MessageKind::SelfId, MessageKind::System, MessageKind::OperatorId]
+    );
+
+    let decoded = PacketMessage::decode(payload).unwrap();
+    assert_eq!(decoded.operator_id_message.unwrap().operator_id, "FIN87astrdge12k8");
+    // 欧盟分类：开放类、C1
+    let system = &decoded.system_message;
+    assert_eq!((system.classification_region, system.ua_category, system.ua_level), (1, 1, 2));
+    assert_eq!(StandardProfile::AsdStan.check(&profile_config(StandardProfile::AsdStan)), Ok(()));
+}
+
+#[test]
+fn french_beacon_element() {
+    let element = parse_vector(FRENCH_BEACON);
+    assert_eq!(element[1] as usize, element.len() - 2);
+    assert_eq!(element[2..6], FRENCH_VENDOR_PREFIX);
+    let payload = &element[6..];
+    let message = FrenchMessage::decode(payload).unwrap();
+
+    assert_eq!(message.version, 1);
+    assert_eq!(message.fr_id.as_deref(), Some("ILLDRN000000000000000000001234"));
+    assert_eq!(message.ansi_id.as_deref(), Some("1596FQ9X2R7T4W8Y3Z61"));
+    assert_eq!((message.latitude, message.longitude), (4815278, 261222));
+    assert_eq!((message.altitude, message.height), (152, 45));
+    assert_eq!((message.home_latitude, message.home_longitude), (4815200, 261100));
+    assert_eq!((message.ground_speed, message.heading), (12, 270));
+    assert_eq!(message.encode(), payload);
+
+    // 缺 ID、长度不对、截断都报错
+    let without_ids: Vec<u8> = [&payload[..3], &payload[3 + 32 + 22..]].concat();
+    assert_eq!(FrenchMessage::decode(&without_ids), Err(MessageError::MissingMessage(2)));
+    let mut bad_length = payload.to_vec();
+    bad_length[1] = 2;
+    assert_eq!(FrenchMessage::decode(&bad_length), Err(MessageError::InvalidTlv(1)));
+    assert!(matches!(FrenchMessage::decode(&payload[..payload.len() - 1]), Err(MessageError::InsufficientLength(_, _))));
+}
+
+#[test]
+fn french_from_astm_pack() {
+    // 由 ASTM 整包换算：单位 1e-5 度、米、米/秒
+    let element = parse_vector(ASTM_F3411_PACK);
+    let packet = PacketMessage::decode(vendor_payload(&element)).unwrap();
+    let message = FrenchMessage::from_packet(&packet);
+    assert_eq!((message.fr_id.as_deref(), message.ansi_id.as_deref()), (None, Some("1596FQ9X2R7T4W8Y3Z61")));
+    assert_eq!((message.latitude, message.longitude), (5251234, 498765));
+    assert_eq!((message.altitude, message.height), (212, 80));
+    assert_eq!((message.home_latitude, message.home_longitude), (5251200, 498700));
+    assert_eq!((message.ground_speed, message.heading), (11, 195));
+    assert_eq!(FrenchMessage::decode(&message.encode()), Ok(message));
+}
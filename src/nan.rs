@@ -0,0 +1,247 @@
+use alloc::vec::Vec;
+
+use crate::message::{message::{Message, MessageError}, packet_message::PacketMessage};
+
+/// NAN 服务 ID：SHA-256("org.opendroneid.remoteid") 的前 6 字节
+pub const ODID_SERVICE_ID: [u8; 6] = [0x88, 0x69, 0x19, 0x9d, 0x92, 0x09];
+/// NAN 网络 ID（组播），SDF 的接收地址
+pub const NAN_NETWORK_ID: [u8; 6] = [0x51, 0x6f, 0x9a, 0x01, 0x00, 0x00];
+/// 默认的 NAN 簇 ID，SDF 的 BSSID；簇 ID 为 50:6f:9a:01 加两字节
+pub const DEFAULT_CLUSTER_ID: [u8; 6] = [0x50, 0x6f, 0x9a, 0x01, 0x00, 0x00];
+/// Wi-Fi 联盟 OUI 和 NAN 的 OUI 类型
+pub const WFA_OUI: [u8; 3] = [0x50, 0x6f, 0x9a];
+pub const NAN_OUI_TYPE: u8 = 0x13;
+/// 管理帧类型：Action
+const FRAME_CONTROL_ACTION: [u8; 2] = [0xd0, 0x00];
+/// 公共 Action 帧里的厂商自定义动作
+const CATEGORY_PUBLIC: u8 = 0x04;
+const ACTION_VENDOR_SPECIFIC: u8 = 0x09;
+/// 服务描述属性 (Service Descriptor Attribute)
+const ATTRIBUTE_SERVICE_DESCRIPTOR: u8 = 0x03;
+/// 服务控制：带服务信息
+const SERVICE_INFO_PRESENT: u8 = 0x10;
+/// 管理帧头 24 字节，Action 头 6 字节，属性头 3 字节，SDA 固定字段（服务 ID、两个实例 ID、控制、信息长度）10 字节
+const HEADER_LENGTH: usize = 24;
+const ACTION_LENGTH: usize = 6;
+const SDA_FIXED_LENGTH: usize = 10;
+/// 服务信息长度只有一个字节
+const MAX_SERVICE_INFO_LENGTH: usize = 255;
+
+/// SDF 的服务类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SdfType {
+    Publish,  // 主动发布
+    FollowUp, // 跟进
+}
+
+impl SdfType {
+    fn control(self) -> u8 {
+        match self {
+            SdfType::Publish => 0x00,
+            SdfType::FollowUp => 0x02,
+        }
+    }
+}
+
+/// NAN 帧组装错误
+#[derive(Debug, PartialEq)]
+pub enum NanError {
+    ServiceInfoTooLong(usize), // RID 负载长度
+    BufferTooSmall,            // 输出缓冲区不够
+}
+
+/// NAN 帧自检错误
+#[derive(Debug, PartialEq)]
+pub enum NanVerifyError {
+    NotNanFrame,              // 不是 NAN 公共 Action 帧
+    MissingServiceDescriptor, // 没有 Open Drone ID 的服务描述属性
+    Message(MessageError),    // RID 消息包解码失败
+}
+
+impl From<MessageError> for NanVerifyError {
+    fn from(e: MessageError) -> Self {
+        NanVerifyError::Message(e)
+    }
+}
+
+/// 组装携带 RID 消息包的 NAN 服务发现帧 (SDF)，用 send_raw_frame 发送
+#[derive(Debug, Clone, PartialEq)]
+pub struct NanSdfBuilder {
+    transmitter_address: [u8; 6],
+    cluster_id: [u8; 6],
+    sdf_type: SdfType,
+    instance_id: u8,
+}
+
+impl NanSdfBuilder {
+    /// 默认按 opendroneid 的做法发布，实例 ID 为 1
+    pub fn new(mac_address: [u8; 6]) -> Self {
+        Self { transmitter_address: mac_address, cluster_id: DEFAULT_CLUSTER_ID, sdf_type: SdfType::Publish, instance_id: 1 }
+    }
+
+    /// 换簇 ID 的后两字节，前 4 字节固定为 50:6f:9a:01
+    pub fn with_cluster_id(mut self, cluster: [u8; 2]) -> Self {
+        self.cluster_id[4..].copy_from_slice(&cluster);
+        self
+    }
+
+    pub fn with_sdf_type(mut self, sdf_type: SdfType) -> Self {
+        self.sdf_type = sdf_type;
+        self
+    }
+
+    pub fn with_instance_id(mut self, instance_id: u8) -> Self {
+        self.instance_id = instance_id;
+        self
+    }
+
+    /// 写入 SDF，rid_payload 与信标厂商元素的内容相同（计数器加消息包），返回写入长度
+    pub fn build(&self, rid_payload: &[u8], buffer: &mut [u8]) -> Result<usize, NanError> {
+        if rid_payload.len() > MAX_SERVICE_INFO_LENGTH {
+            return Err(NanError::ServiceInfoTooLong(rid_payload.len()));
+        }
+        let attribute_length = SDA_FIXED_LENGTH + rid_payload.len();
+        let length = HEADER_LENGTH + ACTION_LENGTH + 3 + attribute_length;
+        if buffer.len() < length {
+            return Err(NanError::BufferTooSmall);
+        }
+
+        let mut frame = Vec::with_capacity(length);
+        frame.extend_from_slice(&FRAME_CONTROL_ACTION);
+        frame.extend_from_slice(&[0x00, 0x00]); // 持续时间
+        frame.extend_from_slice(&NAN_NETWORK_ID);
+        frame.extend_from_slice(&self.transmitter_address);
+        frame.extend_from_slice(&self.cluster_id);
+        frame.extend_from_slice(&[0x00, 0x00]); // 序列控制
+        frame.push(CATEGORY_PUBLIC);
+        frame.push(ACTION_VENDOR_SPECIFIC);
+        frame.extend_from_slice(&WFA_OUI);
+        frame.push(NAN_OUI_TYPE);
+        frame.push(ATTRIBUTE_SERVICE_DESCRIPTOR);
+        frame.extend_from_slice(&(attribute_length as u16).to_le_bytes());
+        frame.extend_from_slice(&ODID_SERVICE_ID);
+        frame.push(self.instance_id);
+        frame.push(0x00); // 请求方实例 ID
+        frame.push(self.sdf_type.control() | SERVICE_INFO_PRESENT);
+        frame.push(rid_payload.len() as u8);
+        frame.extend_from_slice(rid_payload);
+
+        buffer[..length].copy_from_slice(&frame);
+        Ok(length)
+    }
+}
+
+/// 从 SDF（不带 FCS）中取出 Open Drone ID 服务信息
+pub fn extract_nan_payload(frame: &[u8]) -> Option<&[u8]> {
+    let action = frame.get(HEADER_LENGTH..HEADER_LENGTH + ACTION_LENGTH)?;
+    if frame[..2] != FRAME_CONTROL_ACTION || action != [CATEGORY_PUBLIC, ACTION_VENDOR_SPECIFIC, WFA_OUI[0], WFA_OUI[1], WFA_OUI[2], NAN_OUI_TYPE] {
+        return None;
+    }
+    // 逐个属性查找服务 ID 匹配且带服务信息的服务描述属性
+    let mut attributes = &frame[HEADER_LENGTH + ACTION_LENGTH..];
+    while attributes.len() >= 3 {
+        let length = u16::from_le_bytes([attributes[1], attributes[2]]) as usize;
+        let body = attributes.get(3..3 + length)?;
+        if attributes[0] == ATTRIBUTE_SERVICE_DESCRIPTOR
+            && body.len() >= SDA_FIXED_LENGTH
+            && body[..6] == ODID_SERVICE_ID
+            && body[8] & SERVICE_INFO_PRESENT != 0
+        {
+            let info_length = body[9] as usize;
+            return body.get(SDA_FIXED_LENGTH..SDA_FIXED_LENGTH + info_length);
+        }
+        attributes = &attributes[3 + length..];
+    }
+    None
+}
+
+/// 自检：按接收端的方式解析 SDF 并解码其中的 RID 消息包
+pub fn verify_rid_nan(frame: &[u8]) -> Result<PacketMessage, NanVerifyError> {
+    if frame.get(..2) != Some(&FRAME_CONTROL_ACTION[..]) || frame.len() < HEADER_LENGTH + ACTION_LENGTH {
+        return Err(NanVerifyError::NotNanFrame);
+    }
+    let payload = extract_nan_payload(frame).ok_or(NanVerifyError::MissingServiceDescriptor)?;
+    Ok(PacketMessage::decode(payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TransmitterConfig;
+    use sha2::{Digest, Sha256};
+
+    const MAC: [u8; 6] = [0x00, 0x80, 0x41, 0x13, 0x37, 0x42];
+
+    #[test]
+    fn service_id_is_name_hash() {
+        let hash = Sha256::digest(b"org.opendroneid.remoteid");
+        assert_eq!(hash[..6], ODID_SERVICE_ID);
+    }
+
+    #[test]
+    fn golden_sdf() {
+        let mut buffer = [0u8; 300];
+        let length = NanSdfBuilder::new(MAC).build(&[0x01, 0xf1, 0x19, 0x00], &mut buffer).unwrap();
+
+        let expected: &[u8] = &[
+            // 帧控制 Action、持续时间
+            0xd0, 0x00, 0x00, 0x00,
+            // 接收地址为网络 ID、发射地址、BSSID 为簇 ID
+            0x51, 0x6f, 0x9a, 0x01, 0x00, 0x00,
+            0x00, 0x80, 0x41, 0x13, 0x37, 0x42,
+            0x50, 0x6f, 0x9a, 0x01, 0x00, 0x00,
+            // 序列控制
+            0x00, 0x00,
+            // 公共 Action、厂商自定义、WFA OUI、NAN
+            0x04, 0x09, 0x50, 0x6f, 0x9a, 0x13,
+            // 服务描述属性，长度 14
+            0x03, 0x0e, 0x00,
+            // 服务 ID
+            0x88, 0x69, 0x19, 0x9d, 0x92, 0x09,
+            // 实例 ID、请求方实例 ID、发布并带服务信息
+            0x01, 0x00, 0x10,
+            // 服务信息
+            0x04, 0x01, 0xf1, 0x19, 0x00,
+        ];
+        assert_eq!(&buffer[..length], expected);
+    }
+
+    #[test]
+    fn follow_up_and_errors() {
+        let mut buffer = [0u8; 300];
+        let builder = NanSdfBuilder::new(MAC).with_sdf_type(SdfType::FollowUp).with_instance_id(7);
+        let length = builder.build(&[0xaa], &mut buffer).unwrap();
+        assert_eq!(buffer[39..42], [0x07, 0x00, 0x12]);
+        assert_eq!(extract_nan_payload(&buffer[..length]), Some(&[0xaa][..]));
+
+        // A1 为组播的网络 ID，A3 为单播形式的簇 ID
+        NanSdfBuilder::new(MAC).with_cluster_id([0x12, 0x34]).build(&[0xaa], &mut buffer).unwrap();
+        assert_eq!(buffer[4..10], NAN_NETWORK_ID);
+        assert_eq!(buffer[16..22], [0x50, 0x6f, 0x9a, 0x01, 0x12, 0x34]);
+        assert_eq!(buffer[16] & 0x01, 0);
+
+        assert_eq!(builder.build(&[0; 256], &mut buffer), Err(NanError::ServiceInfoTooLong(256)));
+        assert_eq!(builder.build(&[0; 40], &mut buffer[..50]), Err(NanError::BufferTooSmall));
+    }
+
+    #[test]
+    fn round_trip_through_parser() {
+        let mut config = TransmitterConfig::default();
+        config.set_uas_id("1581F7FVC251A00CQ211").unwrap();
+        let package = PacketMessage::from_config(&config);
+        let payload = package.encode();
+
+        let mut buffer = [0u8; 300];
+        let length = NanSdfBuilder::new(MAC).build(&payload, &mut buffer).unwrap();
+        assert_eq!(extract_nan_payload(&buffer[..length]), Some(payload.as_slice()));
+
+        let decoded = verify_rid_nan(&buffer[..length]).unwrap();
+        assert_eq!(decoded.base_message, package.base_message);
+        assert_eq!(decoded.position_message, package.position_message);
+        assert_eq!(decoded.system_message, package.system_message);
+
+        // 截断的属性和信标都不是 RID 的 SDF
+        assert_eq!(verify_rid_nan(&buffer[..length - 1]), Err(NanVerifyError::MissingServiceDescriptor));
+        assert_eq!(verify_rid_nan(&[0x80; 40]), Err(NanVerifyError::NotNanFrame));
+    }
+}
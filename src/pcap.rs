@@ -0,0 +1,454 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// 链路类型：不带 radiotap 的 802.11 帧
+pub const LINKTYPE_IEEE802_11: u32 = 105;
+/// 链路类型：radiotap 头加 802.11 帧
+pub const LINKTYPE_IEEE802_11_RADIOTAP: u32 = 127;
+/// 单帧最大抓取长度
+pub const SNAPLEN: u32 = 65535;
+/// 固件通过串口输出抓包数据时每行的前缀，后接一段十六进制
+pub const SERIAL_LINE_PREFIX: &str = "PCAP ";
+
+/// pcap 魔数：微秒时间戳、纳秒时间戳
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+/// pcapng 块类型与字节序标记
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const PCAPNG_SIMPLE_PACKET: u32 = 0x0000_0003;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x0000_0006;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+/// pcapng 接口选项 if_tsresol
+const PCAPNG_OPTION_TSRESOL: u16 = 9;
+
+/// radiotap 字段位：标志、速率、信道、信号强度
+const RADIOTAP_FLAGS: u32 = 1 << 1;
+const RADIOTAP_RATE: u32 = 1 << 2;
+const RADIOTAP_CHANNEL: u32 = 1 << 3;
+const RADIOTAP_DBM_ANTSIGNAL: u32 = 1 << 5;
+/// radiotap 标志：帧尾带 FCS
+const RADIOTAP_FLAG_FCS: u8 = 0x10;
+/// radiotap 信道标志：CCK、OFDM、2.4 GHz
+const RADIOTAP_CHANNEL_CCK: u16 = 0x0020;
+const RADIOTAP_CHANNEL_OFDM: u16 = 0x0040;
+const RADIOTAP_CHANNEL_2GHZ: u16 = 0x0080;
+
+/// 抓包文件错误
+#[derive(Debug, PartialEq)]
+pub enum PcapError {
+    UnknownFormat(u32),       // 文件开头的魔数
+    Truncated(usize),         // 出错位置
+    UnsupportedLinkType(u32), // 链路类型
+    BadRadiotap(usize),       // 出错帧所在位置
+}
+
+/// radiotap 头里关心的字段
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RadiotapInfo {
+    pub rate: u8,                // 速率，单位 500 kbps
+    pub channel: u8,             // 2.4G 信道号
+    pub signal_dbm: Option<i8>,  // 接收信号强度，发送的帧没有
+}
+
+/// 从抓包文件里读出的一帧
+#[derive(Debug, PartialEq)]
+pub struct CapturedFrame<'a> {
+    pub timestamp_us: u64,               // 时间戳（微秒）
+    pub radiotap: Option<RadiotapInfo>,  // 链路类型 105 时没有
+    pub frame: &'a [u8],                 // 802.11 帧，不含 FCS
+}
+
+/// 2.4G 信道号转中心频率 (MHz)
+pub fn channel_frequency(channel: u8) -> u16 {
+    match channel {
+        14 => 2484,
+        _ => 2407 + 5 * channel as u16,
+    }
+}
+
+/// 6~54 Mbps 是 OFDM 速率，其余 (1/2/5.5/11 Mbps) 是 CCK
+fn channel_flags(rate: u8) -> u16 {
+    let modulation = match rate {
+        12 | 18 | 24 | 36 | 48 | 72 | 96 | 108 => RADIOTAP_CHANNEL_OFDM,
+        _ => RADIOTAP_CHANNEL_CCK,
+    };
+    modulation | RADIOTAP_CHANNEL_2GHZ
+}
+
+fn frequency_channel(frequency: u16) -> u8 {
+    match frequency {
+        2484 => 14,
+        2412..=2472 => ((frequency - 2407) / 5) as u8,
+        _ => 0,
+    }
+}
+
+/// 写 radiotap 头：标志、速率、信道，收到的帧再加信号强度
+pub fn write_radiotap(out: &mut Vec<u8>, info: &RadiotapInfo) {
+    let mut present = RADIOTAP_FLAGS | RADIOTAP_RATE | RADIOTAP_CHANNEL;
+    let mut length: u16 = 14;
+    if info.signal_dbm.is_some() {
+        present |= RADIOTAP_DBM_ANTSIGNAL;
+        length += 1;
+    }
+    out.push(0); // 版本
+    out.push(0); // 填充
+    out.extend_from_slice(&length.to_le_bytes());
+    out.extend_from_slice(&present.to_le_bytes());
+    out.push(0); // 标志：不带 FCS
+    out.push(info.rate);
+    out.extend_from_slice(&channel_frequency(info.channel).to_le_bytes());
+    out.extend_from_slice(&channel_flags(info.rate).to_le_bytes());
+    if let Some(signal) = info.signal_dbm {
+        out.push(signal as u8);
+    }
+}
+
+/// 写 pcap 文件头，链路类型为 radiotap
+pub fn write_global_header(out: &mut Vec<u8>) {
+    out.extend_from_slice(&PCAP_MAGIC_MICROS.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes()); // 主版本
+    out.extend_from_slice(&4u16.to_le_bytes()); // 次版本
+    out.extend_from_slice(&0i32.to_le_bytes()); // 时区
+    out.extend_from_slice(&0u32.to_le_bytes()); // 时间精度
+    out.extend_from_slice(&SNAPLEN.to_le_bytes());
+    out.extend_from_slice(&LINKTYPE_IEEE802_11_RADIOTAP.to_le_bytes());
+}
+
+/// 写一条 pcap 记录：记录头、radiotap 头、802.11 帧
+pub fn write_record(out: &mut Vec<u8>, timestamp_us: u64, info: &RadiotapInfo, frame: &[u8]) {
+    let mut radiotap = Vec::new();
+    write_radiotap(&mut radiotap, info);
+    let length = (radiotap.len() + frame.len()) as u32;
+    out.extend_from_slice(&((timestamp_us / 1_000_000) as u32).to_le_bytes());
+    out.extend_from_slice(&((timestamp_us % 1_000_000) as u32).to_le_bytes());
+    out.extend_from_slice(&length.to_le_bytes());
+    out.extend_from_slice(&length.to_le_bytes());
+    out.extend_from_slice(&radiotap);
+    out.extend_from_slice(frame);
+}
+
+/// 把一段 pcap 数据编成串口输出的一行
+pub fn encode_serial_line(bytes: &[u8]) -> String {
+    let mut line = String::with_capacity(SERIAL_LINE_PREFIX.len() + bytes.len() * 2);
+    line.push_str(SERIAL_LINE_PREFIX);
+    for b in bytes {
+        let _ = write!(line, "{:02x}", b);
+    }
+    line
+}
+
+/// 解析串口输出的一行，不是抓包数据的行返回 None
+pub fn decode_serial_line(line: &str) -> Option<Vec<u8>> {
+    let hex = line.trim_end().strip_prefix(SERIAL_LINE_PREFIX)?;
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(core::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// 按文件自身的字节序读整数
+#[derive(Clone, Copy)]
+struct Reader<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn slice(&self, offset: usize, length: usize) -> Result<&'a [u8], PcapError> {
+        offset
+            .checked_add(length)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or(PcapError::Truncated(offset))
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, PcapError> {
+        let b = self.slice(offset, 2)?;
+        let b = [b[0], b[1]];
+        Ok(if self.big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, PcapError> {
+        let b = self.slice(offset, 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Ok(if self.big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    }
+}
+
+/// 按链路类型拆出 radiotap 头和 802.11 帧
+fn split_frame(link_type: u32, data: &[u8], offset: usize) -> Result<(Option<RadiotapInfo>, &[u8]), PcapError> {
+    match link_type {
+        LINKTYPE_IEEE802_11 => Ok((None, data)),
+        LINKTYPE_IEEE802_11_RADIOTAP => {
+            let (info, frame) = parse_radiotap(data).ok_or(PcapError::BadRadiotap(offset))?;
+            Ok((Some(info), frame))
+        }
+        _ => Err(PcapError::UnsupportedLinkType(link_type)),
+    }
+}
+
+/// 解析 radiotap 头，返回关心的字段和其后的 802.11 帧（去掉 FCS）
+pub fn parse_radiotap(data: &[u8]) -> Option<(RadiotapInfo, &[u8])> {
+    let length = u16::from_le_bytes([*data.get(2)?, *data.get(3)?]) as usize;
+    if data[0] != 0 || length < 8 || data.len() < length {
+        return None;
+    }
+    let present = u32::from_le_bytes(data.get(4..8)?.try_into().ok()?);
+
+    // 跳过扩展的 present 字
+    let mut offset = 8;
+    let mut word = present;
+    while word & (1 << 31) != 0 {
+        word = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?);
+        offset += 4;
+    }
+
+    // 关心的字段都在位 0-5，按对齐依次跳过
+    let mut info = RadiotapInfo::default();
+    let mut flags = 0;
+    let fields: [(usize, usize); 6] = [(8, 8), (1, 1), (1, 1), (2, 4), (1, 2), (1, 1)];
+    for (bit, &(align, size)) in fields.iter().enumerate() {
+        if present & (1 << bit) == 0 {
+            continue;
+        }
+        offset = offset.next_multiple_of(align);
+        let field = data[..length].get(offset..offset + size)?;
+        match bit {
+            1 => flags = field[0],
+            2 => info.rate = field[0],
+            3 => info.channel = frequency_channel(u16::from_le_bytes([field[0], field[1]])),
+            5 => info.signal_dbm = Some(field[0] as i8),
+            _ => {}
+        }
+        offset += size;
+    }
+
+    let mut frame = &data[length..];
+    if flags & RADIOTAP_FLAG_FCS != 0 {
+        frame = frame.get(..frame.len().checked_sub(4)?)?;
+    }
+    Some((info, frame))
+}
+
+/// 读取 pcap 或 pcapng 文件里的全部 802.11 帧
+pub fn read_capture(bytes: &[u8]) -> Result<Vec<CapturedFrame<'_>>, PcapError> {
+    let magic = Reader { bytes, big_endian: false }.u32(0)?;
+    if magic == PCAPNG_SECTION_HEADER {
+        return read_pcapng(bytes);
+    }
+    let (big_endian, nanos) = match magic {
+        PCAP_MAGIC_MICROS => (false, false),
+        PCAP_MAGIC_NANOS => (false, true),
+        _ if magic.swap_bytes() == PCAP_MAGIC_MICROS => (true, false),
+        _ if magic.swap_bytes() == PCAP_MAGIC_NANOS => (true, true),
+        _ => return Err(PcapError::UnknownFormat(magic)),
+    };
+    let reader = Reader { bytes, big_endian };
+    let link_type = reader.u32(20)?;
+
+    let mut frames = Vec::new();
+    let mut offset = 24;
+    while offset < bytes.len() {
+        let seconds = reader.u32(offset)? as u64;
+        let fraction = reader.u32(offset + 4)? as u64;
+        let captured = reader.u32(offset + 8)? as usize;
+        let data = reader.slice(offset + 16, captured)?;
+        let (radiotap, frame) = split_frame(link_type, data, offset)?;
+        let fraction_us = if nanos { fraction / 1000 } else { fraction };
+        frames.push(CapturedFrame { timestamp_us: seconds * 1_000_000 + fraction_us, radiotap, frame });
+        offset += 16 + captured;
+    }
+    Ok(frames)
+}
+
+/// pcapng 接口：链路类型和每秒的时间戳单位数
+struct Interface {
+    link_type: u32,
+    units_per_second: u64,
+}
+
+fn interface_resolution(reader: Reader, mut offset: usize, end: usize) -> Result<u64, PcapError> {
+    while offset + 4 <= end {
+        let code = reader.u16(offset)?;
+        let length = reader.u16(offset + 2)? as usize;
+        if code == 0 {
+            break;
+        }
+        if code == PCAPNG_OPTION_TSRESOL && length >= 1 {
+            let value = reader.slice(offset + 4, 1)?[0];
+            let exponent = (value & 0x7f) as u32;
+            let base: u64 = if value & 0x80 != 0 { 2 } else { 10 };
+            return base.checked_pow(exponent).ok_or(PcapError::Truncated(offset));
+        }
+        offset += 4 + length.next_multiple_of(4);
+    }
+    Ok(1_000_000)
+}
+
+fn read_pcapng(bytes: &[u8]) -> Result<Vec<CapturedFrame<'_>>, PcapError> {
+    let mut frames = Vec::new();
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut reader = Reader { bytes, big_endian: false };
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let block_type = reader.u32(offset)?;
+        if block_type == PCAPNG_SECTION_HEADER {
+            // 每一节自带字节序，接口列表重新开始
+            let order = Reader { bytes, big_endian: false }.u32(offset + 8)?;
+            reader.big_endian = match order {
+                PCAPNG_BYTE_ORDER_MAGIC => false,
+                _ if order.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => true,
+                _ => return Err(PcapError::UnknownFormat(order)),
+            };
+            interfaces.clear();
+        }
+        let length = reader.u32(offset + 4)? as usize;
+        if length < 12 || length % 4 != 0 {
+            return Err(PcapError::Truncated(offset));
+        }
+        let end = offset + length;
+        reader.slice(offset, length)?;
+
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => interfaces.push(Interface {
+                link_type: reader.u16(offset + 8)? as u32,
+                units_per_second: interface_resolution(reader, offset + 16, end - 4)?,
+            }),
+            PCAPNG_ENHANCED_PACKET => {
+                let interface = interfaces.get(reader.u32(offset + 8)? as usize).ok_or(PcapError::Truncated(offset))?;
+                let timestamp = ((reader.u32(offset + 12)? as u64) << 32) | reader.u32(offset + 16)? as u64;
+                let captured = reader.u32(offset + 20)? as usize;
+                if 28 + captured + 4 > length {
+                    return Err(PcapError::Truncated(offset));
+                }
+                let data = reader.slice(offset + 28, captured)?;
+                let (radiotap, frame) = split_frame(interface.link_type, data, offset)?;
+                let timestamp_us = (timestamp as u128 * 1_000_000 / interface.units_per_second as u128) as u64;
+                frames.push(CapturedFrame { timestamp_us, radiotap, frame });
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                // 简单包没有时间戳，属于第一个接口，长度由块长度推出
+                let interface = interfaces.first().ok_or(PcapError::Truncated(offset))?;
+                if length < 16 {
+                    return Err(PcapError::Truncated(offset));
+                }
+                let original = reader.u32(offset + 8)? as usize;
+                let data = reader.slice(offset + 12, original.min(length - 16))?;
+                let (radiotap, frame) = split_frame(interface.link_type, data, offset)?;
+                frames.push(CapturedFrame { timestamp_us: 0, radiotap, frame });
+            }
+            _ => {}
+        }
+        offset = end;
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: &[u8] = &[0x80, 0x00, 0x00, 0x00, 0xff, 0xff];
+
+    #[test]
+    fn radiotap_header_layout() {
+        let mut out = Vec::new();
+        write_radiotap(&mut out, &RadiotapInfo { rate: 2, channel: 6, signal_dbm: Some(-40) });
+        assert_eq!(
+            out,
+            [0x00, 0x00, 0x0f, 0x00, 0x2e, 0x00, 0x00, 0x00, 0x00, 0x02, 0x85, 0x09, 0xa0, 0x00, 0xd8]
+        );
+
+        // 6 Mbps 是 OFDM 速率
+        out.clear();
+        write_radiotap(&mut out, &RadiotapInfo { rate: 12, channel: 6, signal_dbm: None });
+        assert_eq!(out[9..], [0x0c, 0x85, 0x09, 0xc0, 0x00]);
+    }
+
+    #[test]
+    fn pcap_round_trip() {
+        let tx = RadiotapInfo { rate: 2, channel: 11, signal_dbm: None };
+        let mut out = Vec::new();
+        write_global_header(&mut out);
+        write_record(&mut out, 3_000_042, &tx, FRAME);
+        write_record(&mut out, 3_100_000, &tx, &FRAME[..4]);
+        let ofdm = RadiotapInfo { rate: 108, channel: 1, signal_dbm: Some(-67) };
+        write_record(&mut out, 3_200_000, &ofdm, FRAME);
+
+        let frames = read_capture(&out).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], CapturedFrame { timestamp_us: 3_000_042, radiotap: Some(tx), frame: FRAME });
+        assert_eq!(frames[1].frame, &FRAME[..4]);
+        assert_eq!(frames[2], CapturedFrame { timestamp_us: 3_200_000, radiotap: Some(ofdm), frame: FRAME });
+        assert_eq!(read_capture(&out[..out.len() - 1]), Err(PcapError::Truncated(24 + 16 + 20 + 16 + 18 + 16)));
+    }
+
+    /// pcapng 节头块加一个 radiotap 接口描述块，纳秒精度
+    fn pcapng_header() -> Vec<u8> {
+        let mut out = Vec::new();
+        // 节头块
+        out.extend_from_slice(&PCAPNG_SECTION_HEADER.to_le_bytes());
+        out.extend_from_slice(&28u32.to_le_bytes());
+        out.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes());
+        out.extend_from_slice(&[1, 0, 0, 0]);
+        out.extend_from_slice(&u64::MAX.to_le_bytes());
+        out.extend_from_slice(&28u32.to_le_bytes());
+        // 接口描述块，纳秒精度
+        out.extend_from_slice(&PCAPNG_INTERFACE_DESCRIPTION.to_le_bytes());
+        out.extend_from_slice(&32u32.to_le_bytes());
+        out.extend_from_slice(&(LINKTYPE_IEEE802_11_RADIOTAP as u16).to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(&SNAPLEN.to_le_bytes());
+        out.extend_from_slice(&[9, 0, 1, 0, 9, 0, 0, 0, 0, 0, 0, 0]);
+        out.extend_from_slice(&32u32.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn reads_pcapng_and_strips_fcs() {
+        let mut out = pcapng_header();
+        // 增强包块：带 FCS 的 radiotap 帧
+        let data = [0x00, 0x00, 0x09, 0x00, 0x02, 0x00, 0x00, 0x00, 0x10, 0xaa, 0xbb, 1, 2, 3, 4];
+        out.extend_from_slice(&PCAPNG_ENHANCED_PACKET.to_le_bytes());
+        out.extend_from_slice(&48u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&2_000_000_000u32.to_le_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&data);
+        out.push(0);
+        out.extend_from_slice(&48u32.to_le_bytes());
+
+        let frames = read_capture(&out).unwrap();
+        assert_eq!(
+            frames,
+            [CapturedFrame { timestamp_us: 2_000_000, radiotap: Some(RadiotapInfo::default()), frame: &[0xaa, 0xbb] }]
+        );
+    }
+
+    #[test]
+    fn rejects_empty_simple_packet_block() {
+        // 只有块头和块尾的 12 字节简单包块放不下原始长度字段
+        let mut out = pcapng_header();
+        let offset = out.len();
+        out.extend_from_slice(&PCAPNG_SIMPLE_PACKET.to_le_bytes());
+        out.extend_from_slice(&12u32.to_le_bytes());
+        out.extend_from_slice(&12u32.to_le_bytes());
+        assert_eq!(read_capture(&out), Err(PcapError::Truncated(offset)));
+    }
+
+    #[test]
+    fn serial_lines() {
+        let line = encode_serial_line(&[0xa1, 0xb2, 0x00]);
+        assert_eq!(line, "PCAP a1b200");
+        assert_eq!(decode_serial_line("PCAP a1b200\r"), Some(alloc::vec![0xa1, 0xb2, 0x00]));
+        assert_eq!(decode_serial_line("I (123) Transmitted 100 beacon frames"), None);
+        assert_eq!(decode_serial_line("PCAP a1b"), None);
+    }
+}
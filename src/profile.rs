@@ -0,0 +1,188 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::beacon::{SsidPolicy, RID_VENDOR_PREFIX};
+use crate::config::{StandardProfile, TransmitterConfig};
+use crate::message::french_message::FRENCH_VENDOR_PREFIX;
+use crate::schedule::{MessageKind, MessageRule, DEFAULT_RULES, STATIC_MAX_INTERVAL_MS};
+
+/// 系统报文里的等级分类归属区域
+pub const CLASSIFICATION_UNDECLARED: u8 = 0;
+pub const CLASSIFICATION_EU: u8 = 1;
+pub const CLASSIFICATION_GB: u8 = 2;
+/// 欧盟运行类别：开放、特定、审定
+pub const EU_CATEGORY_OPEN: u8 = 1;
+pub const EU_CATEGORY_CERTIFIED: u8 = 3;
+/// 欧盟等级 C0-C6 记为 1-7
+pub const EU_CLASS_C6: u8 = 7;
+
+/// F3411 系统报文时间戳的纪元 2019-01-01 00:00:00 UTC，以 Unix 秒表示
+pub const F3411_EPOCH_UNIX: u32 = 1_546_300_800;
+
+/// 配置不满足标准的要求
+#[derive(Debug, PartialEq)]
+pub enum ProfileError {
+    MissingOperatorId,     // 欧盟要求运营人 ID
+    InvalidClassification, // 欧盟运行类别或等级未填或超出范围
+}
+
+impl StandardProfile {
+    pub fn name(self) -> &'static str {
+        match self {
+            StandardProfile::Gb => "gb",
+            StandardProfile::Astm => "astm",
+            StandardProfile::AsdStan => "asd-stan",
+            StandardProfile::French => "fr",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [StandardProfile::Gb, StandardProfile::Astm, StandardProfile::AsdStan, StandardProfile::French]
+            .into_iter()
+            .find(|profile| profile.name().eq_ignore_ascii_case(name))
+    }
+
+    /// 报文头低 4 位的协议版本：国标与 EN 4709-002 沿用 F3411-19 的 1，ASTM 用 F3411-22a 的 2
+    /// 法国信标不用整包，NAN 与蓝牙仍按 F3411-19 发
+    pub fn message_version(self) -> u8 {
+        match self {
+            StandardProfile::Gb | StandardProfile::AsdStan | StandardProfile::French => 1,
+            StandardProfile::Astm => 2,
+        }
+    }
+
+    /// 整包头：类型 0xF 加协议版本
+    pub fn pack_header(self) -> u8 {
+        0xF0 | self.message_version()
+    }
+
+    /// 整包后是否带 CRC16 校验和与 3 字节预留，只有仿大疆的国标格式有
+    pub fn has_checksum(self) -> bool {
+        self == StandardProfile::Gb
+    }
+
+    /// 国标仿大疆 "RID-" 加 UAS ID，ASTM、欧盟与法国没有约定，直接用 UAS ID
+    pub fn ssid_policy(self) -> SsidPolicy {
+        match self {
+            StandardProfile::Gb => SsidPolicy::Prefixed("RID-"),
+            StandardProfile::Astm | StandardProfile::AsdStan | StandardProfile::French => SsidPolicy::Prefixed(""),
+        }
+    }
+
+    /// 系统报文时间戳：国标沿用 Unix 秒，其余按 F3411 从 2019-01-01 起算（法国的 NAN 与蓝牙也按 F3411 发）
+    pub fn system_timestamp(self, unix_time: u32) -> u32 {
+        match self {
+            StandardProfile::Gb => unix_time,
+            StandardProfile::Astm | StandardProfile::AsdStan | StandardProfile::French => {
+                unix_time.saturating_sub(F3411_EPOCH_UNIX)
+            }
+        }
+    }
+
+    /// 信标厂商元素：法国用自己的 OUI 和 TLV，其余都是 ASTM 整包
+    pub fn vendor_prefix(self) -> &'static [u8; 4] {
+        match self {
+            StandardProfile::French => &FRENCH_VENDOR_PREFIX,
+            _ => &RID_VENDOR_PREFIX,
+        }
+    }
+
+    pub fn classification_type(self) -> u8 {
+        match self {
+            StandardProfile::Gb => CLASSIFICATION_GB,
+            StandardProfile::Astm | StandardProfile::French => CLASSIFICATION_UNDECLARED,
+            StandardProfile::AsdStan => CLASSIFICATION_EU,
+        }
+    }
+
+    /// 整包里的消息，按发送顺序；自我描述为空时不发，欧盟总是带运营人 ID
+    pub fn pack_messages(self, config: &TransmitterConfig) -> Vec<MessageKind> {
+        match self {
+            StandardProfile::Gb => vec![MessageKind::BasicId, MessageKind::Location, MessageKind::System],
+            StandardProfile::Astm | StandardProfile::AsdStan | StandardProfile::French => {
+                let mut messages = vec![MessageKind::BasicId, MessageKind::Location];
+                if !config.self_id.is_empty() {
+                    messages.push(MessageKind::SelfId);
+                }
+                messages.push(MessageKind::System);
+                if self == StandardProfile::AsdStan || !config.operator_id.is_empty() {
+                    messages.push(MessageKind::OperatorId);
+                }
+                messages
+            }
+        }
+    }
+
+    /// 发送频率要求，欧盟另外要求运营人 ID 与静态报文同频
+    pub fn rules(self) -> Vec<MessageRule> {
+        let mut rules = DEFAULT_RULES.to_vec();
+        if self == StandardProfile::AsdStan {
+            rules.push(MessageRule {
+                kind: MessageKind::OperatorId,
+                max_interval_ms: STATIC_MAX_INTERVAL_MS,
+                max_age_ms: None,
+                required: true,
+            });
+        }
+        rules
+    }
+
+    /// 检查配置是否满足标准的强制要求
+    pub fn check(self, config: &TransmitterConfig) -> Result<(), ProfileError> {
+        if self != StandardProfile::AsdStan {
+            return Ok(());
+        }
+        if config.operator_id.is_empty() {
+            return Err(ProfileError::MissingOperatorId);
+        }
+        if !(EU_CATEGORY_OPEN..=EU_CATEGORY_CERTIFIED).contains(&config.ua_category) || config.ua_class > EU_CLASS_C6 {
+            return Err(ProfileError::InvalidClassification);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for profile in [StandardProfile::Gb, StandardProfile::Astm, StandardProfile::AsdStan, StandardProfile::French] {
+            assert_eq!(StandardProfile::from_name(profile.name()), Some(profile));
+        }
+        assert_eq!(StandardProfile::from_name("ASD-STAN"), Some(StandardProfile::AsdStan));
+        assert_eq!(StandardProfile::from_name("faa"), None);
+    }
+
+    #[test]
+    fn pack_contents_follow_profile() {
+        let mut config = TransmitterConfig::default();
+        assert_eq!(StandardProfile::Astm.pack_messages(&config).len(), 3);
+        assert_eq!(
+            StandardProfile::AsdStan.pack_messages(&config),
+            [MessageKind::BasicId, MessageKind::Location, MessageKind::System, MessageKind::OperatorId]
+        );
+        config.set_self_id("Survey flight").unwrap();
+        config.set_operator_id("FIN87astrdge12k8").unwrap();
+        assert_eq!(StandardProfile::Gb.pack_messages(&config).len(), 3);
+        assert_eq!(StandardProfile::Astm.pack_messages(&config).len(), 5);
+        assert_eq!(StandardProfile::Astm.pack_messages(&config)[2], MessageKind::SelfId);
+    }
+
+    #[test]
+    fn eu_requirements() {
+        let mut config = TransmitterConfig::default();
+        assert_eq!(StandardProfile::Gb.check(&config), Ok(()));
+        assert_eq!(StandardProfile::AsdStan.check(&config), Err(ProfileError::MissingOperatorId));
+        config.set_operator_id("FIN87astrdge12k8").unwrap();
+        assert_eq!(StandardProfile::AsdStan.check(&config), Err(ProfileError::InvalidClassification));
+        config.set_classification(EU_CATEGORY_OPEN, 2).unwrap();
+        assert_eq!(StandardProfile::AsdStan.check(&config), Ok(()));
+
+        let rules = StandardProfile::AsdStan.rules();
+        let operator = rules.iter().find(|rule| rule.kind == MessageKind::OperatorId).unwrap();
+        assert!(operator.required);
+        assert!(StandardProfile::Astm.rules().iter().all(|rule| rule.kind != MessageKind::OperatorId));
+    }
+}
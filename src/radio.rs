@@ -0,0 +1,132 @@
+use ieee80211::elements::rates::EncodedRate;
+
+/// Wi-Fi 发射功率范围（dBm），芯片最大 20 dBm，另受所在国家的上限限制
+pub const MIN_TX_POWER_DBM: i8 = 2;
+pub const MAX_TX_POWER_DBM: i8 = 20;
+
+/// 信标的发射速率：2.4G 的 802.11b (DSSS/CCK) 与 802.11g (OFDM) 速率，
+/// 信标的支持速率元素只声明这一个速率，并标为基本速率
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PhyRate {
+    #[default]
+    Mbps1 = 0,
+    Mbps2 = 1,
+    Mbps5_5 = 2,
+    Mbps11 = 3,
+    Mbps6 = 4,
+    Mbps9 = 5,
+    Mbps12 = 6,
+    Mbps18 = 7,
+    Mbps24 = 8,
+    Mbps36 = 9,
+    Mbps48 = 10,
+    Mbps54 = 11,
+}
+
+impl PhyRate {
+    pub const ALL: [PhyRate; 12] = [
+        PhyRate::Mbps1,
+        PhyRate::Mbps2,
+        PhyRate::Mbps5_5,
+        PhyRate::Mbps11,
+        PhyRate::Mbps6,
+        PhyRate::Mbps9,
+        PhyRate::Mbps12,
+        PhyRate::Mbps18,
+        PhyRate::Mbps24,
+        PhyRate::Mbps36,
+        PhyRate::Mbps48,
+        PhyRate::Mbps54,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    pub fn kbps(self) -> u32 {
+        match self {
+            PhyRate::Mbps1 => 1000,
+            PhyRate::Mbps2 => 2000,
+            PhyRate::Mbps5_5 => 5500,
+            PhyRate::Mbps11 => 11_000,
+            PhyRate::Mbps6 => 6000,
+            PhyRate::Mbps9 => 9000,
+            PhyRate::Mbps12 => 12_000,
+            PhyRate::Mbps18 => 18_000,
+            PhyRate::Mbps24 => 24_000,
+            PhyRate::Mbps36 => 36_000,
+            PhyRate::Mbps48 => 48_000,
+            PhyRate::Mbps54 => 54_000,
+        }
+    }
+
+    /// 控制台里的写法，单位 Mbps
+    pub fn name(self) -> &'static str {
+        match self {
+            PhyRate::Mbps1 => "1",
+            PhyRate::Mbps2 => "2",
+            PhyRate::Mbps5_5 => "5.5",
+            PhyRate::Mbps11 => "11",
+            PhyRate::Mbps6 => "6",
+            PhyRate::Mbps9 => "9",
+            PhyRate::Mbps12 => "12",
+            PhyRate::Mbps18 => "18",
+            PhyRate::Mbps24 => "24",
+            PhyRate::Mbps36 => "36",
+            PhyRate::Mbps48 => "48",
+            PhyRate::Mbps54 => "54",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rate| rate.name() == name)
+    }
+
+    /// 6 Mbps 及以上是 802.11g 的 OFDM 速率
+    pub fn is_ofdm(self) -> bool {
+        self as u8 >= PhyRate::Mbps6 as u8
+    }
+
+    /// 支持速率元素里的编码，标为基本速率
+    pub fn encoded(self) -> EncodedRate {
+        EncodedRate::from_rate_in_kbps(self.kbps() as usize, true)
+    }
+
+    /// radiotap 头里的速率，单位 500 kbps
+    pub fn radiotap_rate(self) -> u8 {
+        (self.kbps() / 500) as u8
+    }
+
+    /// 发 length 字节（含 FCS）的时长，单位微秒：
+    /// DSSS 长前导码加 PLCP 头 192 微秒；OFDM 前导 20 微秒，每个符号 4 微秒，另有 16 位 SERVICE 和 6 位尾比特
+    pub fn airtime_us(self, length: usize) -> u32 {
+        let bits = length as u32 * 8;
+        if self.is_ofdm() {
+            let bits_per_symbol = self.kbps() * 4 / 1000;
+            20 + 4 * (16 + bits + 6).div_ceil(bits_per_symbol)
+        } else {
+            192 + (bits * 1000).div_ceil(self.kbps())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_and_airtime() {
+        assert_eq!(PhyRate::from_name("5.5"), Some(PhyRate::Mbps5_5));
+        assert_eq!(PhyRate::from_name("7"), None);
+        assert!(PhyRate::ALL.iter().all(|&rate| PhyRate::from_u8(rate as u8) == Some(rate)));
+        assert_eq!((PhyRate::Mbps1.radiotap_rate(), PhyRate::Mbps5_5.radiotap_rate()), (2, 11));
+        assert!(!PhyRate::Mbps11.is_ofdm() && PhyRate::Mbps6.is_ofdm());
+        // 支持速率元素：1 Mbps 基本速率为 0x82，6 Mbps 为 0x8c
+        assert_eq!((PhyRate::Mbps1.encoded().into_bits(), PhyRate::Mbps6.encoded().into_bits()), (0x82, 0x8c));
+
+        // 104 字节：1 Mbps 为 192 + 832 微秒；6 Mbps 每符号 24 位，854 位共 36 个符号
+        assert_eq!(PhyRate::Mbps1.airtime_us(104), 192 + 832);
+        assert_eq!(PhyRate::Mbps6.airtime_us(104), 20 + 4 * 36);
+        assert!(PhyRate::Mbps54.airtime_us(104) < PhyRate::Mbps11.airtime_us(104));
+    }
+}
@@ -0,0 +1,207 @@
+use core::fmt;
+
+/// 启动过程中会失败的步骤
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BringUpStep {
+    RadioInit = 1,         // 无线协议栈初始化
+    WifiController = 2,    // 创建 Wi-Fi 控制器
+    WifiMode = 3,          // 设置 Wi-Fi 模式
+    WifiConfiguration = 4, // 设置 STA 配置
+    WifiStart = 5,         // 启动 Wi-Fi
+}
+
+impl BringUpStep {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(BringUpStep::RadioInit),
+            2 => Some(BringUpStep::WifiController),
+            3 => Some(BringUpStep::WifiMode),
+            4 => Some(BringUpStep::WifiConfiguration),
+            5 => Some(BringUpStep::WifiStart),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BringUpStep::RadioInit => "radio init",
+            BringUpStep::WifiController => "wifi controller",
+            BringUpStep::WifiMode => "wifi mode",
+            BringUpStep::WifiConfiguration => "wifi configuration",
+            BringUpStep::WifiStart => "wifi start",
+        }
+    }
+}
+
+/// 启动错误：失败的步骤、尝试次数和驱动最后一次返回的错误
+#[derive(Debug, Clone, PartialEq)]
+pub struct BringUpError<E> {
+    pub step: BringUpStep,
+    pub attempts: u32,
+    pub error: E,
+}
+
+/// 指数退避：第 n 次重试前等待 initial_ms * 2^n，不超过 max_ms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    pub initial_ms: u32,
+    pub max_ms: u32,
+    pub max_attempts: u32, // 包括第一次在内的总尝试次数
+}
+
+impl Backoff {
+    pub fn delay_ms(&self, retry: u32) -> u32 {
+        let delay = (self.initial_ms as u64) << retry.min(32);
+        delay.min(self.max_ms as u64) as u32
+    }
+}
+
+/// 执行 attempt，失败时调用 on_retry(错误, 等待毫秒数) 后重试（由它等待并喂狗），
+/// 用完尝试次数后返回最后一次的错误
+pub fn retry<T, E>(
+    step: BringUpStep,
+    backoff: Backoff,
+    mut attempt: impl FnMut() -> Result<T, E>,
+    mut on_retry: impl FnMut(&E, u32),
+) -> Result<T, BringUpError<E>> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match attempt() {
+            Ok(value) => return Ok(value),
+            Err(error) if attempts >= backoff.max_attempts => return Err(BringUpError { step, attempts, error }),
+            Err(error) => on_retry(&error, backoff.delay_ms(attempts - 1)),
+        }
+    }
+}
+
+/// 上次复位的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetCause {
+    Interrupted = 0,   // 没有留下记录：看门狗、掉电或外部复位，具体看芯片的复位原因
+    BringUpFailed = 1, // 启动步骤重试用完后主动重启
+    Panic = 2,         // panic 后重启
+}
+
+/// 保存在跨复位保留的 RTC 内存里的复位记录
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResetRecord {
+    pub cause: ResetCause,
+    pub step: Option<BringUpStep>, // 启动失败时失败的步骤
+    pub boot_failures: u8,         // 连续没有进入主循环的启动次数
+}
+
+/// 记录魔数 "RIDR"，与负载异或后存放，上电后的随机内容不会被当成记录
+const RESET_RECORD_MAGIC: u32 = 0x5249_4452;
+/// 连续启动失败后再次启动前的等待
+const BOOT_BACKOFF: Backoff = Backoff { initial_ms: 1000, max_ms: 60_000, max_attempts: u32::MAX };
+
+impl ResetRecord {
+    /// 本次启动开始时写入的记录：启动中途复位时原因为 Interrupted，失败次数加一
+    pub fn booting(previous: Option<ResetRecord>) -> Self {
+        let boot_failures = previous.map_or(0, |record| record.boot_failures).saturating_add(1);
+        Self { cause: ResetCause::Interrupted, step: None, boot_failures }
+    }
+
+    /// 进入主循环后写入的记录
+    pub fn running() -> Self {
+        Self { cause: ResetCause::Interrupted, step: None, boot_failures: 0 }
+    }
+
+    /// 启动前的等待：上次启动失败越多，等得越久
+    pub fn boot_delay_ms(&self) -> u32 {
+        match self.boot_failures {
+            0 | 1 => 0,
+            failures => BOOT_BACKOFF.delay_ms(failures as u32 - 2),
+        }
+    }
+
+    pub fn encode(&self) -> [u32; 2] {
+        let payload = self.cause as u32 | (self.step.map_or(0, |step| step as u32) << 8) | ((self.boot_failures as u32) << 16);
+        [RESET_RECORD_MAGIC ^ payload, payload]
+    }
+
+    pub fn decode(words: [u32; 2]) -> Option<Self> {
+        let [check, payload] = words;
+        if check != RESET_RECORD_MAGIC ^ payload || payload >> 24 != 0 {
+            return None;
+        }
+        let cause = match payload & 0xff {
+            0 => ResetCause::Interrupted,
+            1 => ResetCause::BringUpFailed,
+            2 => ResetCause::Panic,
+            _ => return None,
+        };
+        let step = match (payload >> 8) as u8 {
+            0 => None,
+            step => Some(BringUpStep::from_u8(step)?),
+        };
+        Some(Self { cause, step, boot_failures: (payload >> 16) as u8 })
+    }
+}
+
+impl fmt::Display for ResetRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.cause, self.step) {
+            (ResetCause::BringUpFailed, Some(step)) => write!(f, "bring-up failed at {}", step.name())?,
+            (ResetCause::BringUpFailed, None) => write!(f, "bring-up failed")?,
+            (ResetCause::Panic, _) => write!(f, "panic")?,
+            (ResetCause::Interrupted, _) => write!(f, "no record (watchdog, brown-out or external reset)")?,
+        }
+        if self.boot_failures > 0 {
+            write!(f, ", failed boots in a row: {}", self.boot_failures)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn retry_backs_off_then_gives_up() {
+        let backoff = Backoff { initial_ms: 100, max_ms: 1000, max_attempts: 6 };
+        assert_eq!((0..6).map(|retry| backoff.delay_ms(retry)).collect::<Vec<u32>>(), [100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(Backoff { initial_ms: 100, max_ms: 1000, max_attempts: 1 }.delay_ms(40), 1000);
+
+        let mut delays = Vec::new();
+        let mut calls = 0;
+        let result = retry(BringUpStep::WifiStart, backoff, || {
+            calls += 1;
+            if calls < 3 { Err(calls) } else { Ok("started") }
+        }, |_, delay| delays.push(delay));
+        assert_eq!((result, delays.as_slice()), (Ok("started"), [100, 200].as_slice()));
+
+        let result: Result<(), _> = retry(BringUpStep::WifiMode, backoff, || Err("busy"), |_, _| {});
+        assert_eq!(result, Err(BringUpError { step: BringUpStep::WifiMode, attempts: 6, error: "busy" }));
+    }
+
+    #[test]
+    fn reset_record_survives_encoding() {
+        let record = ResetRecord { cause: ResetCause::BringUpFailed, step: Some(BringUpStep::WifiStart), boot_failures: 3 };
+        assert_eq!(ResetRecord::decode(record.encode()), Some(record));
+        assert_eq!(record.to_string(), "bring-up failed at wifi start, failed boots in a row: 3");
+        // 上电后 RTC 内存是随机内容
+        assert_eq!(ResetRecord::decode([0, 0]), None);
+        assert_eq!(ResetRecord::decode([0xdead_beef, 0x1234_5678]), None);
+        // 校验对但步骤不存在
+        let payload = (record.encode()[1] & !0xff00) | 0x0900;
+        assert_eq!(ResetRecord::decode([RESET_RECORD_MAGIC ^ payload, payload]), None);
+    }
+
+    #[test]
+    fn failed_boots_back_off() {
+        let first = ResetRecord::booting(None);
+        assert_eq!((first.boot_failures, first.boot_delay_ms()), (1, 0));
+        let second = ResetRecord::booting(Some(first));
+        let delays: Vec<u32> = core::iter::successors(Some(second), |&record| Some(ResetRecord::booting(Some(record))))
+            .take(9)
+            .map(|record| record.boot_delay_ms())
+            .collect();
+        assert_eq!(delays, [1000, 2000, 4000, 8000, 16_000, 32_000, 60_000, 60_000, 60_000]);
+        assert_eq!(ResetRecord::booting(Some(ResetRecord::running())).boot_failures, 1);
+    }
+}
@@ -0,0 +1,17 @@
+// 法规表的数据，src/channel.rs 和 build.rs 都 include! 这个文件，
+// 构建时的国家码、信道和发射功率按同一张表检查
+[
+    RegulatoryDomain { country: *b"CN", last_channel: 13, max_tx_power_dbm: 20 },
+    RegulatoryDomain { country: *b"US", last_channel: 11, max_tx_power_dbm: 30 },
+    RegulatoryDomain { country: *b"CA", last_channel: 11, max_tx_power_dbm: 30 },
+    RegulatoryDomain { country: *b"TW", last_channel: 11, max_tx_power_dbm: 30 },
+    RegulatoryDomain { country: *b"JP", last_channel: 14, max_tx_power_dbm: 20 },
+    RegulatoryDomain { country: *b"KR", last_channel: 13, max_tx_power_dbm: 23 },
+    RegulatoryDomain { country: *b"AU", last_channel: 13, max_tx_power_dbm: 30 },
+    RegulatoryDomain { country: *b"DE", last_channel: 13, max_tx_power_dbm: 20 },
+    RegulatoryDomain { country: *b"FR", last_channel: 13, max_tx_power_dbm: 20 },
+    RegulatoryDomain { country: *b"GB", last_channel: 13, max_tx_power_dbm: 20 },
+    RegulatoryDomain { country: *b"IT", last_channel: 13, max_tx_power_dbm: 20 },
+    RegulatoryDomain { country: *b"ES", last_channel: 13, max_tx_power_dbm: 20 },
+    RegulatoryDomain { country: *b"NL", last_channel: 13, max_tx_power_dbm: 20 },
+]
@@ -0,0 +1,297 @@
+use core::fmt;
+
+use alloc::vec::Vec;
+
+/// 位置报文至少 1 Hz，内容不超过 1 秒
+pub const LOCATION_MAX_INTERVAL_MS: u32 = 1000;
+pub const LOCATION_MAX_AGE_MS: u32 = 1000;
+/// 静态报文（基本 ID、系统、运营人 ID）至少每 3 秒一次
+pub const STATIC_MAX_INTERVAL_MS: u32 = 3000;
+
+/// RID 消息种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    BasicId,
+    Location,
+    SelfId,
+    System,
+    OperatorId,
+}
+
+impl MessageKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageKind::BasicId => "basic-id",
+            MessageKind::Location => "location",
+            MessageKind::SelfId => "self-id",
+            MessageKind::System => "system",
+            MessageKind::OperatorId => "operator-id",
+        }
+    }
+}
+
+/// 一种消息的发送要求
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MessageRule {
+    pub kind: MessageKind,
+    pub max_interval_ms: u32,     // 两次发送的最大间隔
+    pub max_age_ms: Option<u32>,  // 发送时内容的最大年龄
+    pub required: bool,           // 不满足就不合规；可选消息只计入报告
+}
+
+/// 默认规则：位置、基本 ID、系统必发，自我描述可选
+pub const DEFAULT_RULES: [MessageRule; 4] = [
+    MessageRule { kind: MessageKind::Location, max_interval_ms: LOCATION_MAX_INTERVAL_MS, max_age_ms: Some(LOCATION_MAX_AGE_MS), required: true },
+    MessageRule { kind: MessageKind::BasicId, max_interval_ms: STATIC_MAX_INTERVAL_MS, max_age_ms: None, required: true },
+    MessageRule { kind: MessageKind::System, max_interval_ms: STATIC_MAX_INTERVAL_MS, max_age_ms: None, required: true },
+    MessageRule { kind: MessageKind::SelfId, max_interval_ms: STATIC_MAX_INTERVAL_MS, max_age_ms: None, required: false },
+];
+
+#[derive(Debug, Clone, Copy, Default)]
+struct MessageState {
+    first_sent_ms: Option<u64>,
+    last_sent_ms: Option<u64>,
+    updated_ms: Option<u64>,
+    sent: u32,
+    max_gap_ms: u32,
+    late: u32,
+    stale: u32,
+}
+
+/// 按规则跟踪每种消息的发送时间和内容新鲜度，决定下一条发什么，并统计合规情况
+/// 时间由调用方传入，主机测试可用模拟时钟
+#[derive(Debug, Clone)]
+pub struct MessageScheduler {
+    rules: Vec<MessageRule>,
+    states: Vec<MessageState>,
+}
+
+impl MessageScheduler {
+    /// 规则顺序即截止时间相同时的优先级
+    pub fn new(rules: &[MessageRule]) -> Self {
+        Self { rules: rules.to_vec(), states: alloc::vec![MessageState::default(); rules.len()] }
+    }
+
+    pub fn rules(&self) -> &[MessageRule] {
+        &self.rules
+    }
+
+    fn index(&self, kind: MessageKind) -> Option<usize> {
+        self.rules.iter().position(|rule| rule.kind == kind)
+    }
+
+    /// 消息内容更新了，比如收到新的定位
+    pub fn data_updated(&mut self, kind: MessageKind, now_ms: u64) {
+        if let Some(i) = self.index(kind) {
+            self.states[i].updated_ms = Some(now_ms);
+        }
+    }
+
+    /// 内容超过最大年龄，发送前应重新组包
+    pub fn is_stale(&self, kind: MessageKind, now_ms: u64) -> bool {
+        let Some(i) = self.index(kind) else { return false };
+        match (self.rules[i].max_age_ms, self.states[i].updated_ms) {
+            (Some(max_age), Some(updated)) => now_ms.saturating_sub(updated) > max_age as u64,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// 最晚发送时间，从未发过的立即到期
+    pub fn deadline_ms(&self, kind: MessageKind) -> Option<u64> {
+        let i = self.index(kind)?;
+        Some(self.states[i].last_sent_ms.map_or(0, |last| last + self.rules[i].max_interval_ms as u64))
+    }
+
+    /// 必发消息里最早的截止时间
+    pub fn next_deadline_ms(&self) -> Option<u64> {
+        self.rules.iter().filter(|rule| rule.required).filter_map(|rule| self.deadline_ms(rule.kind)).min()
+    }
+
+    /// 单条消息的发送方式（蓝牙传统广播）下一个时隙发什么：截止时间最早的先发
+    pub fn next_message(&self) -> Option<MessageKind> {
+        self.rules
+            .iter()
+            .filter_map(|rule| Some((self.deadline_ms(rule.kind)?, rule.kind)))
+            .min_by_key(|(deadline, _)| *deadline)
+            .map(|(_, kind)| kind)
+    }
+
+    /// 记录一次发送
+    pub fn record_sent(&mut self, kind: MessageKind, now_ms: u64) {
+        let stale = self.is_stale(kind, now_ms);
+        let Some(i) = self.index(kind) else { return };
+        let rule = self.rules[i];
+        let state = &mut self.states[i];
+        if let Some(last) = state.last_sent_ms {
+            let gap = now_ms.saturating_sub(last).min(u32::MAX as u64) as u32;
+            state.max_gap_ms = state.max_gap_ms.max(gap);
+            if gap > rule.max_interval_ms {
+                state.late += 1;
+            }
+        }
+        if stale {
+            state.stale += 1;
+        }
+        state.first_sent_ms.get_or_insert(now_ms);
+        state.last_sent_ms = Some(now_ms);
+        state.sent += 1;
+    }
+
+    /// 到 now_ms 为止的合规报告
+    pub fn report(&self, now_ms: u64) -> ComplianceReport {
+        let entries = self
+            .rules
+            .iter()
+            .zip(&self.states)
+            .map(|(rule, state)| {
+                // 从第一次发送算起的平均频率，单位毫赫兹
+                let rate_mhz = match state.first_sent_ms {
+                    Some(first) if now_ms > first => (state.sent as u64 * 1_000_000 / (now_ms - first)) as u32,
+                    _ => 0,
+                };
+                let overdue = state.last_sent_ms.is_none_or(|last| now_ms.saturating_sub(last) > rule.max_interval_ms as u64);
+                ComplianceEntry {
+                    rule: *rule,
+                    sent: state.sent,
+                    rate_mhz,
+                    max_gap_ms: state.max_gap_ms,
+                    late: state.late,
+                    stale: state.stale,
+                    overdue,
+                }
+            })
+            .collect();
+        ComplianceReport { entries }
+    }
+}
+
+/// 一种消息的合规统计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComplianceEntry {
+    pub rule: MessageRule,
+    pub sent: u32,       // 发送次数
+    pub rate_mhz: u32,   // 平均频率，毫赫兹
+    pub max_gap_ms: u32, // 最大发送间隔
+    pub late: u32,       // 间隔超过要求的次数
+    pub stale: u32,      // 发送时内容过期的次数
+    pub overdue: bool,   // 现在已经超过最大间隔没发
+}
+
+impl ComplianceEntry {
+    pub fn is_compliant(&self) -> bool {
+        !self.rule.required || (self.late == 0 && self.stale == 0 && !self.overdue)
+    }
+}
+
+/// 合规报告，每种消息一行
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplianceReport {
+    pub entries: Vec<ComplianceEntry>,
+}
+
+impl ComplianceReport {
+    pub fn is_compliant(&self) -> bool {
+        self.entries.iter().all(ComplianceEntry::is_compliant)
+    }
+
+    pub fn entry(&self, kind: MessageKind) -> Option<&ComplianceEntry> {
+        self.entries.iter().find(|entry| entry.rule.kind == kind)
+    }
+}
+
+impl fmt::Display for ComplianceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{:<12} {}.{:03} Hz max gap {} ms (limit {}) late {} stale {} {}",
+                entry.rule.kind.name(),
+                entry.rate_mhz / 1000,
+                entry.rate_mhz % 1000,
+                entry.max_gap_ms,
+                entry.rule.max_interval_ms,
+                entry.late,
+                entry.stale,
+                if !entry.rule.required {
+                    "optional"
+                } else if entry.is_compliant() {
+                    "ok"
+                } else {
+                    "VIOLATION"
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 模拟时钟：每个时隙让调度器挑一条消息发送
+    fn run_slots(scheduler: &mut MessageScheduler, slot_ms: u64, duration_ms: u64) {
+        let mut now_ms = 0;
+        while now_ms < duration_ms {
+            scheduler.data_updated(MessageKind::Location, now_ms);
+            let kind = scheduler.next_message().unwrap();
+            scheduler.record_sent(kind, now_ms);
+            now_ms += slot_ms;
+        }
+    }
+
+    #[test]
+    fn earliest_deadline_first() {
+        let mut scheduler = MessageScheduler::new(&DEFAULT_RULES);
+        // 都没发过时按规则顺序，位置优先
+        assert_eq!(scheduler.next_message(), Some(MessageKind::Location));
+        scheduler.record_sent(MessageKind::Location, 0);
+        assert_eq!(scheduler.next_message(), Some(MessageKind::BasicId));
+        scheduler.record_sent(MessageKind::BasicId, 250);
+        scheduler.record_sent(MessageKind::System, 500);
+        scheduler.record_sent(MessageKind::SelfId, 750);
+        // 位置的截止时间 1000 最早
+        assert_eq!(scheduler.next_deadline_ms(), Some(1000));
+        assert_eq!(scheduler.next_message(), Some(MessageKind::Location));
+    }
+
+    #[test]
+    fn quarter_second_slots_are_compliant() {
+        let mut scheduler = MessageScheduler::new(&DEFAULT_RULES);
+        run_slots(&mut scheduler, 250, 30_000);
+        let report = scheduler.report(30_000);
+        assert!(report.is_compliant(), "{}", report);
+        let location = report.entry(MessageKind::Location).unwrap();
+        assert!(location.rate_mhz >= 1000, "{}", report);
+        assert!(location.max_gap_ms <= LOCATION_MAX_INTERVAL_MS);
+        assert!(report.entry(MessageKind::BasicId).unwrap().max_gap_ms <= STATIC_MAX_INTERVAL_MS);
+        assert!(report.entry(MessageKind::SelfId).unwrap().sent > 0);
+    }
+
+    #[test]
+    fn slow_slots_are_reported() {
+        // 每 600 ms 一个时隙，四种消息轮不过来
+        let mut scheduler = MessageScheduler::new(&DEFAULT_RULES);
+        run_slots(&mut scheduler, 600, 30_000);
+        let report = scheduler.report(30_000);
+        assert!(!report.is_compliant());
+        assert!(report.to_string().contains("VIOLATION"));
+    }
+
+    #[test]
+    fn stale_location_and_overdue() {
+        let mut scheduler = MessageScheduler::new(&DEFAULT_RULES);
+        assert!(scheduler.is_stale(MessageKind::Location, 0));
+        scheduler.data_updated(MessageKind::Location, 0);
+        assert!(!scheduler.is_stale(MessageKind::Location, 1000));
+        assert!(scheduler.is_stale(MessageKind::Location, 1001));
+        scheduler.record_sent(MessageKind::Location, 1500);
+        let report = scheduler.report(1500);
+        let location = report.entry(MessageKind::Location).unwrap();
+        assert_eq!((location.sent, location.stale, location.overdue), (1, 1, false));
+        // 基本 ID 从没发过
+        assert!(report.entry(MessageKind::BasicId).unwrap().overdue);
+        assert!(!report.is_compliant());
+    }
+}
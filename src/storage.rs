@@ -0,0 +1,431 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::channel::{ChannelPlan, MAX_PLAN_CHANNELS};
+use crate::mac::MacPolicy;
+use crate::radio::PhyRate;
+use crate::config::{
+    OperationArea, StandardProfile, TransmitterConfig, MAX_CHANNEL, MAX_OPERATOR_ID_LENGTH, MAX_SELF_ID_LENGTH,
+    MAX_UAS_ID_LENGTH, MIN_CHANNEL,
+};
+
+/// 记录魔数
+pub const RECORD_MAGIC: [u8; 4] = *b"RIDC";
+/// 当前记录格式版本。字段只允许追加在末尾，旧版本记录缺少的字段取默认值
+pub const RECORD_VERSION: u8 = 6;
+/// 记录头：魔数(4) + 版本(1) + 负载长度(2)
+const HEADER_LENGTH: usize = 7;
+/// 分区里留给配置记录的空间
+pub const RECORD_CAPACITY: usize = 256;
+
+/// 配置记录错误
+#[derive(Debug, PartialEq)]
+pub enum StorageError {
+    ReadFailed,               // flash 读失败
+    Blank,                    // 分区未写过（全 0xFF）
+    BadMagic,                 // 魔数不对
+    UnsupportedVersion(u8),   // 比固件新的版本
+    Truncated(usize, usize),  // 期望长度, 实际长度
+    ChecksumMismatch(u16, u16), // 期望校验, 实际校验
+    InvalidField(&'static str), // 字段内容非法
+}
+
+/// 配置分区的读写接口，固件里由片上 flash 实现，测试里用 RamFlash
+pub trait ConfigFlash {
+    type Error: core::fmt::Debug;
+
+    /// 从分区起始偏移读数据
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error>;
+    /// 擦除存放配置的扇区
+    fn erase(&mut self) -> Result<(), Self::Error>;
+    /// 向已擦除的区域写数据
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// 内存模拟的 flash，行为与 NOR flash 一致：擦除为 0xFF，写只能把 1 变成 0
+pub struct RamFlash {
+    pub data: [u8; RECORD_CAPACITY],
+}
+
+impl Default for RamFlash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RamFlash {
+    pub const fn new() -> Self {
+        Self { data: [0xff; RECORD_CAPACITY] }
+    }
+}
+
+impl ConfigFlash for RamFlash {
+    type Error = ();
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+        let start = offset as usize;
+        let source = self.data.get(start..start + bytes.len()).ok_or(())?;
+        bytes.copy_from_slice(source);
+        Ok(())
+    }
+
+    fn erase(&mut self) -> Result<(), ()> {
+        self.data = [0xff; RECORD_CAPACITY];
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+        let start = offset as usize;
+        let target = self.data.get_mut(start..start + bytes.len()).ok_or(())?;
+        for (t, b) in target.iter_mut().zip(bytes) {
+            *t &= *b;
+        }
+        Ok(())
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u16 {
+    crc16::State::<crc16::XMODEM>::calculate(bytes)
+}
+
+// 定长字符串字段，不足补 0
+fn push_str(bytes: &mut Vec<u8>, value: &str, width: usize) {
+    let raw = value.as_bytes();
+    let length = raw.len().min(width);
+    bytes.extend_from_slice(&raw[..length]);
+    bytes.resize(bytes.len() + width - length, 0);
+}
+
+/// 把配置编码成一条完整记录（含头部和校验）
+pub fn encode_config(config: &TransmitterConfig) -> Vec<u8> {
+    let mut payload = Vec::new();
+    push_str(&mut payload, &config.uas_id, MAX_UAS_ID_LENGTH);
+    payload.push((config.id_type << 4) | (config.ua_type & 0x0F));
+    payload.extend_from_slice(&config.mac_address);
+    payload.push(config.channel);
+    payload.extend_from_slice(&config.interval_ms.to_le_bytes());
+    push_str(&mut payload, &config.operator_id, MAX_OPERATOR_ID_LENGTH);
+    payload.push(config.profile as u8);
+    let area = &config.operation_area;
+    payload.extend_from_slice(&area.operation_count.to_le_bytes());
+    payload.push(area.operation_radius);
+    payload.extend_from_slice(&area.altitude_upper.to_le_bytes());
+    payload.extend_from_slice(&area.altitude_lower.to_le_bytes());
+    // 版本 2
+    push_str(&mut payload, &config.self_id, MAX_SELF_ID_LENGTH);
+    // 版本 3
+    payload.push((config.ua_category << 4) | (config.ua_class & 0x0F));
+    // 版本 4：国家码、信道计划（类型、个数、定长信道表）、驻留时间
+    payload.extend_from_slice(&config.country);
+    let (plan_type, channels): (u8, &[u8]) = match &config.channel_plan {
+        ChannelPlan::Fixed => (0, &[]),
+        ChannelPlan::List(channels) => (1, channels),
+        ChannelPlan::AllAllowed => (2, &[]),
+    };
+    payload.push(plan_type);
+    payload.push(channels.len() as u8);
+    let mut channel_table = [0u8; MAX_PLAN_CHANNELS];
+    channel_table[..channels.len()].copy_from_slice(channels);
+    payload.extend_from_slice(&channel_table);
+    payload.extend_from_slice(&config.dwell_ms.to_le_bytes());
+    // 版本 5：发射功率、信标速率
+    payload.push(config.tx_power_dbm as u8);
+    payload.push(config.rate as u8);
+    // 版本 6：发射地址策略，不是 Fixed 时开机重新生成地址
+    payload.push(config.mac_policy as u8);
+
+    let mut bytes = Vec::with_capacity(HEADER_LENGTH + payload.len() + 2);
+    bytes.extend_from_slice(&RECORD_MAGIC);
+    bytes.push(RECORD_VERSION);
+    bytes.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    // 校验覆盖版本、长度和负载
+    let crc = checksum(&bytes[4..]);
+    bytes.extend_from_slice(&crc.to_le_bytes());
+    bytes
+}
+
+/// 按顺序读取负载字段，负载读完后的字段保持默认值（兼容旧版本）
+struct FieldReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> FieldReader<'a> {
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < length {
+            return None;
+        }
+        let (head, tail) = self.bytes.split_at(length);
+        self.bytes = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn str(&mut self, width: usize, name: &'static str) -> Result<Option<String>, StorageError> {
+        let Some(raw) = self.take(width) else {
+            return Ok(None);
+        };
+        let length = raw.iter().position(|&b| b == 0).unwrap_or(width);
+        let text = core::str::from_utf8(&raw[..length]).map_err(|_| StorageError::InvalidField(name))?;
+        Ok(Some(String::from(text)))
+    }
+}
+
+/// 解码一条记录，旧版本记录迁移到当前配置结构
+pub fn decode_config(bytes: &[u8]) -> Result<TransmitterConfig, StorageError> {
+    if bytes.len() < HEADER_LENGTH {
+        return Err(StorageError::Truncated(HEADER_LENGTH, bytes.len()));
+    }
+    if bytes[..HEADER_LENGTH].iter().all(|&b| b == 0xff) {
+        return Err(StorageError::Blank);
+    }
+    if bytes[..4] != RECORD_MAGIC {
+        return Err(StorageError::BadMagic);
+    }
+    let version = bytes[4];
+    if version == 0 || version > RECORD_VERSION {
+        return Err(StorageError::UnsupportedVersion(version));
+    }
+    let payload_length = u16::from_le_bytes([bytes[5], bytes[6]]) as usize;
+    let record_length = HEADER_LENGTH + payload_length + 2;
+    if bytes.len() < record_length {
+        return Err(StorageError::Truncated(record_length, bytes.len()));
+    }
+    let expected = checksum(&bytes[4..HEADER_LENGTH + payload_length]);
+    let stored = u16::from_le_bytes([bytes[record_length - 2], bytes[record_length - 1]]);
+    if expected != stored {
+        return Err(StorageError::ChecksumMismatch(expected, stored));
+    }
+
+    let mut reader = FieldReader { bytes: &bytes[HEADER_LENGTH..HEADER_LENGTH + payload_length] };
+    let mut config = TransmitterConfig::default();
+
+    // 版本 1 字段
+    if let Some(uas_id) = reader.str(MAX_UAS_ID_LENGTH, "uas_id")? {
+        config.set_uas_id(&uas_id).map_err(|_| StorageError::InvalidField("uas_id"))?;
+    }
+    if let Some(types) = reader.u8() {
+        config.id_type = types >> 4;
+        config.ua_type = types & 0x0F;
+    }
+    if let Some(mac) = reader.take(6) {
+        let mut address = [0u8; 6];
+        address.copy_from_slice(mac);
+        config.set_mac_address(address).map_err(|_| StorageError::InvalidField("mac_address"))?;
+    }
+    // 信道是否允许要等读到国家码再查
+    if let Some(channel) = reader.u8() {
+        if !(MIN_CHANNEL..=MAX_CHANNEL).contains(&channel) {
+            return Err(StorageError::InvalidField("channel"));
+        }
+        config.channel = channel;
+    }
+    if let Some(interval) = reader.u32() {
+        config.set_interval(interval).map_err(|_| StorageError::InvalidField("interval"))?;
+    }
+    if let Some(operator_id) = reader.str(MAX_OPERATOR_ID_LENGTH, "operator_id")? {
+        config.set_operator_id(&operator_id).map_err(|_| StorageError::InvalidField("operator_id"))?;
+    }
+    if let Some(profile) = reader.u8() {
+        config.profile = StandardProfile::from_u8(profile).ok_or(StorageError::InvalidField("profile"))?;
+    }
+    if let (Some(operation_count), Some(operation_radius), Some(altitude_upper), Some(altitude_lower)) =
+        (reader.u16(), reader.u8(), reader.u16(), reader.u16())
+    {
+        config.operation_area = OperationArea { operation_count, operation_radius, altitude_upper, altitude_lower };
+    }
+
+    // 版本 2 字段
+    if let Some(self_id) = reader.str(MAX_SELF_ID_LENGTH, "self_id")? {
+        config.set_self_id(&self_id).map_err(|_| StorageError::InvalidField("self_id"))?;
+    }
+
+    // 版本 3 字段
+    if let Some(classification) = reader.u8() {
+        config.ua_category = classification >> 4;
+        config.ua_class = classification & 0x0F;
+    }
+
+    // 版本 4 字段
+    if let (Some(country), Some(plan_type), Some(count), Some(table), Some(dwell_ms)) =
+        (reader.take(2), reader.u8(), reader.u8(), reader.take(MAX_PLAN_CHANNELS), reader.u32())
+    {
+        let channels = table.get(..count as usize).ok_or(StorageError::InvalidField("channel_plan"))?;
+        let plan = match plan_type {
+            0 => ChannelPlan::Fixed,
+            1 => ChannelPlan::List(channels.to_vec()),
+            2 => ChannelPlan::AllAllowed,
+            _ => return Err(StorageError::InvalidField("channel_plan")),
+        };
+        let country = core::str::from_utf8(country).map_err(|_| StorageError::InvalidField("country"))?;
+        config.set_country(country).map_err(|_| StorageError::InvalidField("country"))?;
+        config.set_channel_plan(plan).map_err(|_| StorageError::InvalidField("channel_plan"))?;
+        config.set_dwell(dwell_ms).map_err(|_| StorageError::InvalidField("dwell"))?;
+    }
+
+    // 版本 5 字段，发射功率要在国家码之后检查
+    if let (Some(tx_power), Some(rate)) = (reader.u8(), reader.u8()) {
+        config.set_tx_power(tx_power as i8).map_err(|_| StorageError::InvalidField("tx_power"))?;
+        config.rate = PhyRate::from_u8(rate).ok_or(StorageError::InvalidField("rate"))?;
+    }
+
+    // 版本 6 字段，旧记录里的地址都是固定地址
+    config.mac_policy = match reader.u8() {
+        Some(policy) => MacPolicy::from_u8(policy).ok_or(StorageError::InvalidField("mac_policy"))?,
+        None => MacPolicy::Fixed,
+    };
+    config.set_channel(config.channel).map_err(|_| StorageError::InvalidField("channel"))?;
+
+    Ok(config)
+}
+
+/// 启动时读取配置，分区为空或记录损坏时返回错误，由调用方回退到默认配置
+pub fn load_config<F: ConfigFlash>(flash: &mut F) -> Result<TransmitterConfig, StorageError> {
+    let mut bytes = [0u8; RECORD_CAPACITY];
+    if flash.read(0, &mut bytes).is_err() {
+        return Err(StorageError::ReadFailed);
+    }
+    decode_config(&bytes)
+}
+
+/// 擦除后写入新的配置记录
+pub fn save_config<F: ConfigFlash>(flash: &mut F, config: &TransmitterConfig) -> Result<(), F::Error> {
+    let record = encode_config(config);
+    flash.erase()?;
+    flash.write(0, &record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_DWELL_MS;
+
+    fn sample_config() -> TransmitterConfig {
+        let mut config = TransmitterConfig::default();
+        config.set_uas_id("1581F5BKD21340001234").unwrap();
+        config.id_type = 1;
+        config.ua_type = 2;
+        config.set_mac_address([0x02, 0x11, 0x22, 0x33, 0x44, 0x55]).unwrap();
+        config.set_channel(11).unwrap();
+        config.set_interval(250).unwrap();
+        config.set_operator_id("FIN87astrdge12k8").unwrap();
+        config.profile = StandardProfile::AsdStan;
+        config.operation_area = OperationArea { operation_count: 3, operation_radius: 12, altitude_upper: 1200, altitude_lower: 900 };
+        config.set_self_id("Survey flight").unwrap();
+        config.set_classification(1, 2).unwrap();
+        config.set_country("JP").unwrap();
+        config.set_channel_plan(ChannelPlan::List(alloc::vec![1, 6, 14])).unwrap();
+        config.set_dwell(400).unwrap();
+        config.set_tx_power(14).unwrap();
+        config.rate = PhyRate::Mbps11;
+        config
+    }
+
+    #[test]
+    fn round_trip_through_ram_flash() {
+        let mut flash = RamFlash::new();
+        let config = sample_config();
+        save_config(&mut flash, &config).unwrap();
+        assert_eq!(load_config(&mut flash), Ok(config));
+    }
+
+    #[test]
+    fn blank_partition_is_reported() {
+        let mut flash = RamFlash::new();
+        assert_eq!(load_config(&mut flash), Err(StorageError::Blank));
+    }
+
+    #[test]
+    fn corrupted_record_is_rejected() {
+        let mut flash = RamFlash::new();
+        save_config(&mut flash, &sample_config()).unwrap();
+        flash.data[HEADER_LENGTH + 3] ^= 0x01;
+        assert!(matches!(load_config(&mut flash), Err(StorageError::ChecksumMismatch(_, _))));
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let mut record = encode_config(&sample_config());
+        record[4] = RECORD_VERSION + 1;
+        assert_eq!(decode_config(&record), Err(StorageError::UnsupportedVersion(RECORD_VERSION + 1)));
+    }
+
+    #[test]
+    fn invalid_mac_is_rejected() {
+        for mac in [[0; 6], [0x01, 0x00, 0x5e, 0x00, 0x00, 0x01]] {
+            let mut config = sample_config();
+            config.mac_address = mac;
+            assert_eq!(decode_config(&encode_config(&config)), Err(StorageError::InvalidField("mac_address")));
+        }
+    }
+
+    #[test]
+    fn shorter_record_keeps_defaults_for_missing_fields() {
+        // 只有 UAS ID、类型、MAC、信道和间隔的记录
+        let full = encode_config(&sample_config());
+        let payload_length = 20 + 1 + 6 + 1 + 4;
+        let mut record = Vec::new();
+        record.extend_from_slice(&RECORD_MAGIC);
+        record.push(RECORD_VERSION);
+        record.extend_from_slice(&(payload_length as u16).to_le_bytes());
+        record.extend_from_slice(&full[HEADER_LENGTH..HEADER_LENGTH + payload_length]);
+        let crc = checksum(&record[4..]);
+        record.extend_from_slice(&crc.to_le_bytes());
+
+        let config = decode_config(&record).unwrap();
+        assert_eq!(config.uas_id, "1581F5BKD21340001234");
+        assert_eq!(config.channel, 11);
+        assert_eq!(config.interval_ms, 250);
+        assert_eq!(config.operator_id, "");
+        assert_eq!(config.profile, StandardProfile::default());
+        assert_eq!(config.operation_area, TransmitterConfig::default().operation_area);
+        assert_eq!(config.self_id, "");
+        assert_eq!((config.ua_category, config.ua_class), (0, 0));
+        assert_eq!(config.country, TransmitterConfig::default().country);
+        assert_eq!((config.channel_plan, config.dwell_ms), (ChannelPlan::Fixed, DEFAULT_DWELL_MS));
+        assert_eq!((config.tx_power_dbm, config.rate), (TransmitterConfig::default().tx_power_dbm, TransmitterConfig::default().rate));
+        assert_eq!(config.mac_policy, MacPolicy::Fixed);
+    }
+
+    #[test]
+    fn channel_not_allowed_in_country_is_rejected() {
+        let mut config = sample_config();
+        config.channel_plan = ChannelPlan::Fixed;
+        config.channel = 13;
+        config.country = *b"US";
+        assert_eq!(decode_config(&encode_config(&config)), Err(StorageError::InvalidField("country")));
+    }
+
+    #[test]
+    fn mac_policy_is_kept() {
+        let mut config = sample_config();
+        config.mac_policy = MacPolicy::Random;
+        assert_eq!(decode_config(&encode_config(&config)).map(|config| config.mac_policy), Ok(MacPolicy::Random));
+    }
+
+    #[test]
+    fn invalid_radio_settings_are_rejected() {
+        let mut config = sample_config();
+        config.tx_power_dbm = 25;
+        assert_eq!(decode_config(&encode_config(&config)), Err(StorageError::InvalidField("tx_power")));
+
+        let mut record = encode_config(&sample_config());
+        let rate_offset = record.len() - 4;
+        record[rate_offset] = PhyRate::ALL.len() as u8;
+        let length = record.len();
+        let crc = checksum(&record[4..length - 2]);
+        record[length - 2..].copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(decode_config(&record), Err(StorageError::InvalidField("rate")));
+    }
+}
@@ -0,0 +1,313 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::beacon::RidBeaconBuilder;
+use crate::config::{ConfigError, TransmitterConfig, MAX_UAS_ID_LENGTH};
+use crate::message::{packet_message::PacketMessage, self_id_message::SelfIdMessage};
+use crate::radio::PhyRate;
+use crate::schedule::MessageKind;
+use crate::transport::{Broadcast, RidTransport, TransportScheduler};
+
+/// 一块板子最多模拟的无人机数
+pub const MAX_SWARM_SIZE: u8 = 16;
+/// 发射占空比上限（百分比），给接收端和其他设备留出信道
+pub const AIRTIME_BUDGET_PERCENT: u32 = 25;
+/// 帧后的 FCS
+const FCS_LENGTH: usize = 4;
+/// 南北方向 1 公里约 89831 个 1e-7 度
+const LATITUDE_UNITS_PER_KM: i64 = 89_831;
+
+/// 机群错误
+#[derive(Debug, PartialEq)]
+pub enum SwarmError {
+    InvalidSize(u8),      // 数量不在 1-MAX_SWARM_SIZE 之间
+    AirtimeExceeded(u32), // 估计的发射占空比（百分比）超过预算
+    Config(ConfigError),  // 基础配置不可用，如国家码不在法规表里
+}
+
+/// 按 rate 发一帧的时长，单位微秒
+pub fn frame_airtime_us(frame_length: usize, rate: PhyRate) -> u32 {
+    rate.airtime_us(frame_length + FCS_LENGTH)
+}
+
+/// count 架无人机每个间隔各发 frames_per_interval 帧时的占空比（百分比，向上取整）
+pub fn airtime_percent(count: u8, frame_length: usize, rate: PhyRate, frames_per_interval: u32, interval_ms: u32) -> u32 {
+    let busy_us = frame_airtime_us(frame_length, rate) * frames_per_interval * count as u32;
+    (busy_us * 100).div_ceil(interval_ms.max(1) * 1000)
+}
+
+/// sin，单位百万分之一，角度单位千分之一度；用 Bhaskara I 近似，误差约千分之二，不需要浮点库
+fn sin_ppm(millidegrees: i64) -> i64 {
+    let angle = millidegrees.rem_euclid(360_000);
+    let (x, sign) = if angle < 180_000 { (angle, 1) } else { (angle - 180_000, -1) };
+    let p = x * (180_000 - x);
+    sign * 4 * p * 1_000_000 / (40_500_000_000 - p)
+}
+
+fn cos_ppm(millidegrees: i64) -> i64 {
+    sin_ppm(90_000 - millidegrees)
+}
+
+/// 模拟航迹：绕中心点匀速转圈
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trajectory {
+    pub center_latitude: i32,  // 中心纬度 (1e-7 度)
+    pub center_longitude: i32, // 中心经度 (1e-7 度)
+    pub radius_m: u32,         // 半径 (米)
+    pub period_ms: u32,        // 转一圈的时间
+    pub phase_mdeg: u32,       // 起始方位 (千分之一度，0 为正北)
+}
+
+/// 航迹上某一时刻的状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectoryPoint {
+    pub latitude: i32,      // 纬度 (1e-7 度)
+    pub longitude: i32,     // 经度 (1e-7 度)
+    pub heading_deg: u16,   // 航迹角 (度，0-359)
+    pub speed_cm_s: u32,    // 地速 (厘米/秒)
+}
+
+impl Trajectory {
+    /// 顺时针转圈，航迹角为方位加 90 度
+    pub fn position(&self, now_ms: u64) -> TrajectoryPoint {
+        let period_ms = self.period_ms.max(1) as u64;
+        let bearing = self.phase_mdeg as i64 + ((now_ms % period_ms) * 360_000 / period_ms) as i64;
+        let radius_mm = self.radius_m as i64 * 1000;
+        let north_mm = radius_mm * cos_ppm(bearing) / 1_000_000;
+        let east_mm = radius_mm * sin_ppm(bearing) / 1_000_000;
+        // 经度 1 米随纬度变长，高纬度处 cos 取个下限免得除以 0
+        let latitude_cos = cos_ppm(self.center_latitude as i64 / 10_000).max(10_000);
+        let latitude = self.center_latitude as i64 + north_mm * LATITUDE_UNITS_PER_KM / 1_000_000;
+        let longitude = self.center_longitude as i64 + east_mm * LATITUDE_UNITS_PER_KM / latitude_cos;
+        TrajectoryPoint {
+            latitude: latitude.clamp(-900_000_000, 900_000_000) as i32,
+            longitude: ((longitude + 1_800_000_000).rem_euclid(3_600_000_000) - 1_800_000_000) as i32,
+            heading_deg: ((bearing / 1000 + 90).rem_euclid(360)) as u16,
+            // 周长 2πr 米每圈
+            speed_cm_s: (628_318 * self.radius_m as u64 / period_ms) as u32,
+        }
+    }
+}
+
+/// 一架虚拟无人机：自己的身份、航迹和发送调度，计数器在调度器里各发送方式各自维护
+pub struct VirtualDrone<'a> {
+    pub config: TransmitterConfig,
+    pub trajectory: Trajectory,
+    scheduler: TransportScheduler<'a>,
+}
+
+impl VirtualDrone<'_> {
+    /// 按航迹组整包，位置报文带上航迹角和地速
+    pub fn build_packet(&self, now_ms: u64) -> PacketMessage {
+        let point = self.trajectory.position(now_ms);
+        let mut packet = PacketMessage::from_config(&self.config);
+        let position = &mut packet.position_message;
+        position.latitude = point.latitude;
+        position.longitude = point.longitude;
+        position.set_track_and_speed(point.heading_deg, point.speed_cm_s);
+        packet.system_message.timestamp = (now_ms / 1000) as u32;
+        packet
+    }
+}
+
+/// 一块板子模拟多架无人机，各自的发送时间错开
+pub struct Swarm<'a> {
+    drones: Vec<VirtualDrone<'a>>,
+}
+
+impl<'a> Swarm<'a> {
+    /// 以 base 为模板生成 count 架：UAS ID 末两位换成序号，MAC 末字节加序号，
+    /// 围绕 base 的位置转圈，半径和起始方位各不相同；占空比按每个间隔信标加 NAN 两帧估计
+    pub fn new(base: &TransmitterConfig, count: u8) -> Result<Self, SwarmError> {
+        if !(1..=MAX_SWARM_SIZE).contains(&count) {
+            return Err(SwarmError::InvalidSize(count));
+        }
+        let mut drones = Vec::new();
+        for index in 0..count {
+            let mut config = base.clone();
+            config.uas_id = swarm_uas_id(&base.uas_id, index);
+            config.mac_address[5] = base.mac_address[5].wrapping_add(index);
+            let trajectory = Trajectory {
+                center_latitude: base.latitude,
+                center_longitude: base.longitude,
+                radius_m: 50 + 20 * index as u32,
+                period_ms: 60_000,
+                phase_mdeg: index as u32 * 360_000 / count as u32,
+            };
+            drones.push(VirtualDrone { config, trajectory, scheduler: TransportScheduler::new() });
+        }
+
+        let mut frame = [0u8; 300];
+        let payload = drones[0].build_packet(0).encode_with_counter(0);
+        let frame_length = RidBeaconBuilder::new(base.mac_address)
+            .with_ssid_policy(base.profile.ssid_policy())
+            .with_supported_rates(&[base.rate.encoded()])
+            .with_country(Some(base.country_element().map_err(SwarmError::Config)?))
+            .build(&drones[0].config.uas_id, &payload, &mut frame)
+            .unwrap_or(frame.len());
+        let percent = airtime_percent(count, frame_length, base.rate, 2, base.interval_ms);
+        if percent > AIRTIME_BUDGET_PERCENT {
+            return Err(SwarmError::AirtimeExceeded(percent));
+        }
+        Ok(Self { drones })
+    }
+
+    pub fn len(&self) -> usize {
+        self.drones.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.drones.is_empty()
+    }
+
+    pub fn drones(&self) -> &[VirtualDrone<'a>] {
+        &self.drones
+    }
+
+    /// 给每架加一种发送方式，第 i 架在 offset_ms 后再错开 i/count 个间隔
+    pub fn add<T: RidTransport + 'a>(&mut self, make: impl Fn() -> T, interval_ms: u32, offset_ms: u32) {
+        let count = self.drones.len() as u32;
+        for (index, drone) in self.drones.iter_mut().enumerate() {
+            let stagger = interval_ms * index as u32 / count;
+            drone.scheduler.add(make(), interval_ms, offset_ms + stagger);
+        }
+    }
+
+    /// 跳信道后各架的信标声明新的信道
+    pub fn set_channel(&mut self, channel: u8) {
+        for drone in &mut self.drones {
+            drone.config.channel = channel;
+        }
+    }
+
+    /// 最早一架要发送的时间，没有发送方式时为 None
+    pub fn next_due_ms(&self) -> Option<u64> {
+        self.drones.iter().filter_map(|drone| drone.scheduler.next_due_ms()).min()
+    }
+
+    /// 发送所有到时间的无人机，返回发出的帧数；每架只在要发时组包
+    pub fn poll(&mut self, now_ms: u64) -> usize {
+        let mut count = 0;
+        for drone in &mut self.drones {
+            if !drone.scheduler.is_due(now_ms) {
+                continue;
+            }
+            let packet = drone.build_packet(now_ms);
+            let self_id = SelfIdMessage::new(&drone.config.self_id);
+            drone.scheduler.data_updated(MessageKind::Location, now_ms);
+            count += drone.scheduler.poll(now_ms, &Broadcast::new(&drone.config, &packet, &self_id));
+        }
+        count
+    }
+}
+
+/// 第 index 架的 UAS ID：末两位换成两位序号，太短时补在后面
+fn swarm_uas_id(base: &str, index: u8) -> String {
+    let suffix = format!("{:02}", index);
+    let keep = base.len().saturating_sub(2).min(MAX_UAS_ID_LENGTH - 2);
+    let mut id = String::from(&base[..keep]);
+    id.push_str(&suffix);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon::verify_rid_beacon;
+    use crate::transport::{RawFrameSink, WifiBeaconTransport};
+    use core::cell::RefCell;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        frames: Vec<Vec<u8>>,
+    }
+
+    impl RawFrameSink for RecordingSink {
+        type Error = ();
+
+        fn send_raw(&mut self, frame: &[u8]) -> Result<(), ()> {
+            self.frames.push(frame.to_vec());
+            Ok(())
+        }
+
+        fn now_us(&mut self) -> u64 {
+            0
+        }
+    }
+
+    #[test]
+    fn sine_approximation() {
+        for (mdeg, expected) in [(0, 0), (30_000, 500_000), (90_000, 1_000_000), (210_000, -500_000), (-90_000, -1_000_000)] {
+            assert!((sin_ppm(mdeg) - expected).abs() <= 2_000, "sin({}) = {}", mdeg, sin_ppm(mdeg));
+        }
+        assert!((cos_ppm(60_000) - 500_000).abs() <= 2_000);
+    }
+
+    #[test]
+    fn trajectory_circles_center() {
+        let trajectory = Trajectory {
+            center_latitude: 300_000_000,
+            center_longitude: 1_200_000_000,
+            radius_m: 100,
+            period_ms: 60_000,
+            phase_mdeg: 0,
+        };
+        // 起点在正北 100 米，向东飞
+        let start = trajectory.position(0);
+        assert!((start.latitude - 300_008_983).abs() <= 20, "{:?}", start);
+        assert_eq!((start.longitude, start.heading_deg), (1_200_000_000, 90));
+        // 四分之一圈后在正东，纬度 30 度处经度 1 米约 1.15 倍
+        let east = trajectory.position(15_000);
+        assert!((east.latitude - 300_000_000).abs() <= 20, "{:?}", east);
+        assert!((east.longitude - 1_200_010_373).abs() <= 30, "{:?}", east);
+        assert_eq!(east.heading_deg, 180);
+        // 2π × 100 米 / 60 秒
+        assert_eq!(start.speed_cm_s, 1047);
+        assert_eq!(trajectory.position(60_000), start);
+    }
+
+    #[test]
+    fn drones_have_own_identity_and_counter() {
+        let mut base = TransmitterConfig::default();
+        base.set_uas_id("1581F7FVC251A00CQ211").unwrap();
+        let wifi = RefCell::new(RecordingSink::default());
+        {
+            let mut swarm = Swarm::new(&base, 4).unwrap();
+            swarm.add(|| WifiBeaconTransport::new(&wifi), 1000, 0);
+            for now_ms in 0..2000 {
+                swarm.poll(now_ms);
+            }
+        }
+
+        // 同一个间隔里四架依次发，间隔错开
+        let frames = &wifi.borrow().frames;
+        assert_eq!(frames.len(), 8);
+        let decoded: Vec<PacketMessage> = frames.iter().map(|frame| verify_rid_beacon(frame).unwrap()).collect();
+        let ids: Vec<&str> = decoded[..4].iter().map(|packet| packet.base_message.uas_id.as_str()).collect();
+        assert_eq!(ids, ["1581F7FVC251A00CQ200", "1581F7FVC251A00CQ201", "1581F7FVC251A00CQ202", "1581F7FVC251A00CQ203"]);
+        // 发射地址与 BSSID 各不相同
+        assert_eq!(frames[1][10..16], [0x00, 0x80, 0x41, 0x13, 0x37, 0x43]);
+        assert_eq!(frames[1][16..22], frames[1][10..16]);
+        // 每架的计数器从 0 开始各自递增
+        let counters: Vec<u8> = decoded.iter().map(PacketMessage::message_counter).collect();
+        assert_eq!(counters, [0, 0, 0, 0, 1, 1, 1, 1]);
+        // 各自在不同的位置
+        assert_ne!(decoded[0].position_message.latitude, decoded[2].position_message.latitude);
+    }
+
+    #[test]
+    fn size_and_airtime_limits() {
+        let mut base = TransmitterConfig::default();
+        assert_eq!(Swarm::new(&base, 0).err(), Some(SwarmError::InvalidSize(0)));
+        assert_eq!(Swarm::new(&base, MAX_SWARM_SIZE + 1).err(), Some(SwarmError::InvalidSize(17)));
+        // 信标约 160 字节，1 Mbps 下一帧 1.5 毫秒，16 架每 20 毫秒发两帧远超预算
+        base.set_interval(20).unwrap();
+        assert!(matches!(Swarm::new(&base, MAX_SWARM_SIZE).err(), Some(SwarmError::AirtimeExceeded(_))));
+        assert_eq!(frame_airtime_us(100, PhyRate::Mbps1), 192 + 104 * 8);
+        assert_eq!(airtime_percent(10, 100, PhyRate::Mbps1, 2, 500), 5);
+        // 提高速率后同样的机群能放进预算
+        base.rate = PhyRate::Mbps24;
+        assert_eq!(Swarm::new(&base, MAX_SWARM_SIZE).err(), None);
+    }
+}
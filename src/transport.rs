@@ -0,0 +1,876 @@
+use core::cell::RefCell;
+use core::fmt::{Debug, Write};
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::beacon::{interval_to_tu, verify_french_beacon, verify_rid_beacon, BeaconError, RidBeaconBuilder, SEQUENCE_NUMBER_MODULO};
+use crate::ble::{extended_advertising_data, legacy_advertising_data, BleAdvertiser, BleError, LegacyRotation};
+use crate::config::{ConfigError, StandardProfile, TransmitterConfig};
+use crate::counter::MessageCounters;
+use crate::message::{
+    french_message::FrenchMessage, message::Message, packet_message::PacketMessage, self_id_message::SelfIdMessage,
+};
+use crate::nan::{NanError, NanSdfBuilder};
+use crate::schedule::{ComplianceReport, MessageKind, MessageRule, MessageScheduler, DEFAULT_RULES, LOCATION_MAX_INTERVAL_MS};
+
+/// 帧缓冲区大小，够放信标和 NAN 帧
+const FRAME_BUFFER_LENGTH: usize = 300;
+
+/// 发送方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+    WifiBeacon,  // Wi-Fi 信标厂商元素
+    WifiNan,     // Wi-Fi NAN 服务发现帧
+    BleLegacy,   // 蓝牙 4 传统广播，单条消息轮播
+    BleExtended, // 蓝牙 5 Coded PHY 扩展广播，整包
+    Serial,      // 串口输出，台架调试用
+}
+
+impl TransportKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            TransportKind::WifiBeacon => "wifi-beacon",
+            TransportKind::WifiNan => "wifi-nan",
+            TransportKind::BleLegacy => "ble-legacy",
+            TransportKind::BleExtended => "ble-extended",
+            TransportKind::Serial => "serial",
+        }
+    }
+}
+
+/// 发送错误
+#[derive(Debug, PartialEq)]
+pub enum TransportError {
+    Beacon(BeaconError), // 信标组装失败
+    Nan(NanError),       // NAN 帧组装失败
+    Ble(BleError),       // 蓝牙广播数据组装失败
+    Radio(String),       // 底层驱动错误的调试文本
+    MissingMessage(MessageKind), // 整包里没有这条消息
+    Config(ConfigError),         // 配置不能用于组帧，如国家码不在法规表里
+}
+
+impl From<BeaconError> for TransportError {
+    fn from(e: BeaconError) -> Self {
+        TransportError::Beacon(e)
+    }
+}
+
+impl From<ConfigError> for TransportError {
+    fn from(e: ConfigError) -> Self {
+        TransportError::Config(e)
+    }
+}
+
+impl From<NanError> for TransportError {
+    fn from(e: NanError) -> Self {
+        TransportError::Nan(e)
+    }
+}
+
+impl From<BleError> for TransportError {
+    fn from(e: BleError) -> Self {
+        TransportError::Ble(e)
+    }
+}
+
+fn radio_error<E: Debug>(e: E) -> TransportError {
+    TransportError::Radio(format!("{:?}", e))
+}
+
+/// 一次广播的内容：同一份整包供所有发送方式使用，各自组帧
+#[derive(Clone, Copy)]
+pub struct Broadcast<'a> {
+    pub config: &'a TransmitterConfig,
+    pub packet: &'a PacketMessage,
+    pub self_id: &'a SelfIdMessage,
+    pub message: Option<MessageKind>, // 单条消息的发送方式这次发哪条，由调度器填写
+    pub counter: u8, // 这次用的计数器：整包计数或该条消息的计数，由调度器按发送方式填写
+    pub channel: u8, // 当前信道，信标的 DSSS 元素用它；跳信道时由主循环改写
+}
+
+impl<'a> Broadcast<'a> {
+    /// 还没经过调度器的广播，调度器发送前填写 message 和 counter
+    pub fn new(config: &'a TransmitterConfig, packet: &'a PacketMessage, self_id: &'a SelfIdMessage) -> Self {
+        Self { config, packet, self_id, message: None, counter: 0, channel: config.channel }
+    }
+
+    /// 跳信道时换成当前所在的信道
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// 计数器加整包，即信标厂商元素、NAN 服务信息、扩展广播里的内容
+    pub fn payload(&self) -> Vec<u8> {
+        self.packet.encode_with_counter(self.counter)
+    }
+
+    /// 按整包的协议版本编码其中一条消息；自我描述不在整包里时用 self_id
+    pub fn encode_message(&self, kind: MessageKind) -> Result<Vec<u8>, TransportError> {
+        if let Some(bytes) = self.packet.encode_message(kind) {
+            return Ok(bytes);
+        }
+        match kind {
+            MessageKind::SelfId => {
+                let mut bytes = self.self_id.encode();
+                bytes[0] = (bytes[0] & 0xF0) | (self.packet.protocol_version() & 0x0F);
+                Ok(bytes)
+            }
+            _ => Err(TransportError::MissingMessage(kind)),
+        }
+    }
+}
+
+/// 一种 RID 发送方式
+pub trait RidTransport {
+    fn kind(&self) -> TransportKind;
+    /// 一次只能发一条消息，由调度器按截止时间挑选；否则一次发整包
+    fn single_message(&self) -> bool {
+        false
+    }
+    /// 调度器加入或改了间隔时告知，信标据此声明信标间隔
+    fn set_interval(&mut self, _interval_ms: u32) {}
+    /// 按广播内容组帧并发出
+    fn send(&mut self, broadcast: &Broadcast) -> Result<(), TransportError>;
+}
+
+/// 发原始 802.11 帧的接口，固件里是 sniffer 接口的 send_raw_frame
+pub trait RawFrameSink {
+    type Error: Debug;
+
+    fn send_raw(&mut self, frame: &[u8]) -> Result<(), Self::Error>;
+    /// 单调递增的微秒时钟，信标的 TSF 取它
+    fn now_us(&mut self) -> u64;
+}
+
+/// 信标和 NAN 共用一个 Wi-Fi 接口，蓝牙两种广播共用一个控制器，借 RefCell 分给多个发送方式
+impl<S: RawFrameSink> RawFrameSink for &RefCell<S> {
+    type Error = S::Error;
+
+    fn send_raw(&mut self, frame: &[u8]) -> Result<(), S::Error> {
+        self.borrow_mut().send_raw(frame)
+    }
+
+    fn now_us(&mut self) -> u64 {
+        self.borrow_mut().now_us()
+    }
+}
+
+impl<A: BleAdvertiser> BleAdvertiser for &RefCell<A> {
+    type Error = A::Error;
+
+    fn start_legacy(&mut self, interval_ms: u32) -> Result<(), A::Error> {
+        self.borrow_mut().start_legacy(interval_ms)
+    }
+
+    fn set_legacy_data(&mut self, data: &[u8; crate::ble::LEGACY_ADV_DATA_LENGTH]) -> Result<(), A::Error> {
+        self.borrow_mut().set_legacy_data(data)
+    }
+
+    fn start_extended(&mut self, interval_ms: u32) -> Result<(), A::Error> {
+        self.borrow_mut().start_extended(interval_ms)
+    }
+
+    fn set_extended_data(&mut self, data: &[u8]) -> Result<(), A::Error> {
+        self.borrow_mut().set_extended_data(data)
+    }
+}
+
+/// Wi-Fi 信标，声明的信标间隔跟随调度器里的发送间隔，每发出一帧序列号加一
+pub struct WifiBeaconTransport<S: RawFrameSink> {
+    pub sink: S,
+    beacon_interval: u16,
+    sequence_number: u16,
+}
+
+impl<S: RawFrameSink> WifiBeaconTransport<S> {
+    pub fn new(sink: S) -> Self {
+        Self { sink, beacon_interval: interval_to_tu(1000), sequence_number: 0 }
+    }
+}
+
+impl<S: RawFrameSink> RidTransport for WifiBeaconTransport<S> {
+    fn kind(&self) -> TransportKind {
+        TransportKind::WifiBeacon
+    }
+
+    fn set_interval(&mut self, interval_ms: u32) {
+        // 位置报文至少每秒一次，调度间隔再长，声明的信标间隔也不超过 1 秒
+        self.beacon_interval = interval_to_tu(interval_ms.min(LOCATION_MAX_INTERVAL_MS));
+    }
+
+    fn send(&mut self, broadcast: &Broadcast) -> Result<(), TransportError> {
+        let config = broadcast.config;
+        // 法国信标带由整包换算的 TLV，其余标准直接带整包
+        let french = (config.profile == StandardProfile::French).then(|| FrenchMessage::from_packet(broadcast.packet));
+        let french_payload = french.as_ref().map(Message::encode);
+        let mut frame = [0u8; FRAME_BUFFER_LENGTH];
+        let length = RidBeaconBuilder::new(config.mac_address)
+            .with_ssid_policy(config.profile.ssid_policy())
+            .with_vendor_prefix(config.profile.vendor_prefix())
+            .with_channel(broadcast.channel)
+            .with_supported_rates(&[config.rate.encoded()])
+            .with_country(Some(config.country_element()?))
+            .with_beacon_interval(self.beacon_interval)
+            .with_timestamp(self.sink.now_us())
+            .with_sequence_number(self.sequence_number)
+            .build(&config.uas_id, &french_payload.unwrap_or_else(|| broadcast.payload()), &mut frame)?;
+
+        // 调试版自检：按接收端的方式解析刚组好的信标，解码结果应与发送内容一致
+        if cfg!(debug_assertions) {
+            if let Some(french) = &french {
+                match verify_french_beacon(&frame[..length]) {
+                    Ok(decoded) => debug_assert!(decoded == *french, "French beacon round trip mismatch"),
+                    Err(e) => debug_assert!(false, "French beacon self-check failed: {:?}", e),
+                }
+            } else {
+                match verify_rid_beacon(&frame[..length]) {
+                    Ok(decoded) => debug_assert!(
+                        decoded.base_message == broadcast.packet.base_message
+                            && decoded.position_message == broadcast.packet.position_message
+                            && decoded.system_message == broadcast.packet.system_message,
+                        "RID beacon round trip mismatch"
+                    ),
+                    Err(e) => debug_assert!(false, "RID beacon self-check failed: {:?}", e),
+                }
+            }
+        }
+        self.sink.send_raw(&frame[..length]).map_err(radio_error)?;
+        self.sequence_number = (self.sequence_number + 1) % SEQUENCE_NUMBER_MODULO;
+        Ok(())
+    }
+}
+
+/// Wi-Fi NAN 服务发现帧
+pub struct NanTransport<S: RawFrameSink> {
+    pub sink: S,
+}
+
+impl<S: RawFrameSink> NanTransport<S> {
+    pub fn new(sink: S) -> Self {
+        Self { sink }
+    }
+}
+
+impl<S: RawFrameSink> RidTransport for NanTransport<S> {
+    fn kind(&self) -> TransportKind {
+        TransportKind::WifiNan
+    }
+
+    fn send(&mut self, broadcast: &Broadcast) -> Result<(), TransportError> {
+        let mut frame = [0u8; FRAME_BUFFER_LENGTH];
+        let length = NanSdfBuilder::new(broadcast.config.mac_address).build(&broadcast.payload(), &mut frame)?;
+        self.sink.send_raw(&frame[..length]).map_err(radio_error)
+    }
+}
+
+/// 蓝牙传统广播，发调度器挑的消息，没挑时按固定顺序轮播（用轮播自己的计数器）；广播须已由 start_legacy 打开
+pub struct BleLegacyTransport<A: BleAdvertiser> {
+    pub advertiser: A,
+    rotation: LegacyRotation,
+}
+
+impl<A: BleAdvertiser> BleLegacyTransport<A> {
+    pub fn new(advertiser: A) -> Self {
+        Self { advertiser, rotation: LegacyRotation::new() }
+    }
+}
+
+impl<A: BleAdvertiser> RidTransport for BleLegacyTransport<A> {
+    fn kind(&self) -> TransportKind {
+        TransportKind::BleLegacy
+    }
+
+    fn single_message(&self) -> bool {
+        true
+    }
+
+    fn send(&mut self, broadcast: &Broadcast) -> Result<(), TransportError> {
+        let data = match broadcast.message {
+            Some(kind) => legacy_advertising_data(broadcast.counter, &broadcast.encode_message(kind)?)?,
+            None => self.rotation.next(broadcast.packet, broadcast.self_id)?,
+        };
+        self.advertiser.set_legacy_data(&data).map_err(radio_error)
+    }
+}
+
+/// 蓝牙扩展广播，广播须已由 start_extended 打开
+pub struct BleExtendedTransport<A: BleAdvertiser> {
+    pub advertiser: A,
+}
+
+impl<A: BleAdvertiser> BleExtendedTransport<A> {
+    pub fn new(advertiser: A) -> Self {
+        Self { advertiser }
+    }
+}
+
+impl<A: BleAdvertiser> RidTransport for BleExtendedTransport<A> {
+    fn kind(&self) -> TransportKind {
+        TransportKind::BleExtended
+    }
+
+    fn send(&mut self, broadcast: &Broadcast) -> Result<(), TransportError> {
+        let data = extended_advertising_data(&broadcast.payload())?;
+        self.advertiser.set_extended_data(&data).map_err(radio_error)
+    }
+}
+
+/// 把整包按 "RID <十六进制>" 写到串口，没有射频也能在台架上看发送内容
+pub struct SerialTransport<W: Write> {
+    pub writer: W,
+}
+
+impl<W: Write> SerialTransport<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write> RidTransport for SerialTransport<W> {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Serial
+    }
+
+    fn send(&mut self, broadcast: &Broadcast) -> Result<(), TransportError> {
+        let mut line = String::from("RID ");
+        for byte in broadcast.payload() {
+            let _ = write!(line, "{:02x}", byte);
+        }
+        line.push('\n');
+        self.writer.write_str(&line).map_err(radio_error)
+    }
+}
+
+/// 每种发送方式的发送统计
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TransportStats {
+    pub sent: u32,   // 发送成功次数
+    pub failed: u32, // 发送失败次数
+}
+
+struct Slot<'a> {
+    transport: Box<dyn RidTransport + 'a>,
+    interval_ms: u32,
+    next_due_ms: u64,
+    last_attempt_ms: Option<u64>,
+    stats: TransportStats,
+    messages: MessageScheduler,
+    counters: MessageCounters,
+}
+
+impl Slot<'_> {
+    /// 下次到时间的时刻：自己的间隔，或整包里还没为它尝试过的必发消息截止时间
+    fn next_due_ms(&self) -> u64 {
+        let deadline = (!self.transport.single_message())
+            .then(|| self.messages.next_deadline_ms())
+            .flatten()
+            .filter(|&deadline| self.last_attempt_ms.is_none_or(|last| last < deadline));
+        deadline.map_or(self.next_due_ms, |deadline| deadline.min(self.next_due_ms))
+    }
+
+    /// 到了自己的间隔，或者整包里有必发消息到了截止时间；截止时间到了发送失败不马上重试
+    fn is_due(&self, now_ms: u64) -> bool {
+        now_ms >= self.next_due_ms
+            || (!self.transport.single_message()
+                && self.messages.next_deadline_ms().is_some_and(|deadline| {
+                    now_ms >= deadline && self.last_attempt_ms.is_none_or(|last| last < deadline)
+                }))
+    }
+}
+
+/// 多种发送方式同时工作，各按自己的间隔发送；时间由调用方传入，主机测试可用模拟时钟
+/// 每种发送方式单独按消息规则跟踪合规情况，接收端往往只听其中一种
+pub struct TransportScheduler<'a> {
+    slots: Vec<Slot<'a>>,
+    rules: Vec<MessageRule>,
+}
+
+impl Default for TransportScheduler<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> TransportScheduler<'a> {
+    pub fn new() -> Self {
+        Self { slots: Vec::new(), rules: DEFAULT_RULES.to_vec() }
+    }
+
+    /// 消息规则，对之后加入的发送方式生效
+    pub fn with_rules(mut self, rules: &[MessageRule]) -> Self {
+        self.rules = rules.to_vec();
+        self
+    }
+
+    /// 换一套消息规则（比如切换了标准），所有发送方式的合规统计重新开始
+    pub fn set_rules(&mut self, rules: &[MessageRule]) {
+        self.rules = rules.to_vec();
+        for slot in &mut self.slots {
+            slot.messages = MessageScheduler::new(&self.rules);
+        }
+    }
+
+    /// 加入一种发送方式，第一次在 offset_ms 时发送，之后每 interval_ms 一次
+    pub fn add(&mut self, mut transport: impl RidTransport + 'a, interval_ms: u32, offset_ms: u32) {
+        transport.set_interval(interval_ms);
+        self.slots.push(Slot {
+            transport: Box::new(transport),
+            interval_ms,
+            next_due_ms: offset_ms as u64,
+            last_attempt_ms: None,
+            stats: TransportStats::default(),
+            messages: MessageScheduler::new(&self.rules),
+            counters: MessageCounters::new(),
+        });
+    }
+
+    /// 修改某种发送方式的间隔，下次发送时间按新间隔重新计算
+    pub fn set_interval(&mut self, kind: TransportKind, interval_ms: u32) {
+        for slot in self.slots.iter_mut().filter(|slot| slot.transport.kind() == kind) {
+            if let Some(last_attempt_ms) = slot.last_attempt_ms {
+                slot.next_due_ms = last_attempt_ms + interval_ms as u64;
+            }
+            slot.interval_ms = interval_ms;
+            slot.transport.set_interval(interval_ms);
+        }
+    }
+
+    /// 是否有发送方式到时间了，没有就不必组包
+    pub fn is_due(&self, now_ms: u64) -> bool {
+        self.slots.iter().any(|slot| slot.is_due(now_ms))
+    }
+
+    /// 最早一种发送方式到时间的时刻，调用方睡到这时再 poll；没有发送方式时为 None
+    pub fn next_due_ms(&self) -> Option<u64> {
+        self.slots.iter().map(Slot::next_due_ms).min()
+    }
+
+    /// 消息内容更新了（比如新的定位），用于检查发送时内容是否过期
+    pub fn data_updated(&mut self, kind: MessageKind, now_ms: u64) {
+        for slot in &mut self.slots {
+            slot.messages.data_updated(kind, now_ms);
+        }
+    }
+
+    /// 发送所有到时间的发送方式，返回本次发送的个数；失败只计数，不影响其他发送方式
+    /// 计数器归各发送方式所有，发送成功才加一
+    pub fn poll(&mut self, now_ms: u64, broadcast: &Broadcast) -> usize {
+        let mut count = 0;
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_due(now_ms)) {
+            let mut broadcast = *broadcast;
+            broadcast.message = if slot.transport.single_message() { slot.messages.next_message() } else { None };
+            broadcast.counter = match broadcast.message {
+                Some(kind) => slot.counters.message(kind),
+                None => slot.counters.pack(),
+            };
+            match slot.transport.send(&broadcast) {
+                Ok(_) => {
+                    slot.stats.sent += 1;
+                    match broadcast.message {
+                        Some(kind) => {
+                            slot.counters.message_sent(kind);
+                            slot.messages.record_sent(kind, now_ms);
+                        }
+                        None => {
+                            slot.counters.pack_sent();
+                            broadcast.packet.message_kinds().into_iter().for_each(|kind| slot.messages.record_sent(kind, now_ms));
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::warn!("{} send failed: {:?}", slot.transport.kind().name(), e);
+                    slot.stats.failed += 1;
+                }
+            }
+            // 落后太多时不补发，从现在起重新计时
+            slot.last_attempt_ms = Some(now_ms);
+            slot.next_due_ms += slot.interval_ms as u64;
+            if slot.next_due_ms <= now_ms {
+                slot.next_due_ms = now_ms + slot.interval_ms as u64;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// 各发送方式的统计，按加入顺序
+    pub fn stats(&self) -> impl Iterator<Item = (TransportKind, TransportStats)> + '_ {
+        self.slots.iter().map(|slot| (slot.transport.kind(), slot.stats))
+    }
+
+    /// 各发送方式到 now_ms 为止的合规报告，按加入顺序
+    pub fn report(&self, now_ms: u64) -> impl Iterator<Item = (TransportKind, ComplianceReport)> + '_ {
+        self.slots.iter().map(move |slot| (slot.transport.kind(), slot.messages.report(now_ms)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ble::HciAdvertiser;
+    use crate::ble::HciTransport;
+    use crate::nan::verify_rid_nan;
+    use crate::radio::PhyRate;
+
+    /// 记录发出的原始帧，fail 为真时全部失败；时钟每读一次前进 1 毫秒
+    #[derive(Default)]
+    struct RecordingSink {
+        frames: Vec<Vec<u8>>,
+        fail: bool,
+        clock_us: u64,
+    }
+
+    impl RawFrameSink for RecordingSink {
+        type Error = &'static str;
+
+        fn send_raw(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+            if self.fail {
+                return Err("tx queue full");
+            }
+            self.frames.push(frame.to_vec());
+            Ok(())
+        }
+
+        fn now_us(&mut self) -> u64 {
+            self.clock_us += 1000;
+            self.clock_us
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingHci {
+        packets: Vec<Vec<u8>>,
+    }
+
+    impl HciTransport for RecordingHci {
+        type Error = ();
+
+        fn send(&mut self, packet: &[u8]) -> Result<(), ()> {
+            self.packets.push(packet.to_vec());
+            Ok(())
+        }
+    }
+
+    /// 按模拟时钟每毫秒跑一次广播循环，与固件主循环一样只在有发送方式到时间时组包
+    fn run(scheduler: &mut TransportScheduler, config: &TransmitterConfig, duration_ms: u64) {
+        let self_id = SelfIdMessage::new(&config.self_id);
+        for now_ms in 0..duration_ms {
+            if scheduler.is_due(now_ms) {
+                scheduler.data_updated(MessageKind::Location, now_ms);
+                let packet = PacketMessage::from_config(config);
+                scheduler.poll(now_ms, &Broadcast::new(config, &packet, &self_id));
+            }
+        }
+    }
+
+    #[test]
+    fn independent_intervals() {
+        let config = TransmitterConfig::default();
+        let wifi = RefCell::new(RecordingSink::default());
+        let ble = RefCell::new(HciAdvertiser::new(RecordingHci::default()));
+        let mut serial = String::new();
+        {
+            let mut scheduler = TransportScheduler::new();
+            scheduler.add(WifiBeaconTransport::new(&wifi), 1000, 0);
+            scheduler.add(NanTransport::new(&wifi), 1000, 500);
+            scheduler.add(BleLegacyTransport::new(&ble), 250, 0);
+            scheduler.add(BleExtendedTransport::new(&ble), 1000, 0);
+            scheduler.add(SerialTransport::new(&mut serial), 500, 0);
+            run(&mut scheduler, &config, 3000);
+
+            let stats: Vec<(TransportKind, TransportStats)> = scheduler.stats().collect();
+            let sent = |kind| stats.iter().find(|(k, _)| *k == kind).unwrap().1;
+            assert_eq!(sent(TransportKind::WifiBeacon), TransportStats { sent: 3, failed: 0 });
+            assert_eq!(sent(TransportKind::WifiNan), TransportStats { sent: 3, failed: 0 });
+            assert_eq!(sent(TransportKind::BleLegacy), TransportStats { sent: 12, failed: 0 });
+            assert_eq!(sent(TransportKind::BleExtended), TransportStats { sent: 3, failed: 0 });
+            assert_eq!(sent(TransportKind::Serial), TransportStats { sent: 6, failed: 0 });
+            for (kind, report) in scheduler.report(3000) {
+                assert!(report.is_compliant(), "{}\n{}", kind.name(), report);
+            }
+        }
+
+        // 信标与 NAN 交替，接收端能解出同样的内容
+        let frames = &wifi.borrow().frames;
+        assert_eq!(frames.len(), 6);
+        for (i, frame) in frames.iter().enumerate() {
+            let decoded = if i % 2 == 0 { verify_rid_beacon(frame).unwrap() } else { verify_rid_nan(frame).unwrap() };
+            assert_eq!(decoded.base_message.uas_id, config.uas_id);
+        }
+        // 蓝牙 12 次传统数据、3 次扩展数据
+        let data_commands = |opcode: u16, handle: u8| {
+            ble.borrow().transport.packets.iter().filter(|p| u16::from_le_bytes([p[1], p[2]]) == opcode && p[4] == handle).count()
+        };
+        assert_eq!(data_commands(0x2037, 0), 12);
+        assert_eq!(data_commands(0x2037, 1), 3);
+        assert_eq!(serial.lines().count(), 6);
+        assert!(serial.starts_with("RID "));
+    }
+
+    #[test]
+    fn beacon_timing_follows_schedule() {
+        let config = TransmitterConfig::default();
+        let wifi = RefCell::new(RecordingSink::default());
+        {
+            let mut scheduler = TransportScheduler::new();
+            scheduler.add(WifiBeaconTransport::new(&wifi), 500, 0);
+            run(&mut scheduler, &config, 1000);
+            scheduler.set_interval(TransportKind::WifiBeacon, 200);
+            let packet = PacketMessage::from_config(&config);
+            let self_id = SelfIdMessage::new("");
+            scheduler.poll(1000, &Broadcast::new(&config, &packet, &self_id));
+            scheduler.set_interval(TransportKind::WifiBeacon, 3000);
+            scheduler.poll(4000, &Broadcast::new(&config, &packet, &self_id));
+        }
+        // 序列控制、时间戳、信标间隔依次在偏移 22、24、32
+        let frames = &wifi.borrow().frames;
+        let field = |frame: &Vec<u8>| {
+            let sequence_number = u16::from_le_bytes([frame[22], frame[23]]) >> 4;
+            let timestamp_us = u64::from_le_bytes(frame[24..32].try_into().unwrap());
+            (sequence_number, timestamp_us, u16::from_le_bytes([frame[32], frame[33]]))
+        };
+        let fields: Vec<(u16, u64, u16)> = frames.iter().map(field).collect();
+        assert_eq!(fields, [(0, 1000, 488), (1, 2000, 488), (2, 3000, 195), (3, 4000, 977)]);
+    }
+
+    #[test]
+    fn beacon_advertises_current_channel() {
+        use ieee80211::{elements::DSSSParameterSetElement, mgmt_frame::BeaconFrame, scroll::Pread};
+
+        let config = TransmitterConfig::default();
+        let packet = PacketMessage::from_config(&config);
+        let self_id = SelfIdMessage::new("");
+        let wifi = RefCell::new(RecordingSink::default());
+        let mut beacon = WifiBeaconTransport::new(&wifi);
+        for channel in [1, 6, 11] {
+            beacon.send(&Broadcast::new(&config, &packet, &self_id).with_channel(channel)).unwrap();
+        }
+        let channels: Vec<u8> = wifi
+            .borrow()
+            .frames
+            .iter()
+            .map(|frame| {
+                let beacon = frame.pread_with::<BeaconFrame>(0, false).unwrap();
+                beacon.body.elements.get_first_element::<DSSSParameterSetElement>().unwrap().current_channel
+            })
+            .collect();
+        assert_eq!(channels, [1, 6, 11]);
+    }
+
+    #[test]
+    fn beacon_advertises_country_and_rate() {
+        use ieee80211::{elements::ElementID, mgmt_frame::BeaconFrame, scroll::Pread};
+
+        let mut config = TransmitterConfig::default();
+        config.set_country("US").unwrap();
+        config.rate = PhyRate::Mbps6;
+        let packet = PacketMessage::from_config(&config);
+        let self_id = SelfIdMessage::new("");
+        let wifi = RefCell::new(RecordingSink::default());
+        WifiBeaconTransport::new(&wifi).send(&Broadcast::new(&config, &packet, &self_id)).unwrap();
+
+        let sink = wifi.borrow();
+        let elements = sink.frames[0].pread_with::<BeaconFrame>(0, false).unwrap().body.elements;
+        // 只声明 6 Mbps 基本速率
+        assert_eq!(elements.get_first_element_raw(ElementID::Id(1)).unwrap().slice, [0x8c]);
+        // 国家码元素：US，室内外，1 信道起共 11 个，最大 30 dBm
+        assert_eq!(elements.get_first_element_raw(ElementID::Id(7)).unwrap().slice, [b'U', b'S', b' ', 1, 11, 30]);
+    }
+
+    #[test]
+    fn french_profile_changes_beacon_only() {
+        let config = TransmitterConfig { profile: StandardProfile::French, ..TransmitterConfig::default() };
+        let wifi = RefCell::new(RecordingSink::default());
+        {
+            let mut scheduler = TransportScheduler::new();
+            scheduler.add(WifiBeaconTransport::new(&wifi), 1000, 0);
+            scheduler.add(NanTransport::new(&wifi), 1000, 500);
+            run(&mut scheduler, &config, 1000);
+        }
+        // 信标带法国 TLV，NAN 仍是 ASTM 整包
+        let frames = &wifi.borrow().frames;
+        let french = verify_french_beacon(&frames[0]).unwrap();
+        assert_eq!(french.ansi_id.as_deref(), Some(config.uas_id.as_str()));
+        assert_eq!(french.latitude, config.latitude / 100);
+        assert_eq!(verify_rid_nan(&frames[1]).unwrap().base_message.uas_id, config.uas_id);
+    }
+
+    #[test]
+    fn location_deadline_overrides_slow_interval() {
+        // 配置的间隔 2.5 秒，位置报文仍须每秒一次
+        let config = TransmitterConfig::default();
+        let mut serial = String::new();
+        let mut scheduler = TransportScheduler::new();
+        scheduler.add(SerialTransport::new(&mut serial), 2500, 0);
+        run(&mut scheduler, &config, 10_000);
+
+        let (_, report) = scheduler.report(10_000).next().unwrap();
+        assert!(report.is_compliant(), "{}", report);
+        let location = report.entry(MessageKind::Location).unwrap();
+        assert_eq!((location.sent, location.max_gap_ms), (10, 1000));
+    }
+
+    #[test]
+    fn sleeping_until_next_due_matches_polling() {
+        // 固件的发送任务睡到 next_due_ms 再醒，结果应与每毫秒轮询一样
+        let config = TransmitterConfig::default();
+        let self_id = SelfIdMessage::new(&config.self_id);
+        let mut polled = String::new();
+        let mut woken = String::new();
+        let mut wake_times = Vec::new();
+        {
+            let mut reference = TransportScheduler::new();
+            reference.add(SerialTransport::new(&mut polled), 2500, 0);
+            run(&mut reference, &config, 10_000);
+
+            let mut scheduler = TransportScheduler::new();
+            scheduler.add(SerialTransport::new(&mut woken), 2500, 0);
+            let mut now_ms = 0;
+            while now_ms < 10_000 {
+                assert!(scheduler.is_due(now_ms) && (now_ms == 0 || !scheduler.is_due(now_ms - 1)));
+                scheduler.data_updated(MessageKind::Location, now_ms);
+                let packet = PacketMessage::from_config(&config);
+                scheduler.poll(now_ms, &Broadcast::new(&config, &packet, &self_id));
+                wake_times.push(now_ms);
+                now_ms = scheduler.next_due_ms().unwrap();
+            }
+        }
+        assert_eq!(woken, polled);
+        // 位置报文每秒一次的截止时间比 2.5 秒的间隔先到，每次醒来都在截止时间上
+        assert_eq!(wake_times, (0..10).map(|second| second * 1000).collect::<Vec<u64>>());
+        assert_eq!(TransportScheduler::new().next_due_ms(), None);
+    }
+
+    #[test]
+    fn legacy_slots_follow_deadlines() {
+        let config = TransmitterConfig::default();
+        let ble = RefCell::new(HciAdvertiser::new(RecordingHci::default()));
+        let mut scheduler = TransportScheduler::new();
+        scheduler.add(BleLegacyTransport::new(&ble), 250, 0);
+        run(&mut scheduler, &config, 30_000);
+
+        let (_, report) = scheduler.report(30_000).next().unwrap();
+        assert!(report.is_compliant(), "{}", report);
+        assert!(report.entry(MessageKind::SelfId).unwrap().sent > 0);
+        // 第一条传统广播数据是位置报文：HCI 头 8 字节、AD 头 5 字节、计数器之后是消息头
+        let packets = &ble.borrow().transport.packets;
+        assert_eq!(packets[0][8 + 5 + 1] >> 4, 1);
+    }
+
+    #[test]
+    fn stale_location_is_reported() {
+        // 组包时不更新定位时间，发送的位置内容都过期
+        let config = TransmitterConfig::default();
+        let packet = PacketMessage::from_config(&config);
+        let self_id = SelfIdMessage::new("");
+        let broadcast = Broadcast::new(&config, &packet, &self_id);
+        let mut serial = String::new();
+        let mut scheduler = TransportScheduler::new();
+        scheduler.add(SerialTransport::new(&mut serial), 1000, 0);
+        scheduler.data_updated(MessageKind::Location, 0);
+        for now_ms in [0, 1000, 2000] {
+            scheduler.poll(now_ms, &broadcast);
+        }
+        let (_, report) = scheduler.report(2000).next().unwrap();
+        assert_eq!(report.entry(MessageKind::Location).unwrap().stale, 1);
+        assert!(!report.is_compliant());
+    }
+
+    #[test]
+    fn failures_are_counted_per_transport() {
+        let config = TransmitterConfig::default();
+        let failing = RefCell::new(RecordingSink { fail: true, ..Default::default() });
+        let mut serial = String::new();
+        let mut scheduler = TransportScheduler::new();
+        scheduler.add(WifiBeaconTransport::new(&failing), 500, 0);
+        scheduler.add(SerialTransport::new(&mut serial), 500, 0);
+        run(&mut scheduler, &config, 2000);
+
+        let stats: Vec<(TransportKind, TransportStats)> = scheduler.stats().collect();
+        assert_eq!(
+            stats,
+            [
+                (TransportKind::WifiBeacon, TransportStats { sent: 0, failed: 4 }),
+                (TransportKind::Serial, TransportStats { sent: 4, failed: 0 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn counters_belong_to_each_transport() {
+        let config = TransmitterConfig::default();
+        let wifi = RefCell::new(RecordingSink::default());
+        let failing = RefCell::new(RecordingSink { fail: true, ..Default::default() });
+        let mut serial = String::new();
+        {
+            let mut scheduler = TransportScheduler::new();
+            scheduler.add(WifiBeaconTransport::new(&wifi), 1000, 0);
+            scheduler.add(WifiBeaconTransport::new(&failing), 1000, 0);
+            scheduler.add(SerialTransport::new(&mut serial), 500, 0);
+            run(&mut scheduler, &config, 3000);
+        }
+        // 信标每秒一包、串口每 0.5 秒一包，各自从 0 开始计数；失败的发送方式不消耗计数器
+        let counters: Vec<u8> = wifi.borrow().frames.iter().map(|frame| verify_rid_beacon(frame).unwrap().message_counter()).collect();
+        assert_eq!(counters, [0, 1, 2]);
+        assert_eq!(serial.lines().count(), 6);
+
+        failing.borrow_mut().fail = false;
+        let packet = PacketMessage::from_config(&config);
+        let self_id = SelfIdMessage::new("");
+        let mut scheduler = TransportScheduler::new();
+        scheduler.add(WifiBeaconTransport::new(&failing), 1000, 0);
+        scheduler.poll(0, &Broadcast::new(&config, &packet, &self_id));
+        assert_eq!(verify_rid_beacon(&failing.borrow().frames[0]).unwrap().message_counter(), 0);
+    }
+
+    #[test]
+    fn legacy_counters_per_message_type() {
+        let config = TransmitterConfig::default();
+        let ble = RefCell::new(HciAdvertiser::new(RecordingHci::default()));
+        let mut scheduler = TransportScheduler::new();
+        scheduler.add(BleLegacyTransport::new(&ble), 250, 0);
+        run(&mut scheduler, &config, 10_000);
+
+        // 传统广播数据：HCI 头 8 字节、AD 头 5 字节，然后是计数器和消息头；同类消息的计数器连续
+        let mut last = [None::<u8>; 16];
+        for packet in ble.borrow().transport.packets.iter().filter(|p| u16::from_le_bytes([p[1], p[2]]) == 0x2037) {
+            let (counter, message_type) = (packet[8 + 5], packet[8 + 5 + 1] >> 4);
+            let expected = last[message_type as usize].map_or(0, |c| c.wrapping_add(1));
+            assert_eq!(counter, expected, "message type {}", message_type);
+            last[message_type as usize] = Some(counter);
+        }
+        assert!(last[1].unwrap() > last[0].unwrap());
+    }
+
+    #[test]
+    fn encoding_is_pure() {
+        let packet = PacketMessage::from_config(&TransmitterConfig::default());
+        assert_eq!(packet.encode(), packet.encode());
+        assert_eq!(packet.encode_with_counter(7), packet.encode_with_counter(7));
+        assert_eq!(packet.encode_with_counter(7)[0], 7);
+    }
+
+    #[test]
+    fn interval_change_and_catch_up() {
+        let config = TransmitterConfig::default();
+        let mut serial = String::new();
+        let mut scheduler = TransportScheduler::new();
+        scheduler.add(SerialTransport::new(&mut serial), 1000, 0);
+        let packet = PacketMessage::from_config(&config);
+        let self_id = SelfIdMessage::new("");
+        let broadcast = Broadcast::new(&config, &packet, &self_id);
+
+        assert_eq!(scheduler.poll(0, &broadcast), 1);
+        scheduler.set_interval(TransportKind::Serial, 200);
+        assert!(!scheduler.is_due(199));
+        assert!(scheduler.is_due(200));
+        // 停了很久之后只发一次，不补发
+        assert_eq!(scheduler.poll(5000, &broadcast), 1);
+        assert!(!scheduler.is_due(5199));
+        assert!(scheduler.is_due(5200));
+    }
+}
@@ -0,0 +1,247 @@
+//! Host-side Remote ID decoder.
+//!
+//! rid-decode <capture>                  decode RID beacons (ASTM or French), NAN frames in a pcap/pcapng file or a firmware serial log
+//! rid-decode extract <serial.log> <out>  turn the firmware's "PCAP ..." serial lines into a pcap file
+//! rid-decode generate <out> [count]      write beacons and NAN frames built from the default config, for Wireshark
+//! rid-decode crc <capture>               try every CRC-16 variant and byte range against the packs' checksums
+
+use std::process::ExitCode;
+
+use esp32c6_test::beacon::{
+    extract_rid_payload, interval_to_tu, verify_french_beacon, verify_rid_beacon, RidBeaconBuilder, VerifyError,
+};
+use esp32c6_test::config::TransmitterConfig;
+use esp32c6_test::message::checksum::{self, ChecksumMatch};
+use esp32c6_test::message::{french_message::FrenchMessage, packet_message::PacketMessage};
+use esp32c6_test::nan::{extract_nan_payload, verify_rid_nan, NanSdfBuilder};
+use esp32c6_test::pcap::{self, RadiotapInfo};
+
+fn usage() -> ExitCode {
+    eprintln!("usage: rid-decode <capture.pcap|capture.pcapng|serial.log>");
+    eprintln!("       rid-decode extract <serial.log> <out.pcap>");
+    eprintln!("       rid-decode generate <out.pcap> [count]");
+    eprintln!("       rid-decode crc <capture.pcap|capture.pcapng|serial.log>");
+    ExitCode::FAILURE
+}
+
+/// Serial logs are text with "PCAP <hex>" lines; everything else is read as a capture file.
+fn load_capture(path: &str) -> Result<Vec<u8>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    if bytes.starts_with(pcap::SERIAL_LINE_PREFIX.as_bytes()) || bytes.windows(6).any(|w| w == b"\nPCAP ") {
+        let text = String::from_utf8_lossy(&bytes);
+        let capture: Vec<u8> = text.lines().filter_map(pcap::decode_serial_line).flatten().collect();
+        if capture.is_empty() {
+            return Err(format!("{}: no PCAP lines found", path));
+        }
+        return Ok(capture);
+    }
+    Ok(bytes)
+}
+
+/// Timestamp, transmitter address and radio info shared by every decoded line.
+fn print_frame_info(timestamp_us: u64, radiotap: Option<RadiotapInfo>, frame: &[u8]) {
+    let ta = &frame[10..16];
+    print!(
+        "{}.{:06} {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+        timestamp_us / 1_000_000,
+        timestamp_us % 1_000_000,
+        ta[0],
+        ta[1],
+        ta[2],
+        ta[3],
+        ta[4],
+        ta[5]
+    );
+    if let Some(info) = radiotap {
+        print!(" ch{}", info.channel);
+        if let Some(signal) = info.signal_dbm {
+            print!(" {}dBm", signal);
+        }
+    }
+}
+
+fn print_packet(timestamp_us: u64, radiotap: Option<RadiotapInfo>, frame: &[u8], packet: &PacketMessage) {
+    print_frame_info(timestamp_us, radiotap, frame);
+    let base = &packet.base_message;
+    let position = &packet.position_message;
+    let system = &packet.system_message;
+    println!(
+        " #{} id={} type={}/{} pos={:.7},{:.7} alt={} speed={} track={} operator={:.7},{:.7} ts={}",
+        packet.message_counter(),
+        base.uas_id,
+        base.id_type,
+        base.ua_type,
+        position.latitude as f64 * 1e-7,
+        position.longitude as f64 * 1e-7,
+        position.geometric_altitude,
+        position.ground_speed,
+        position.calculate_full_track_angle(),
+        system.latitude as f64 * 1e-7,
+        system.longitude as f64 * 1e-7,
+        system.timestamp
+    );
+}
+
+fn print_french(timestamp_us: u64, radiotap: Option<RadiotapInfo>, frame: &[u8], message: &FrenchMessage) {
+    print_frame_info(timestamp_us, radiotap, frame);
+    println!(
+        " FR v{} id={} ansi={} pos={:.5},{:.5} alt={} height={} speed={} heading={} home={:.5},{:.5}",
+        message.version,
+        message.fr_id.as_deref().unwrap_or("-"),
+        message.ansi_id.as_deref().unwrap_or("-"),
+        message.latitude as f64 * 1e-5,
+        message.longitude as f64 * 1e-5,
+        message.altitude,
+        message.height,
+        message.ground_speed,
+        message.heading,
+        message.home_latitude as f64 * 1e-5,
+        message.home_longitude as f64 * 1e-5
+    );
+}
+
+fn decode(path: &str) -> Result<(), String> {
+    let bytes = load_capture(path)?;
+    let frames = pcap::read_capture(&bytes).map_err(|e| format!("{}: {:?}", path, e))?;
+    let mut decoded = 0;
+    for captured in &frames {
+        // RID travels in beacons (vendor element) and NAN action frames (service descriptor).
+        // Beacons without the ASTM element may carry the French TLV element instead.
+        let result = match captured.frame.first() {
+            Some(0x80) => match verify_rid_beacon(captured.frame) {
+                Err(VerifyError::MissingRidElement) => match verify_french_beacon(captured.frame) {
+                    Ok(message) => {
+                        print_french(captured.timestamp_us, captured.radiotap, captured.frame, &message);
+                        decoded += 1;
+                        continue;
+                    }
+                    Err(e) => Err(format!("{:?}", e)),
+                },
+                result => result.map_err(|e| format!("{:?}", e)),
+            },
+            Some(0xd0) => verify_rid_nan(captured.frame).map_err(|e| format!("{:?}", e)),
+            _ => continue,
+        };
+        match result {
+            Ok(packet) => {
+                print_packet(captured.timestamp_us, captured.radiotap, captured.frame, &packet);
+                decoded += 1;
+            }
+            Err(e) => eprintln!("{}.{:06} not decoded: {:?}", captured.timestamp_us / 1_000_000, captured.timestamp_us % 1_000_000, e),
+        }
+    }
+    eprintln!("{} frames, {} RID frames decoded", frames.len(), decoded);
+    Ok(())
+}
+
+/// Checksum detection: packs with a CRC trailer are checked against every supported variant,
+/// then the combinations that matched every pack are reported.
+fn detect_crc(path: &str) -> Result<(), String> {
+    let bytes = load_capture(path)?;
+    let frames = pcap::read_capture(&bytes).map_err(|e| format!("{}: {:?}", path, e))?;
+    let mut checked = 0;
+    let mut common: Option<Vec<ChecksumMatch>> = None;
+    for captured in &frames {
+        let pack = match captured.frame.first() {
+            Some(0x80) => extract_rid_payload(captured.frame),
+            Some(0xd0) => extract_nan_payload(captured.frame),
+            _ => None,
+        };
+        let Some(pack) = pack.filter(|pack| checksum::checksum_offset(pack).is_some()) else {
+            continue;
+        };
+        let matches = checksum::detect(pack);
+        print_frame_info(captured.timestamp_us, captured.radiotap, captured.frame);
+        if matches.is_empty() {
+            println!(" no match");
+        } else {
+            println!(" {}", matches.iter().map(describe_match).collect::<Vec<_>>().join(", "));
+        }
+        checked += 1;
+        common = Some(match common {
+            Some(common) => common.into_iter().filter(|m| matches.contains(m)).collect(),
+            None => matches,
+        });
+    }
+    match common {
+        None => eprintln!("{} frames, no packs with a checksum", frames.len()),
+        Some(common) if common.is_empty() => eprintln!("{} packs checked, no variant matches all of them", checked),
+        Some(common) => eprintln!(
+            "{} packs checked, matching all: {}",
+            checked,
+            common.iter().map(describe_match).collect::<Vec<_>>().join(", ")
+        ),
+    }
+    Ok(())
+}
+
+fn describe_match(m: &ChecksumMatch) -> String {
+    format!("{} over {} ({})", m.algorithm.name(), m.range.name(), if m.big_endian { "big endian" } else { "little endian" })
+}
+
+fn extract(input: &str, output: &str) -> Result<(), String> {
+    let capture = load_capture(input)?;
+    let frames = pcap::read_capture(&capture).map_err(|e| format!("{}: {:?}", input, e))?;
+    std::fs::write(output, &capture).map_err(|e| format!("{}: {}", output, e))?;
+    eprintln!("wrote {} frames to {}", frames.len(), output);
+    Ok(())
+}
+
+fn generate(output: &str, count: u32) -> Result<(), String> {
+    let config = TransmitterConfig::default();
+    let country = config.country_element().map_err(|e| format!("country: {:?}", e))?;
+    let builder = RidBeaconBuilder::new(config.mac_address)
+        .with_channel(config.channel)
+        .with_supported_rates(&[config.rate.encoded()])
+        .with_country(Some(country))
+        .with_beacon_interval(interval_to_tu(config.interval_ms));
+    let nan = NanSdfBuilder::new(config.mac_address);
+    let info = RadiotapInfo { rate: config.rate.radiotap_rate(), channel: config.channel, signal_dbm: None };
+
+    let mut capture = Vec::new();
+    pcap::write_global_header(&mut capture);
+    let mut beacon = [0u8; 300];
+    for i in 0..count {
+        let mut package = PacketMessage::from_config(&config);
+        package.system_message.timestamp = i * config.interval_ms / 1000;
+        let payload = package.encode_with_counter(i as u8);
+        let timestamp_us = i as u64 * config.interval_ms as u64 * 1000;
+        // TSF follows the capture timestamps, one sequence number per beacon
+        let length = builder
+            .clone()
+            .with_timestamp(timestamp_us)
+            .with_sequence_number(i as u16)
+            .build(&config.uas_id, &payload, &mut beacon)
+            .map_err(|e| format!("failed to build beacon: {:?}", e))?;
+        pcap::write_record(&mut capture, timestamp_us, &info, &beacon[..length]);
+        // Like the firmware, a NAN frame with the same pack half an interval after each beacon
+        let length = nan.build(&payload, &mut beacon).map_err(|e| format!("failed to build NAN frame: {:?}", e))?;
+        pcap::write_record(&mut capture, timestamp_us + config.interval_ms as u64 * 500, &info, &beacon[..length]);
+    }
+    std::fs::write(output, &capture).map_err(|e| format!("{}: {}", output, e))?;
+    eprintln!("wrote {} beacons and NAN frames to {}", count, output);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["extract", input, output] => extract(input, output),
+        ["crc", path] => detect_crc(path),
+        ["generate", output] => generate(output, 10),
+        ["generate", output, count] => match count.parse() {
+            Ok(count) => generate(output, count),
+            Err(_) => return usage(),
+        },
+        [path] if !path.starts_with('-') => decode(path),
+        _ => return usage(),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}